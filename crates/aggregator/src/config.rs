@@ -0,0 +1,72 @@
+//! The API servers presented by the aggregator.
+//!
+//! ```toml
+//! # How often the upstreams are checked.
+//! check_interval_secs = 10
+//!
+//! # The groups of each upstream are presented as `<id>.<group>`.
+//! [[upstreams]]
+//! id = "lobby"
+//! url = "http://lobby.example.com:14875"
+//!
+//! [[upstreams]]
+//! id = "survival"
+//! url = "https://survival.example.com/webconsole"
+//! headers = { Authorization = "Bearer secret" }
+//! ```
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+use std::time::Duration;
+
+use anyhow::Context as _;
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    #[serde(default = "default_check_interval_secs")]
+    pub check_interval_secs: u64,
+    pub upstreams:           Vec<UpstreamConfig>,
+}
+
+fn default_check_interval_secs() -> u64 { 10 }
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct UpstreamConfig {
+    /// Prefix of the groups of this upstream.
+    pub id:      String,
+    /// Base URL of the API server.
+    pub url:     String,
+    /// Extra headers sent with every request, e.g. for an authenticating proxy.
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+}
+
+impl Config {
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let source =
+            std::fs::read_to_string(path).with_context(|| format!("read {}", path.display()))?;
+        let config: Self =
+            toml::from_str(&source).with_context(|| format!("parse {}", path.display()))?;
+
+        anyhow::ensure!(!config.upstreams.is_empty(), "no upstreams are configured");
+        anyhow::ensure!(config.check_interval_secs > 0, "check_interval_secs must be positive");
+        let mut ids = BTreeSet::new();
+        for upstream in &config.upstreams {
+            // the ID is used in group IDs, URL paths and Fluent message identifiers
+            anyhow::ensure!(
+                !upstream.id.is_empty()
+                    && upstream.id.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-'),
+                "upstream ID {:?} must only contain ASCII letters, digits and `-`",
+                upstream.id,
+            );
+            anyhow::ensure!(ids.insert(&upstream.id), "duplicate upstream ID {:?}", upstream.id);
+        }
+
+        Ok(config)
+    }
+
+    pub fn check_interval(&self) -> Duration { Duration::from_secs(self.check_interval_secs) }
+}
@@ -0,0 +1,306 @@
+//! Presents the upstreams as a single API server.
+
+use std::collections::BTreeSet;
+use std::convert::Infallible;
+use std::sync::Arc;
+
+use axum::body::Body;
+use axum::extract::State as StateExt;
+use axum::http::{header, HeaderMap, Method, Request, StatusCode};
+use axum::response::sse::{self, KeepAlive, Sse};
+use axum::response::{IntoResponse, Response};
+use axum::{Json, Router};
+use futures::stream::{self, BoxStream};
+use futures::StreamExt;
+use tokio::sync::watch;
+use tower_http::cors::CorsLayer;
+use webconsole_protocol::{
+    parse_object_query, DisplayMode, FieldDef, FieldType, Group, GroupKind, KnownFieldMetadata,
+    KnownObjectMetadata, Object, ObjectDef, Selection, WatchListEvent, WatchSingleEvent,
+};
+use webconsole_proxy::{copy_headers, error, stream_response, url_decode};
+
+use crate::locales::Merger;
+use crate::upstream::{HealthMap, Upstream};
+
+/// The group of the kinds provided by the aggregator itself.
+pub const GROUP: &str = "aggregator";
+/// The kind presenting the health of each upstream.
+pub const UPSTREAM_KIND: &str = "upstream";
+
+pub struct Aggregator {
+    pub upstreams: Vec<Arc<Upstream>>,
+    pub http:      reqwest::Client,
+    pub health:    Arc<watch::Sender<HealthMap>>,
+}
+
+pub fn router(aggregator: Arc<Aggregator>) -> Router {
+    Router::new().fallback(handle).layer(CorsLayer::permissive()).with_state(aggregator)
+}
+
+async fn handle(StateExt(aggregator): StateExt<Arc<Aggregator>>, req: Request<Body>) -> Response {
+    if req.method() != Method::GET {
+        return error(StatusCode::METHOD_NOT_ALLOWED);
+    }
+
+    let path = url_decode(req.uri().path());
+    match path.as_str() {
+        "/discovery" => return discovery(&aggregator),
+        "/locales" => return locales(&aggregator),
+        "/health" => return Json(&*aggregator.health.borrow()).into_response(),
+        _ => {}
+    }
+    if let Some(locale) = path.strip_prefix('/').and_then(|file| file.strip_suffix(".ftl")) {
+        if !locale.contains('/') {
+            return locale_file(&aggregator, locale);
+        }
+    }
+
+    let raw = req.uri().path().trim_start_matches('/');
+    let (group, rest) = raw.split_once('/').unwrap_or((raw, ""));
+    let group = url_decode(group);
+    let query = req.uri().query().unwrap_or_default();
+
+    if group == GROUP {
+        let mut parts = rest.splitn(2, '/');
+        if parts.next().map(url_decode).as_deref() != Some(UPSTREAM_KIND) {
+            return error(StatusCode::NOT_FOUND);
+        }
+        let name = parts.next().map(url_decode);
+        return upstream_kind(&aggregator, name, query);
+    }
+
+    let Some((id, group)) = group.split_once('.') else { return error(StatusCode::NOT_FOUND) };
+    let Some(upstream) = aggregator.upstreams.iter().find(|upstream| upstream.id == id) else {
+        return error(StatusCode::NOT_FOUND);
+    };
+    let query = req.uri().query().map_or(String::new(), |query| format!("?{query}"));
+    let path = format!("/{}/{rest}{query}", percent_encode(group));
+    forward(&aggregator.http, upstream, &path, req.headers()).await
+}
+
+fn percent_encode(str: &str) -> String {
+    str.bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                char::from(byte).to_string()
+            }
+            _ => format!("%{byte:02X}"),
+        })
+        .collect()
+}
+
+/// Streams the response of an upstream, which is necessary for watches.
+///
+/// `Last-Event-ID` is forwarded as is, since event IDs are only meaningful to their upstream.
+async fn forward(
+    http: &reqwest::Client,
+    upstream: &Upstream,
+    path: &str,
+    headers: &HeaderMap,
+) -> Response {
+    let mut forwarded = HeaderMap::new();
+    copy_headers(headers, &mut forwarded);
+    for (name, value) in &upstream.headers {
+        forwarded.insert(name.clone(), value.clone());
+    }
+
+    let resp = match upstream.request(http, path).headers(forwarded).send().await {
+        Ok(resp) => resp,
+        Err(err) => {
+            let message = format!("cannot reach {}: {err}", upstream.id);
+            return (StatusCode::BAD_GATEWAY, message).into_response();
+        }
+    };
+
+    stream_response(resp)
+}
+
+/// Merges the last good discovery documents of the upstreams with the aggregator kinds.
+fn discovery(aggregator: &Aggregator) -> Response {
+    let mut groups = Vec::new();
+    let mut apis = Vec::new();
+    for upstream in &aggregator.upstreams {
+        let known = upstream.known.lock().expect("poisoned");
+        let Some(discovery) = &known.discovery else { continue };
+        for (key, list) in [("groups", &mut groups), ("apis", &mut apis)] {
+            if let Some(items) = discovery.get(key).and_then(|items| items.as_array()) {
+                list.extend(items.iter().cloned());
+            }
+        }
+    }
+
+    let group = Group {
+        id:               GROUP.to_string(),
+        display_name:     "aggregator-group".to_string(),
+        display_priority: 0,
+    };
+    groups.push(serde_json::to_value(group).expect("group is serializable"));
+    apis.push(serde_json::to_value(upstream_def()).expect("object def is serializable"));
+
+    // no WebSocket transport, since watches are only proxied over SSE
+    Json(serde_json::json!({ "groups": groups, "apis": apis })).into_response()
+}
+
+fn upstream_def() -> ObjectDef {
+    let field = |path: &str, ty| FieldDef {
+        path: path.to_string(),
+        display_name: format!("aggregator-upstream-{path}"),
+        metadata: KnownFieldMetadata::default(),
+        ty,
+    };
+    let int64 = |is_timestamp| FieldType::Int64 { is_timestamp, min: None, max: None };
+    ObjectDef {
+        id:           GroupKind { group: GROUP.to_string(), kind: UPSTREAM_KIND.to_string() },
+        display_name: "aggregator-upstream-kind".to_string(),
+        metadata:     KnownObjectMetadata {
+            default_display_mode: DisplayMode::Table,
+            ..Default::default()
+        },
+        fields:       [
+            field("url", FieldType::String {}),
+            field("up", FieldType::Bool {}),
+            field("error", FieldType::Nullable { item: Box::new(FieldType::String {}) }),
+            field("checked", FieldType::Nullable { item: Box::new(int64(true)) }),
+            field("kinds", int64(false)),
+        ]
+        .into_iter()
+        .collect(),
+    }
+}
+
+/// The locales supported by any upstream, and English for the aggregator messages.
+fn locales(aggregator: &Aggregator) -> Response {
+    let mut locales = BTreeSet::from(["en".to_string()]);
+    for upstream in &aggregator.upstreams {
+        locales.extend(upstream.known.lock().expect("poisoned").locales.keys().cloned());
+    }
+    Json(locales).into_response()
+}
+
+fn locale_file(aggregator: &Aggregator, locale: &str) -> Response {
+    let mut merger = Merger::new();
+    let mut found = locale == "en";
+    for upstream in &aggregator.upstreams {
+        let known = upstream.known.lock().expect("poisoned");
+        if let Some(source) = known.locales.get(locale) {
+            merger.add(source);
+            found = true;
+        }
+        for (key, original) in &known.group_names {
+            merger.add_group_name(&upstream.id, key, original);
+        }
+    }
+    if !found {
+        return error(StatusCode::NOT_FOUND);
+    }
+    ([(header::CONTENT_TYPE, "text/plain")], merger.finish(locale)).into_response()
+}
+
+/// Serves the `aggregator/upstream` kind with the same wire format as the API server.
+///
+/// Watches cannot be resumed, so they always start with the current objects.
+fn upstream_kind(aggregator: &Aggregator, name: Option<String>, query: &str) -> Response {
+    let (is_watch, selection) = match parse_object_query(query) {
+        Ok(query) => query,
+        Err(err) => return (StatusCode::BAD_REQUEST, err.to_string()).into_response(),
+    };
+    let objects = move |health: &HealthMap| -> Vec<Object> {
+        let objects = health.iter().map(|(id, health)| select(health.object(id), &selection));
+        objects.take(selection.limit.map_or(usize::MAX, |limit| limit as usize)).collect()
+    };
+
+    let mut receiver = aggregator.health.subscribe();
+    let current = objects(&receiver.borrow_and_update());
+    let Some(name) = name else {
+        if !is_watch {
+            let mut body = String::new();
+            for object in current {
+                body.push_str(&serde_json::to_string(&object).expect("objects are serializable"));
+                body.push('\n');
+            }
+            return ([(header::CACHE_CONTROL, "no-cache")], body).into_response();
+        }
+
+        let mut initial = vec![WatchListEvent::Clear];
+        initial.extend(current.iter().map(|item| WatchListEvent::Added { item: item.clone() }));
+        let live = changes(receiver, current, move |previous, health| {
+            let current = objects(health);
+            let events = updates(previous, &current).into_iter();
+            let events = events
+                .map(|(name, field, value)| WatchListEvent::FieldUpdate { name, field, value })
+                .collect();
+            *previous = current;
+            events
+        });
+        return event_stream(stream::iter(initial).chain(live).boxed());
+    };
+
+    let Some(current) = current.into_iter().find(|object| object.name == name) else {
+        return error(StatusCode::NOT_FOUND);
+    };
+    if !is_watch {
+        return Json(current).into_response();
+    }
+
+    let initial = updates(&[], std::slice::from_ref(&current)).into_iter();
+    let initial: Vec<_> =
+        initial.map(|(_, field, value)| WatchSingleEvent::Update { field, value }).collect();
+    let live = changes(receiver, current, move |previous, health| {
+        let Some(current) = objects(health).into_iter().find(|object| object.name == name) else {
+            return Vec::new();
+        };
+        let events = updates(std::slice::from_ref(previous), std::slice::from_ref(&current));
+        let events =
+            events.into_iter().map(|(_, field, value)| WatchSingleEvent::Update { field, value });
+        let events = events.collect();
+        *previous = current;
+        events
+    });
+    event_stream(stream::iter(initial).chain(live).boxed())
+}
+
+/// Keeps the selected top-level fields, since the fields of the kind are not nested.
+fn select(mut object: Object, selection: &Selection) -> Object {
+    if let (Some(fields), Some(map)) = (&selection.fields, object.fields.as_object_mut()) {
+        map.retain(|key, _| fields.contains(key));
+    }
+    object
+}
+
+/// The object name, path and value of the fields in `current` that differ from `previous`.
+fn updates(previous: &[Object], current: &[Object]) -> Vec<(String, String, serde_json::Value)> {
+    let mut updates = Vec::new();
+    for object in current {
+        let old = previous.iter().find(|old| old.name == object.name);
+        for (key, value) in object.fields.as_object().into_iter().flatten() {
+            if old.and_then(|old| old.fields.get(key)) != Some(value) {
+                updates.push((object.name.clone(), key.clone(), value.clone()));
+            }
+        }
+    }
+    updates
+}
+
+/// Converts the changes of the health of the upstreams into events.
+fn changes<S: Send + 'static, E: Send + 'static>(
+    receiver: watch::Receiver<HealthMap>,
+    state: S,
+    diff: impl FnMut(&mut S, &HealthMap) -> Vec<E> + Send + 'static,
+) -> BoxStream<'static, E> {
+    stream::unfold((receiver, state, diff), |(mut receiver, mut state, mut diff)| async move {
+        receiver.changed().await.ok()?;
+        let events = diff(&mut state, &receiver.borrow_and_update());
+        Some((stream::iter(events), (receiver, state, diff)))
+    })
+    .flatten()
+    .boxed()
+}
+
+fn event_stream<E: serde::Serialize + Send + 'static>(events: BoxStream<'static, E>) -> Response {
+    let events = events.map(|event| {
+        let data = serde_json::to_string(&event).expect("events are serializable");
+        Ok::<_, Infallible>(sse::Event::default().data(data))
+    });
+    Sse::new(events).keep_alive(KeepAlive::default()).into_response()
+}
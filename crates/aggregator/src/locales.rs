@@ -0,0 +1,67 @@
+//! Merges the locale files of the upstreams.
+//!
+//! Clients load a locale file as a single Fluent resource, which fails on duplicate messages,
+//! but upstreams running the same plugin define the same messages.
+
+use std::collections::BTreeSet;
+
+/// The messages of the aggregator itself, appended to the English locale.
+const MESSAGES: &str = "\
+aggregator-group = Aggregator
+aggregator-upstream-kind = Upstream servers
+aggregator-upstream-url = URL
+aggregator-upstream-up = up
+aggregator-upstream-error = error
+aggregator-upstream-checked = last checked
+aggregator-upstream-kinds = kinds
+";
+
+/// Concatenates Fluent sources, keeping only the first definition of each message or term.
+pub struct Merger {
+    output:  String,
+    defined: BTreeSet<String>,
+}
+
+impl Merger {
+    pub fn new() -> Self { Self { output: String::new(), defined: BTreeSet::new() } }
+
+    pub fn add(&mut self, source: &str) {
+        // whether the lines of the current entry are kept
+        let mut keep = true;
+        for line in source.lines() {
+            if let Some(id) = entry_id(line) {
+                keep = self.defined.insert(id.to_string());
+            } else if line.starts_with('#') {
+                keep = true;
+            }
+            // other lines continue the current entry, including a `}` at the start of a line
+            if keep {
+                self.output.push_str(line);
+                self.output.push('\n');
+            }
+        }
+    }
+
+    /// Adds the display names of the qualified groups, which reference the upstream messages.
+    pub fn add_group_name(&mut self, upstream: &str, key: &str, original: &str) {
+        self.add(&format!("{key} = {upstream}: {{ {original} }}\n"));
+    }
+
+    pub fn finish(mut self, locale: &str) -> String {
+        if locale == "en" {
+            self.add(MESSAGES);
+        }
+        self.output
+    }
+}
+
+/// The identifier of the message or term that a line starts, if any.
+fn entry_id(line: &str) -> Option<&str> {
+    let (id, _) = line.split_once('=')?;
+    let id = id.trim_end();
+    let name = id.strip_prefix('-').unwrap_or(id);
+    let mut chars = name.chars();
+    let valid = chars.next().map_or(false, |c| c.is_ascii_alphabetic())
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    valid.then_some(id)
+}
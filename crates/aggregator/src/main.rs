@@ -0,0 +1,66 @@
+//! Presents several API servers as a single one.
+//!
+//! The groups of each upstream are prefixed with its ID, e.g. `lobby.main`,
+//! and the health of the upstreams is served as the `aggregator/upstream` kind.
+
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use anyhow::Context as _;
+use clap::Parser;
+use tokio::sync::watch;
+
+mod config;
+mod http;
+mod locales;
+mod upstream;
+
+#[derive(Parser)]
+#[command(version, about)]
+struct Args {
+    /// Path of the configuration file listing the upstreams.
+    config: PathBuf,
+    /// Address to serve the aggregated API on.
+    #[arg(long, default_value = "127.0.0.1:14877")]
+    listen: SocketAddr,
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+    let config = config::Config::load(&args.config)?;
+
+    let http = reqwest::Client::new();
+    let mut upstreams = Vec::new();
+    let mut health = upstream::HealthMap::new();
+    for (index, upstream) in config.upstreams.iter().enumerate() {
+        let upstream = Arc::new(upstream::Upstream::new(index, upstream)?);
+        health.insert(
+            upstream.id.clone(),
+            upstream::Health {
+                url:     upstream.url.clone(),
+                up:      false,
+                error:   None,
+                checked: None,
+                kinds:   0,
+            },
+        );
+        upstreams.push(upstream);
+    }
+
+    let health = Arc::new(watch::channel(health).0);
+    for upstream in &upstreams {
+        let task = Arc::clone(upstream).run(http.clone(), config.check_interval(), health.clone());
+        tokio::spawn(task);
+    }
+
+    let aggregator = Arc::new(http::Aggregator { upstreams, http, health });
+    eprintln!("serving {} upstreams on http://{}", config.upstreams.len(), args.listen);
+    axum::Server::try_bind(&args.listen)
+        .with_context(|| format!("bind {}", args.listen))?
+        .serve(http::router(aggregator).into_make_service())
+        .await?;
+
+    Ok(())
+}
@@ -0,0 +1,259 @@
+//! Periodic checks of the upstreams, which keep their last good discovery documents and locales.
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::Context as _;
+use axum::body::Bytes;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use serde::Serialize;
+use tokio::sync::watch;
+use webconsole_protocol::Object;
+
+use crate::config::UpstreamConfig;
+
+pub struct Upstream {
+    pub id:      String,
+    /// The position in the configuration, which identifies the upstream in synthetic keys.
+    pub index:   usize,
+    /// Base URL of the API server, without the trailing slash.
+    pub url:     String,
+    pub headers: HeaderMap,
+    pub known:   Mutex<Known>,
+}
+
+/// What was received in the last successful check.
+#[derive(Default)]
+pub struct Known {
+    /// The discovery document with qualified groups, or `None` if no check has succeeded yet.
+    pub discovery:   Option<serde_json::Value>,
+    /// The group display name keys replaced in `discovery`, by their synthetic keys.
+    pub group_names: BTreeMap<String, String>,
+    /// The locale files by locale.
+    pub locales:     BTreeMap<String, String>,
+}
+
+/// The status of an upstream, presented as an object of the `aggregator/upstream` kind.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Health {
+    pub url:     String,
+    /// Whether the last check succeeded.
+    pub up:      bool,
+    /// Why the last check failed.
+    pub error:   Option<String>,
+    /// When the last check completed, in microseconds since the Unix epoch.
+    pub checked: Option<i64>,
+    /// Number of kinds in the last good discovery document.
+    pub kinds:   i64,
+}
+
+impl Health {
+    pub fn object(&self, id: &str) -> Object {
+        let fields = serde_json::to_value(self).expect("health is serializable");
+        Object { name: id.to_string(), fields }
+    }
+}
+
+/// The health of every upstream by ID, published to watchers on every change.
+pub type HealthMap = BTreeMap<String, Health>;
+
+impl Upstream {
+    pub fn new(index: usize, config: &UpstreamConfig) -> anyhow::Result<Self> {
+        let mut headers = HeaderMap::new();
+        for (name, value) in &config.headers {
+            headers.append(
+                HeaderName::try_from(name.as_str())
+                    .with_context(|| format!("invalid header name {name:?} of {}", config.id))?,
+                HeaderValue::try_from(value.as_str())
+                    .with_context(|| format!("invalid header value of {name} of {}", config.id))?,
+            );
+        }
+        Ok(Self {
+            id: config.id.clone(),
+            index,
+            url: config.url.trim_end_matches('/').to_string(),
+            headers,
+            known: Mutex::default(),
+        })
+    }
+
+    pub fn request(&self, http: &reqwest::Client, path: &str) -> reqwest::RequestBuilder {
+        http.get(format!("{}{path}", self.url)).headers(self.headers.clone())
+    }
+
+    async fn fetch(&self, http: &reqwest::Client, path: &str) -> anyhow::Result<Bytes> {
+        let resp = self.request(http, path).send().await.with_context(|| format!("GET {path}"))?;
+        anyhow::ensure!(resp.status().is_success(), "GET {path}: {}", resp.status());
+        resp.bytes().await.with_context(|| format!("GET {path}"))
+    }
+
+    /// Fetches the discovery document and locales, and returns the number of kinds.
+    async fn check(&self, http: &reqwest::Client) -> anyhow::Result<i64> {
+        let discovery = self.fetch(http, "/discovery").await?;
+        let mut discovery: serde_json::Value =
+            serde_json::from_slice(&discovery).context("parse discovery document")?;
+        let group_names = qualify(&self.id, self.index, &mut discovery);
+
+        let locales = self.fetch(http, "/locales").await?;
+        let locales: Vec<String> = serde_json::from_slice(&locales).context("parse locales")?;
+        let mut files = BTreeMap::new();
+        for locale in locales {
+            let file = self.fetch(http, &format!("/{locale}.ftl")).await?;
+            let file = String::from_utf8(file.to_vec())
+                .with_context(|| format!("{locale}.ftl is not UTF-8"))?;
+            files.insert(locale, file);
+        }
+
+        let kinds = discovery.get("apis").and_then(|apis| apis.as_array()).map_or(0, Vec::len);
+        *self.known.lock().expect("poisoned") =
+            Known { discovery: Some(discovery), group_names, locales: files };
+        Ok(kinds as i64)
+    }
+
+    /// Checks the upstream periodically until the program exits.
+    pub async fn run(
+        self: Arc<Self>,
+        http: reqwest::Client,
+        interval: Duration,
+        health: Arc<watch::Sender<HealthMap>>,
+    ) {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            ticker.tick().await;
+            let result = tokio::time::timeout(interval, self.check(&http)).await;
+            let result = result.unwrap_or_else(|_| Err(anyhow::anyhow!("check timed out")));
+            let checked = Some(chrono::Utc::now().timestamp_micros());
+
+            health.send_modify(|map| {
+                let Some(health) = map.get_mut(&self.id) else { return };
+                match result {
+                    Ok(kinds) => {
+                        if !health.up {
+                            eprintln!("{} is up", self.id);
+                        }
+                        *health =
+                            Health { up: true, error: None, checked, kinds, ..health.clone() };
+                    }
+                    Err(err) => {
+                        // the causes of request errors repeat each other
+                        let error = match err.chain().nth(1) {
+                            Some(_) => format!("{err}: {}", err.root_cause()),
+                            None => err.to_string(),
+                        };
+                        if health.up || health.checked.is_none() {
+                            eprintln!("{} is down: {error}", self.id);
+                        }
+                        health.up = false;
+                        health.error = Some(error);
+                        health.checked = checked;
+                    }
+                }
+            });
+        }
+    }
+}
+
+/// Prefixes the groups in a discovery document with the upstream ID,
+/// and returns the original display name keys of the groups by their synthetic keys.
+///
+/// The JSON is rewritten instead of `Discovery` to keep fields unknown to this version.
+fn qualify(
+    upstream: &str,
+    index: usize,
+    discovery: &mut serde_json::Value,
+) -> BTreeMap<String, String> {
+    let mut group_names = BTreeMap::new();
+    if let Some(groups) = discovery.get_mut("groups").and_then(|groups| groups.as_array_mut()) {
+        for (group_index, group) in groups.iter_mut().enumerate() {
+            let Some(group) = group.as_object_mut() else { continue };
+            let Some(id) = group.get("id").and_then(|id| id.as_str()).map(str::to_string) else {
+                continue;
+            };
+            // IDs are not used since `a-b` + `c` and `a` + `b-c` would collide
+            let key = format!("aggregator-group-{index}-{group_index}");
+            if let Some(name) = group.get("display_name").and_then(|name| name.as_str()) {
+                group_names.insert(key.clone(), name.to_string());
+            }
+            group.insert("id".into(), format!("{upstream}.{id}").into());
+            group.insert("display_name".into(), key.into());
+        }
+    }
+    if let Some(apis) = discovery.get_mut("apis").and_then(|apis| apis.as_array_mut()) {
+        for api in apis {
+            if let Some(group) = api.get("group").and_then(|group| group.as_str()) {
+                api["group"] = format!("{upstream}.{group}").into();
+            }
+            if let Some(fields) = api.get_mut("fields") {
+                qualify_refs(upstream, fields);
+            }
+        }
+    }
+    if let Some(transports) = discovery.get_mut("transports").and_then(|t| t.as_object_mut()) {
+        // watches are only proxied over SSE
+        transports.remove("websocket");
+    }
+    group_names
+}
+
+/// Prefixes the groups referenced by `object` field types, at any depth.
+fn qualify_refs(upstream: &str, value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(map) => {
+            if map.get("type").and_then(|ty| ty.as_str()) == Some("object") {
+                if let Some(group) = map.get("group").and_then(|group| group.as_str()) {
+                    let group = format!("{upstream}.{group}");
+                    map.insert("group".into(), group.into());
+                }
+            }
+            map.values_mut().for_each(|value| qualify_refs(upstream, value));
+        }
+        serde_json::Value::Array(items) => {
+            items.iter_mut().for_each(|value| qualify_refs(upstream, value));
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn qualify_groups_and_refs() {
+        let mut discovery = json!({
+            "groups": [
+                { "id": "main", "display_name": "main-group" },
+                { "id": "main-extra", "display_name": "extra-group" },
+            ],
+            "apis": [{
+                "group": "main",
+                "kind": "player",
+                "fields": [{
+                    "path": "world",
+                    "type": { "type": "list", "item": { "type": "object", "group": "main", "kind": "world" } },
+                }],
+            }],
+            "transports": { "websocket": "/ws" },
+        });
+        let group_names = qualify("a", 1, &mut discovery);
+
+        assert_eq!(
+            group_names,
+            BTreeMap::from([
+                (String::from("aggregator-group-1-0"), String::from("main-group")),
+                ("aggregator-group-1-1".into(), "extra-group".into()),
+            ]),
+        );
+        assert_eq!(
+            discovery["groups"][1],
+            json!({ "id": "a.main-extra", "display_name": "aggregator-group-1-1" })
+        );
+        assert_eq!(discovery["apis"][0]["group"], "a.main");
+        assert_eq!(discovery["apis"][0]["fields"][0]["type"]["item"]["group"], "a.main");
+        assert_eq!(discovery["transports"], json!({}));
+    }
+}
@@ -0,0 +1,394 @@
+//! A native client for the WebConsole API.
+//!
+//! This is the counterpart of the client used by the site,
+//! speaking the same schema from [`webconsole_protocol`].
+//! Watches are returned as [`Stream`]s of protocol events,
+//! which can be applied to an [`ObjectStore`] to materialise a list.
+
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use futures::stream::BoxStream;
+use futures::{Stream, StreamExt};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use serde::de::DeserializeOwned;
+pub use webconsole_protocol as protocol;
+use webconsole_protocol::session::Record;
+pub use webconsole_protocol::store::ObjectStore;
+use webconsole_protocol::{decode_json, ndjson, object_path, sse};
+pub use webconsole_protocol::{
+    ApiError, Discovery, Object, ObjectDef, Selection, WatchListEvent, WatchSingleEvent,
+};
+
+mod record;
+use record::Exchange;
+pub use record::Recorder;
+mod retry;
+pub use retry::{Retrying, BACKOFF_BASE, BACKOFF_MAX};
+
+pub type Result<T, E = ApiError> = std::result::Result<T, E>;
+
+#[derive(Clone)]
+pub struct Client {
+    http:     reqwest::Client,
+    /// Base URL of the API server, e.g. `http://localhost:14875`.
+    pub host: String,
+    headers:  HeaderMap,
+    recorder: Option<Arc<Recorder>>,
+}
+
+impl Client {
+    pub fn new(host: impl Into<String>) -> Self {
+        Self {
+            http:     reqwest::Client::new(),
+            host:     host.into(),
+            headers:  HeaderMap::new(),
+            recorder: None,
+        }
+    }
+
+    /// Sends an extra header with every request, e.g. for an authenticating proxy.
+    pub fn with_header(mut self, name: &str, value: &str) -> Result<Self> {
+        let name = HeaderName::try_from(name).map_err(ApiError::invalid_header)?;
+        let value = HeaderValue::try_from(value).map_err(ApiError::invalid_header)?;
+        self.headers.append(name, value);
+        Ok(self)
+    }
+
+    /// Sends an extra header given in the form `Name: value`, as in the `-H` option of tools.
+    pub fn with_header_line(self, line: &str) -> Result<Self> {
+        let Some((name, value)) = line.split_once(':') else {
+            return Err(ApiError::invalid_header(format!("{line:?} is not `Name: value`")));
+        };
+        self.with_header(name.trim(), value.trim())
+    }
+
+    /// Uses a preconfigured HTTP client, e.g. with custom timeouts or TLS roots.
+    pub fn with_http_client(mut self, http: reqwest::Client) -> Self {
+        self.http = http;
+        self
+    }
+
+    /// Records every request, response and event of this client and its clones.
+    pub fn with_recorder(mut self, recorder: Recorder) -> Self {
+        self.recorder = Some(Arc::new(recorder));
+        self
+    }
+
+    fn request(&self, path: &str) -> reqwest::RequestBuilder {
+        let host = self.host.trim_end_matches('/');
+        self.http.get(format!("{host}/{path}")).headers(self.headers.clone())
+    }
+
+    fn exchange(&self, path: &str, last_event_id: Option<&str>) -> Exchange {
+        Exchange::start(self.recorder.as_ref(), path, last_event_id)
+    }
+
+    /// Sends a request and rejects unsuccessful statuses.
+    async fn send(
+        &self,
+        path: &str,
+        req: reqwest::RequestBuilder,
+        exchange: &Exchange,
+    ) -> Result<reqwest::Response> {
+        let resp = match req.send().await {
+            Ok(resp) => resp,
+            Err(err) => {
+                exchange.record(Record::Error { message: err.to_string() });
+                return Err(ApiError::network(err));
+            }
+        };
+
+        let status = resp.status().as_u16();
+        if resp.status().is_success() {
+            exchange.record(Record::Response { status, body: None });
+            return Ok(resp);
+        }
+
+        let body = resp.text().await.unwrap_or_default();
+        exchange.record(Record::Response { status, body: Some(body.clone()) });
+        Err(ApiError::from_status(path, status, body))
+    }
+
+    /// Sends a request and reads the whole body of the response.
+    async fn get_text(&self, path: &str) -> Result<String> {
+        let exchange = self.exchange(path, None);
+        let resp = self.send(path, self.request(path), &exchange).await?;
+        match resp.text().await {
+            Ok(text) => {
+                exchange.record(Record::Body { text: text.clone() });
+                Ok(text)
+            }
+            Err(err) => {
+                exchange.record(Record::Error { message: err.to_string() });
+                Err(ApiError::network(err))
+            }
+        }
+    }
+
+    async fn get_json<T: DeserializeOwned>(&self, path: &str) -> Result<T> {
+        decode_json(&self.get_text(path).await?)
+    }
+
+    /// The locales that the server provides translations for.
+    pub async fn locales(&self) -> Result<Vec<String>> { self.get_json("locales").await }
+
+    /// The Fluent source of the translations for a locale returned by [`Client::locales`].
+    pub async fn locale_file(&self, locale: &str) -> Result<String> {
+        self.get_text(&format!("{locale}.ftl")).await
+    }
+
+    pub async fn discovery(&self) -> Result<Discovery> { self.get_json("discovery").await }
+
+    /// Yields the objects of a kind as their lines arrive.
+    pub async fn list(
+        &self,
+        group: &str,
+        kind: &str,
+        selection: &Selection,
+    ) -> Result<BoxStream<'static, Result<Object>>> {
+        let path = object_path(&format!("{group}/{kind}"), false, selection);
+        let exchange = self.exchange(&path, None);
+        let resp = self.send(&path, self.request(&path), &exchange).await?;
+
+        let mut decoder = ndjson::Decoder::default();
+        let errors = exchange.clone();
+        let lines = resp
+            .bytes_stream()
+            .map(Some)
+            .chain(futures::stream::once(async { None }))
+            .map(move |chunk| match chunk {
+                Some(Ok(chunk)) => decoder.feed(&chunk).into_iter().map(Ok).collect(),
+                Some(Err(err)) => {
+                    errors.record(Record::Error { message: err.to_string() });
+                    vec![Err(ApiError::network(err))]
+                }
+                None => decoder.finish().into_iter().map(Ok).collect(),
+            })
+            .flat_map(futures::stream::iter);
+        let lines_exchange = exchange.clone();
+        let objects = lines.map(move |line| {
+            let line = line?;
+            let text = String::from_utf8_lossy(&line.bytes);
+            lines_exchange.record(Record::Line { line: text.to_string() });
+            decode_json(&text).map_err(|err| err.on_line(line.number))
+        });
+
+        Ok(exchange.until_end(objects).boxed())
+    }
+
+    pub async fn get(
+        &self,
+        group: &str,
+        kind: &str,
+        name: &str,
+        fields: Option<&[String]>,
+    ) -> Result<Object> {
+        let selection = Selection { fields: fields.map(<[_]>::to_vec), limit: None };
+        self.get_json(&object_path(&format!("{group}/{kind}/{name}"), false, &selection)).await
+    }
+
+    pub async fn watch_list(
+        &self,
+        group: &str,
+        kind: &str,
+        selection: &Selection,
+        resume: &ResumeToken,
+    ) -> Result<BoxStream<'static, Result<WatchListEvent>>> {
+        self.watch(&format!("{group}/{kind}"), selection, resume).await
+    }
+
+    pub async fn watch_single(
+        &self,
+        group: &str,
+        kind: &str,
+        name: &str,
+        fields: Option<Vec<String>>,
+        resume: &ResumeToken,
+    ) -> Result<BoxStream<'static, Result<WatchSingleEvent>>> {
+        let selection = Selection { fields, limit: None };
+        self.watch(&format!("{group}/{kind}/{name}"), &selection, resume).await
+    }
+
+    async fn watch<T: DeserializeOwned + Send + 'static>(
+        &self,
+        path: &str,
+        selection: &Selection,
+        resume: &ResumeToken,
+    ) -> Result<BoxStream<'static, Result<T>>> {
+        let path = object_path(path, true, selection);
+        let last_event_id = resume.get();
+        let exchange = self.exchange(&path, last_event_id.as_deref());
+        let mut req = self.request(&path).header("Accept", "text/event-stream");
+        if let Some(id) = last_event_id {
+            req = req.header("Last-Event-ID", id);
+        }
+        let resp = self.send(&path, req, &exchange).await?;
+
+        let resume = resume.clone();
+        let events_exchange = exchange.clone();
+        let events = sse_events(resp.bytes_stream()).filter_map(move |event| {
+            let result = match event {
+                Ok(event) => {
+                    events_exchange.record(Record::Event {
+                        event: event.event.clone(),
+                        data:  event.data.clone(),
+                        id:    event.id.clone(),
+                        retry: event.retry.map(|retry| retry.as_millis() as u64),
+                    });
+                    resume.observe(&event);
+                    (event.event == "message").then(|| decode_json(&event.data))
+                }
+                Err(err) => {
+                    events_exchange.record(Record::Error { message: err.to_string() });
+                    Some(Err(err))
+                }
+            };
+            async move { result }
+        });
+
+        Ok(exchange.until_end(events).boxed())
+    }
+}
+
+/// Parses a `text/event-stream` body.
+fn sse_events(
+    body: impl Stream<Item = reqwest::Result<bytes::Bytes>> + Send + 'static,
+) -> impl Stream<Item = Result<sse::Event>> + Send {
+    let mut parser = sse::Parser::default();
+    body.map(move |chunk| match chunk {
+        Ok(chunk) => parser.feed(&chunk).into_iter().map(Ok).collect(),
+        Err(err) => vec![Err(ApiError::network(err))],
+    })
+    .flat_map(futures::stream::iter)
+}
+
+/// Remembers the last event ID of a watch across reconnections.
+///
+/// Pass the same token when reopening a watch
+/// so that the server can replay the missed events instead of restarting it.
+/// The reconnection time requested by the server with `retry:` is kept alongside.
+#[derive(Debug, Clone, Default)]
+pub struct ResumeToken(Arc<Mutex<Resume>>);
+
+#[derive(Debug, Default)]
+struct Resume {
+    id:    Option<String>,
+    retry: Option<Duration>,
+}
+
+impl ResumeToken {
+    pub fn get(&self) -> Option<String> { self.lock().id.clone() }
+
+    pub fn set(&self, id: Option<String>) { self.lock().id = id; }
+
+    /// The reconnection time most recently requested by the server.
+    pub fn retry(&self) -> Option<Duration> { self.lock().retry }
+
+    /// Remembers the event ID and reconnection time of a server-sent event.
+    fn observe(&self, event: &sse::Event) {
+        let mut resume = self.lock();
+        if event.id.is_some() {
+            resume.id = event.resume_id().map(str::to_string);
+        }
+        if event.retry.is_some() {
+            resume.retry = event.retry;
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Resume> {
+        self.0.lock().expect("resume token lock is poisoned")
+    }
+}
+
+/// The definitions of the kinds given in the form `group/kind`, or all definitions if empty.
+pub fn select_kinds<'t>(
+    discovery: &'t Discovery,
+    kinds: &[String],
+) -> Result<Vec<&'t ObjectDef>, SelectError> {
+    if kinds.is_empty() {
+        return Ok(discovery.apis.values().collect());
+    }
+    kinds
+        .iter()
+        .map(|gk| {
+            let (group, kind) =
+                gk.split_once('/').ok_or_else(|| SelectError::Malformed(gk.clone()))?;
+            let def =
+                discovery.apis.values().find(|def| def.id.group == group && def.id.kind == kind);
+            def.ok_or_else(|| SelectError::Unknown(gk.clone()))
+        })
+        .collect()
+}
+
+/// Why [`select_kinds`] failed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SelectError {
+    /// The kind is not in the form `group/kind`.
+    Malformed(String),
+    /// The server does not provide the kind.
+    Unknown(String),
+}
+
+impl fmt::Display for SelectError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Malformed(gk) => write!(f, "kind {gk:?} is not `group/kind`"),
+            Self::Unknown(gk) => write!(f, "{gk} is not in discovery"),
+        }
+    }
+}
+
+impl std::error::Error for SelectError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn discovery() -> Discovery {
+        let kind = |kind: &str| serde_json::json!({ "group": "main", "kind": kind, "display_name": kind, "fields": [] });
+        serde_json::from_value(serde_json::json!({
+            "groups": [],
+            "apis": [kind("player"), kind("world")],
+        }))
+        .unwrap()
+    }
+
+    fn kinds(kinds: &[&str]) -> Vec<String> { kinds.iter().map(|gk| gk.to_string()).collect() }
+
+    #[test]
+    fn select_kinds_in_order() {
+        let discovery = discovery();
+        let all: Vec<_> = select_kinds(&discovery, &[]).unwrap();
+        assert_eq!(all.len(), 2);
+
+        let defs = select_kinds(&discovery, &kinds(&["main/world", "main/player"])).unwrap();
+        let names: Vec<_> = defs.iter().map(|def| def.id.kind.as_str()).collect();
+        assert_eq!(names, ["world", "player"]);
+    }
+
+    #[test]
+    fn select_kinds_errors() {
+        let discovery = discovery();
+        assert_eq!(
+            select_kinds(&discovery, &kinds(&["player"])).unwrap_err(),
+            SelectError::Malformed("player".into()),
+        );
+        assert_eq!(
+            select_kinds(&discovery, &kinds(&["main/block"])).unwrap_err(),
+            SelectError::Unknown("main/block".into()),
+        );
+    }
+
+    #[test]
+    fn header_lines() {
+        let client = Client::new("http://localhost").with_header_line("X-Token:  a:b ").unwrap();
+        assert_eq!(client.headers["x-token"], "a:b");
+        for line in ["X-Token", "X Token: a", "X-Token: a\nb"] {
+            let err = Client::new("http://localhost").with_header_line(line).err().unwrap();
+            assert!(matches!(err, ApiError::InvalidHeader { .. }), "{line:?}");
+            assert!(!err.is_transient());
+        }
+    }
+}
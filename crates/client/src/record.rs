@@ -0,0 +1,72 @@
+//! Records the traffic of a [`Client`](crate::Client) into a session file.
+//!
+//! See [`webconsole_protocol::session`] for the file format.
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use futures::{Stream, StreamExt};
+use webconsole_protocol::session::{Entry, Record};
+
+pub struct Recorder {
+    out:   Mutex<Box<dyn Write + Send>>,
+    start: Instant,
+    next:  AtomicU64,
+}
+
+impl Recorder {
+    /// Writes the session to `out`, flushing after every entry.
+    pub fn new(out: impl Write + Send + 'static) -> Self {
+        Self { out: Mutex::new(Box::new(out)), start: Instant::now(), next: AtomicU64::new(1) }
+    }
+
+    /// Writes the session to a new file at `path`.
+    pub fn create(path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(Self::new(BufWriter::new(File::create(path)?)))
+    }
+
+    fn write(&self, entry: &Entry) {
+        let line = serde_json::to_string(entry).expect("entries are serializable");
+        let mut out = self.out.lock().expect("recorder lock is poisoned");
+        // a failing recorder must not break the client
+        _ = writeln!(out, "{line}").and_then(|()| out.flush());
+    }
+}
+
+/// The records of one request, which are dropped if the client is not recording.
+#[derive(Clone, Default)]
+pub(crate) struct Exchange(Option<(Arc<Recorder>, u64)>);
+
+impl Exchange {
+    pub(crate) fn start(
+        recorder: Option<&Arc<Recorder>>,
+        path: &str,
+        last_event_id: Option<&str>,
+    ) -> Self {
+        let Some(recorder) = recorder else { return Self(None) };
+        let id = recorder.next.fetch_add(1, Ordering::Relaxed);
+        let exchange = Self(Some((recorder.clone(), id)));
+        exchange.record(Record::Request {
+            path:          path.to_string(),
+            last_event_id: last_event_id.map(str::to_string),
+        });
+        exchange
+    }
+
+    pub(crate) fn record(&self, record: Record) {
+        if let Some((recorder, exchange)) = &self.0 {
+            let at = recorder.start.elapsed().as_micros() as f64 / 1000.;
+            recorder.write(&Entry { at, exchange: *exchange, record });
+        }
+    }
+
+    /// Records the end of `stream` when it is exhausted, but not if it is dropped before.
+    pub(crate) fn until_end<S: Stream>(self, stream: S) -> impl Stream<Item = S::Item> {
+        let end = futures::stream::once(async move { self.record(Record::End) });
+        stream.chain(end.filter_map(|()| async { None }))
+    }
+}
@@ -0,0 +1,146 @@
+//! Watches that are reopened whenever they fail, for long-running clients.
+
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
+
+use futures::stream::{self, BoxStream};
+use futures::StreamExt;
+
+use crate::{ApiError, Client, ResumeToken, Selection, WatchListEvent};
+
+/// The delay before reopening a watch after its first failure.
+pub const BACKOFF_BASE: Duration = Duration::from_secs(1);
+/// The longest delay between two attempts to reopen a watch.
+pub const BACKOFF_MAX: Duration = Duration::from_secs(30);
+
+/// An item of a watch returned by [`Client::watch_list_retrying`].
+#[derive(Debug)]
+pub enum Retrying<T> {
+    Event(T),
+    /// The watch failed. Transient errors are retried with an exponential
+    /// backoff; after any other error the stream ends.
+    Failed(ApiError),
+}
+
+struct Watch {
+    client:    Client,
+    group:     String,
+    kind:      String,
+    selection: Selection,
+    resume:    ResumeToken,
+    events:    Option<BoxStream<'static, Result<WatchListEvent, ApiError>>>,
+    /// Failures since the last event, which scale the next delay.
+    attempts:  u32,
+    /// Whether the watch failed permanently and the stream must end.
+    done:      bool,
+}
+
+impl Client {
+    /// Watches a list until the stream is dropped, reopening the watch whenever it fails
+    /// with a transient error. Any other error ends the stream after it is yielded.
+    ///
+    /// Reopened watches resume from the last event if the server still has it,
+    /// otherwise they start with [`WatchListEvent::Clear`] again.
+    pub fn watch_list_retrying(
+        &self,
+        group: &str,
+        kind: &str,
+        selection: Selection,
+    ) -> BoxStream<'static, Retrying<WatchListEvent>> {
+        let watch = Watch {
+            client: self.clone(),
+            group: group.to_string(),
+            kind: kind.to_string(),
+            selection,
+            resume: ResumeToken::default(),
+            events: None,
+            attempts: 0,
+            done: false,
+        };
+        stream::unfold(watch, |mut watch| async move {
+            if watch.done {
+                return None;
+            }
+            let item = watch.next().await;
+            Some((item, watch))
+        })
+        .boxed()
+    }
+}
+
+impl Watch {
+    async fn next(&mut self) -> Retrying<WatchListEvent> {
+        let events = match &mut self.events {
+            Some(events) => events,
+            None => {
+                if self.attempts > 0 {
+                    let base = self.resume.retry().unwrap_or(BACKOFF_BASE);
+                    tokio::time::sleep(backoff(base, self.attempts - 1, jitter())).await;
+                }
+                let opened = self
+                    .client
+                    .watch_list(&self.group, &self.kind, &self.selection, &self.resume)
+                    .await;
+                match opened {
+                    Ok(events) => self.events.insert(events),
+                    Err(err) => return self.fail(err),
+                }
+            }
+        };
+
+        match events.next().await {
+            Some(Ok(event)) => {
+                self.attempts = 0;
+                Retrying::Event(event)
+            }
+            Some(Err(err)) => self.fail(err),
+            None => self.fail(ApiError::network("closed by server")),
+        }
+    }
+
+    fn fail(&mut self, err: ApiError) -> Retrying<WatchListEvent> {
+        self.events = None;
+        self.attempts = self.attempts.saturating_add(1);
+        self.done = !err.is_transient();
+        Retrying::Failed(err)
+    }
+}
+
+/// The delay before the next attempt after `attempts` earlier retries, with
+/// `jitter` in `[0, 1)` spreading it over the upper half of the window.
+///
+/// `base` is the reconnection time requested by the server with `retry:`, or [`BACKOFF_BASE`].
+fn backoff(base: Duration, attempts: u32, jitter: f64) -> Duration {
+    let window = base.saturating_mul(1 << attempts.min(16)).min(BACKOFF_MAX);
+    window.mul_f64(0.5 + jitter * 0.5)
+}
+
+/// A random number in `[0, 1)`, good enough to keep clients from reconnecting in lockstep.
+fn jitter() -> f64 {
+    let bits = RandomState::new().build_hasher().finish();
+    (bits >> 11) as f64 / (1u64 << 53) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_grows_and_is_capped() {
+        assert_eq!(backoff(BACKOFF_BASE, 0, 1.), BACKOFF_BASE);
+        assert_eq!(backoff(BACKOFF_BASE, 0, 0.), BACKOFF_BASE / 2);
+        assert_eq!(backoff(BACKOFF_BASE, 3, 1.), BACKOFF_BASE * 8);
+        assert_eq!(backoff(BACKOFF_BASE, 5, 1.), BACKOFF_MAX);
+        assert_eq!(backoff(BACKOFF_BASE, u32::MAX, 0.), BACKOFF_MAX / 2);
+        // a server `retry:` replaces the base
+        assert_eq!(backoff(Duration::from_secs(5), 1, 1.), Duration::from_secs(10));
+    }
+
+    #[test]
+    fn jitter_is_in_range() {
+        for _ in 0..100 {
+            assert!((0. ..1.).contains(&jitter()));
+        }
+    }
+}
@@ -0,0 +1,135 @@
+//! Generates typed Rust structs, JSON Schemas and OpenAPI documents from a discovery document.
+//!
+//! Each group becomes a module with a struct for each kind,
+//! so that objects can be deserialized instead of read with JSON paths:
+//!
+//! ```ignore
+//! let player: main::Player = serde_json::from_value(serde_json::to_value(&object)?)?;
+//! ```
+//!
+//! Enum fields become enums, nullable fields `Option`s, lists `Vec`s,
+//! compound fields and dotted paths nested structs,
+//! and references to objects newtypes such as `main::WorldRef`.
+//! The generated code only depends on `serde`.
+//!
+//! In a build script, generate the module from a saved discovery document
+//! and include it with `include!(concat!(env!("OUT_DIR"), "/api.rs"))`:
+//!
+//! ```no_run
+//! let out = std::path::Path::new(&std::env::var("OUT_DIR").unwrap()).join("api.rs");
+//! println!("cargo:rerun-if-changed=discovery.json");
+//! webconsole_codegen::Builder::from_file("discovery.json").write_to(&out).unwrap();
+//! ```
+//!
+//! For other languages, [`json_schema`] describes the objects of a kind
+//! and [`openapi`] the list, get and watch endpoints of the kinds.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context as _;
+use webconsole_client::protocol::Discovery;
+use webconsole_client::{select_kinds, Client};
+
+mod rust;
+pub use rust::generate;
+mod schema;
+pub use schema::{json_schema, openapi};
+
+enum Source {
+    /// The URL and the header lines in the form `Name: value`.
+    Server {
+        url:     String,
+        headers: Vec<String>,
+    },
+    File(PathBuf),
+}
+
+/// Loads a discovery document and generates the code for some or all of its kinds.
+pub struct Builder {
+    source: Source,
+    kinds:  Vec<String>,
+}
+
+impl Builder {
+    /// Fetches the discovery document from an API server.
+    pub fn from_server(url: impl Into<String>) -> Self {
+        Self { source: Source::Server { url: url.into(), headers: Vec::new() }, kinds: Vec::new() }
+    }
+
+    /// Reads a discovery document saved as JSON.
+    pub fn from_file(path: impl Into<PathBuf>) -> Self {
+        Self { source: Source::File(path.into()), kinds: Vec::new() }
+    }
+
+    /// Adds a header to the discovery request, e.g. for an authenticating proxy.
+    ///
+    /// Ignored when reading from a file.
+    pub fn header(self, name: &str, value: &str) -> Self {
+        self.header_line(&format!("{name}: {value}"))
+    }
+
+    /// Adds a header given in the form `Name: value`, as in the `-H` option.
+    pub fn header_line(mut self, line: &str) -> Self {
+        if let Source::Server { headers, .. } = &mut self.source {
+            headers.push(line.to_string());
+        }
+        self
+    }
+
+    /// Only generates this kind, in the form `group/kind`.
+    /// All kinds are generated if none is specified.
+    pub fn kind(mut self, kind: impl Into<String>) -> Self {
+        self.kinds.push(kind.into());
+        self
+    }
+
+    pub fn discovery(&self) -> anyhow::Result<Discovery> {
+        match &self.source {
+            Source::Server { url, headers } => {
+                let mut client = Client::new(url);
+                for header in headers {
+                    client = client.with_header_line(header)?;
+                }
+                // build scripts are synchronous
+                let runtime = tokio::runtime::Builder::new_current_thread()
+                    .enable_all()
+                    .build()
+                    .context("start runtime")?;
+                runtime.block_on(client.discovery()).context("fetch discovery")
+            }
+            Source::File(path) => {
+                let json = std::fs::read_to_string(path)
+                    .with_context(|| format!("read {}", path.display()))?;
+                serde_json::from_str(&json).with_context(|| format!("parse {}", path.display()))
+            }
+        }
+    }
+
+    pub fn generate(&self) -> anyhow::Result<String> {
+        let discovery = self.discovery()?;
+        let defs = select_kinds(&discovery, &self.kinds)?;
+        Ok(generate(&discovery, &defs))
+    }
+
+    /// The JSON Schemas of the kinds, by `group/kind`.
+    pub fn json_schemas(&self) -> anyhow::Result<BTreeMap<String, serde_json::Value>> {
+        let discovery = self.discovery()?;
+        let defs = select_kinds(&discovery, &self.kinds)?;
+        let schemas = defs
+            .into_iter()
+            .map(|def| (format!("{}/{}", def.id.group, def.id.kind), json_schema(def)));
+        Ok(schemas.collect())
+    }
+
+    pub fn openapi(&self) -> anyhow::Result<serde_json::Value> {
+        let discovery = self.discovery()?;
+        let defs = select_kinds(&discovery, &self.kinds)?;
+        Ok(openapi(&discovery, &defs))
+    }
+
+    pub fn write_to(&self, path: &Path) -> anyhow::Result<()> {
+        let code = self.generate()?;
+        std::fs::write(path, code).with_context(|| format!("write {}", path.display()))
+    }
+}
@@ -0,0 +1,90 @@
+//! Generates typed Rust structs, JSON Schemas or an OpenAPI document
+//! from the discovery document of an API server.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context as _;
+use clap::Parser;
+use webconsole_codegen::Builder;
+
+#[derive(Parser)]
+#[command(version, about)]
+struct Args {
+    /// Base URL of the API server.
+    #[arg(long, env = "WEBCONSOLE_SERVER", default_value = "http://localhost:14875")]
+    server:    String,
+    /// Extra request header in the form `Name: value`, e.g. for an authenticating proxy.
+    #[arg(long = "header", short = 'H')]
+    headers:   Vec<String>,
+    /// Read the discovery document from a JSON file instead of the server.
+    #[arg(long, conflicts_with_all = ["server", "headers"])]
+    discovery: Option<PathBuf>,
+    /// Only generate these kinds, in the form `group/kind`. All kinds are generated if unspecified.
+    #[arg(long = "kind")]
+    kinds:     Vec<String>,
+    /// What to generate.
+    #[arg(long, value_enum, default_value_t = Format::Rust)]
+    format:    Format,
+    /// File to write the output to, instead of stdout.
+    ///
+    /// For JSON Schemas, a directory where each kind is written to `<group>/<kind>.schema.json`.
+    /// On stdout, the schemas are printed as an object keyed by `group/kind`.
+    #[arg(long, short)]
+    output:    Option<PathBuf>,
+}
+
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum Format {
+    /// A Rust module with a struct for each kind.
+    Rust,
+    /// A JSON Schema for each kind.
+    JsonSchema,
+    /// An OpenAPI document of the list, get and watch endpoints.
+    Openapi,
+}
+
+fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+
+    let mut builder = match args.discovery {
+        Some(path) => Builder::from_file(path),
+        None => Builder::from_server(args.server),
+    };
+    for header in &args.headers {
+        builder = builder.header_line(header);
+    }
+    for kind in args.kinds {
+        builder = builder.kind(kind);
+    }
+
+    match (args.format, args.output) {
+        (Format::Rust, Some(path)) => builder.write_to(&path)?,
+        (Format::Rust, None) => print!("{}", builder.generate()?),
+        (Format::JsonSchema, Some(dir)) => {
+            for (gk, schema) in builder.json_schemas()? {
+                let path = dir.join(format!("{gk}.schema.json"));
+                let parent = path.parent().expect("the path has a group directory");
+                fs::create_dir_all(parent)
+                    .with_context(|| format!("create {}", parent.display()))?;
+                write_json(&path, &schema)?;
+            }
+        }
+        (Format::JsonSchema, None) => {
+            print_json(&serde_json::Value::Object(builder.json_schemas()?.into_iter().collect()))?
+        }
+        (Format::Openapi, Some(path)) => write_json(&path, &builder.openapi()?)?,
+        (Format::Openapi, None) => print_json(&builder.openapi()?)?,
+    }
+    Ok(())
+}
+
+fn write_json(path: &Path, value: &serde_json::Value) -> anyhow::Result<()> {
+    let json = serde_json::to_string_pretty(value)?;
+    fs::write(path, json + "\n").with_context(|| format!("write {}", path.display()))
+}
+
+fn print_json(value: &serde_json::Value) -> anyhow::Result<()> {
+    println!("{}", serde_json::to_string_pretty(value)?);
+    Ok(())
+}
@@ -0,0 +1,376 @@
+//! Generates Rust types for the kinds of a discovery document.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write as _;
+
+use webconsole_client::protocol::{Discovery, FieldType, GroupKind, ObjectDef};
+
+/// Generates a module for each group, with a struct for each kind.
+///
+/// The generated code only depends on `serde`.
+pub fn generate(discovery: &Discovery, defs: &[&ObjectDef]) -> String {
+    let mut out = String::from("// @generated by webconsole-codegen. Do not edit.\n");
+
+    let mut groups = BTreeMap::<&str, Vec<&ObjectDef>>::new();
+    for def in defs {
+        groups.entry(&def.id.group).or_default().push(def);
+    }
+
+    // references to kinds that are not generated fall back to `String`
+    let refs: BTreeSet<&GroupKind> = defs.iter().map(|def| &def.id).collect();
+
+    // groups such as `a-b` and `a_b` would have the same module
+    let mut used = BTreeSet::new();
+    let modules: BTreeMap<&str, String> =
+        groups.keys().map(|&group| (group, unique(module_ident(group), &mut used))).collect();
+
+    for (group, defs) in groups {
+        let mut module = Module {
+            group,
+            refs: &refs,
+            modules: &modules,
+            items: Vec::new(),
+            names: BTreeSet::new(),
+        };
+        for def in &defs {
+            module.names.insert(type_ident(&def.id.kind));
+            module.names.insert(format!("{}Ref", type_ident(&def.id.kind)));
+        }
+        for def in defs {
+            module.kind(def);
+        }
+
+        let display_name = discovery.groups.get(group).map_or("", |group| &group.display_name);
+        writeln!(out).unwrap();
+        writeln!(out, "/// The `{group}` group, with display name `{display_name}`.").unwrap();
+        writeln!(out, "pub mod {} {{", modules[group]).unwrap();
+        writeln!(out, "    use serde::{{Deserialize, Serialize}};").unwrap();
+        for item in module.items {
+            writeln!(out).unwrap();
+            for line in item.lines() {
+                if line.is_empty() {
+                    writeln!(out).unwrap();
+                } else {
+                    writeln!(out, "    {line}").unwrap();
+                }
+            }
+        }
+        writeln!(out, "}}").unwrap();
+    }
+
+    out
+}
+
+/// The items generated in the module of a group.
+struct Module<'t> {
+    group:   &'t str,
+    /// The generated kinds, which have reference types.
+    refs:    &'t BTreeSet<&'t GroupKind>,
+    /// The module of each generated group.
+    modules: &'t BTreeMap<&'t str, String>,
+    items:   Vec<String>,
+    /// Type names already used in the module.
+    names:   BTreeSet<String>,
+}
+
+/// A struct field, or a nested struct for the fields under a common prefix.
+enum Node<'t> {
+    Field { ty: &'t FieldType, display_name: &'t str },
+    Struct(BTreeMap<&'t str, Node<'t>>),
+}
+
+impl<'t> Module<'t> {
+    fn kind(&mut self, def: &ObjectDef) {
+        let name = type_ident(&def.id.kind);
+        let GroupKind { group, kind } = &def.id;
+
+        let mut root = BTreeMap::new();
+        for field in def.fields.values() {
+            let mut node = &mut root;
+            let mut segments = field.path.split('.').peekable();
+            while let Some(segment) = segments.next() {
+                if segments.peek().is_none() {
+                    let leaf =
+                        Node::Field { ty: &field.ty, display_name: &field.display_name };
+                    node.entry(segment).or_insert(leaf);
+                    break;
+                }
+                let child = node.entry(segment).or_insert_with(|| Node::Struct(BTreeMap::new()));
+                // a field nested under another field is invalid and skipped
+                let Node::Struct(child) = child else { break };
+                node = child;
+            }
+        }
+
+        // nested types follow the kind
+        let index = self.items.len();
+        let mut item = String::new();
+        writeln!(
+            item,
+            "/// An object of the `{group}/{kind}` kind, with display name `{}`.",
+            def.display_name
+        )
+        .unwrap();
+        item.push_str(STRUCT_DERIVES);
+        writeln!(item, "pub struct {name} {{").unwrap();
+        writeln!(item, "    #[serde(rename = \"_name\")]").unwrap();
+        writeln!(item, "    pub name: String,").unwrap();
+        let reserved = BTreeSet::from(["name".to_string()]);
+        let fields = self.fields(&name, &root, reserved);
+        item.push_str(&fields);
+        writeln!(item, "}}").unwrap();
+        writeln!(item).unwrap();
+        writeln!(item, "impl {name} {{").unwrap();
+        writeln!(item, "    pub const GROUP: &'static str = {group:?};").unwrap();
+        writeln!(item, "    pub const KIND: &'static str = {kind:?};").unwrap();
+        writeln!(item).unwrap();
+        writeln!(
+            item,
+            "    pub fn reference(&self) -> {name}Ref {{ {name}Ref(self.name.clone()) }}"
+        )
+        .unwrap();
+        writeln!(item, "}}").unwrap();
+        writeln!(item).unwrap();
+        writeln!(item, "/// The name of an object of the `{group}/{kind}` kind.").unwrap();
+        writeln!(
+            item,
+            "#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]"
+        )
+        .unwrap();
+        writeln!(item, "#[serde(transparent)]").unwrap();
+        writeln!(item, "pub struct {name}Ref(pub String);").unwrap();
+        self.items.insert(index, item);
+    }
+
+    /// Generates the fields of a struct, and nested types named after `prefix`.
+    fn fields(
+        &mut self,
+        prefix: &str,
+        nodes: &BTreeMap<&str, Node>,
+        mut reserved: BTreeSet<String>,
+    ) -> String {
+        let mut fields = String::new();
+        for (key, node) in nodes {
+            let ident = unique(field_ident(key), &mut reserved);
+            let nested = format!("{prefix}{}", type_ident(key));
+            let ty = match node {
+                Node::Field { ty, display_name } => {
+                    writeln!(fields, "    /// Display name: `{display_name}`.").unwrap();
+                    for line in describe(ty) {
+                        writeln!(fields, "    ///").unwrap();
+                        writeln!(fields, "    /// {line}").unwrap();
+                    }
+                    self.ty(&nested, ty)
+                }
+                Node::Struct(children) => {
+                    let name = self.name(nested);
+                    let index = self.items.len();
+                    let fields = self.fields(&name, children, BTreeSet::new());
+                    self.structure(index, &name, &fields);
+                    name
+                }
+            };
+            if ident.trim_start_matches("r#") != *key {
+                writeln!(fields, "    #[serde(rename = {key:?})]").unwrap();
+            }
+            writeln!(fields, "    pub {ident}: {ty},").unwrap();
+        }
+        fields
+    }
+
+    /// The Rust type of a field type, generating the nested types it needs.
+    fn ty(&mut self, name: &str, ty: &FieldType) -> String {
+        match ty {
+            FieldType::String {} => "String".to_string(),
+            FieldType::Int64 { .. } => "i64".to_string(),
+            FieldType::Float64 { .. } => "f64".to_string(),
+            FieldType::Bool {} => "bool".to_string(),
+            FieldType::Enum { options } => {
+                let name = self.name(name.to_string());
+                let mut item = String::new();
+                item.push_str(ENUM_DERIVES);
+                writeln!(item, "pub enum {name} {{").unwrap();
+                let mut variants = BTreeSet::new();
+                for option in options.values() {
+                    let variant = unique(type_ident(&option.id), &mut variants);
+                    writeln!(item, "    /// Display name: `{}`.", option.i18n).unwrap();
+                    writeln!(item, "    #[serde(rename = {:?})]", option.id).unwrap();
+                    writeln!(item, "    {variant},").unwrap();
+                }
+                writeln!(item, "}}").unwrap();
+                self.items.push(item);
+                name
+            }
+            FieldType::Object { gk } if self.refs.contains(gk) => {
+                let name = format!("{}Ref", type_ident(&gk.kind));
+                if gk.group == self.group {
+                    name
+                } else {
+                    format!("super::{}::{name}", self.modules[gk.group.as_str()])
+                }
+            }
+            FieldType::Object { .. } => "String".to_string(),
+            FieldType::Nullable { item } => format!("Option<{}>", self.ty(name, item)),
+            FieldType::List { item } => format!("Vec<{}>", self.ty(&format!("{name}Item"), item)),
+            FieldType::Compound { fields } => {
+                let name = self.name(name.to_string());
+                let index = self.items.len();
+                let mut body = String::new();
+                let mut reserved = BTreeSet::new();
+                for field in fields.values() {
+                    let ident = unique(field_ident(&field.key), &mut reserved);
+                    let ty = self.ty(&format!("{name}{}", type_ident(&field.key)), &field.ty);
+                    writeln!(body, "    /// Display name: `{}`.", field.name).unwrap();
+                    for line in describe(&field.ty) {
+                        writeln!(body, "    ///").unwrap();
+                        writeln!(body, "    /// {line}").unwrap();
+                    }
+                    if ident.trim_start_matches("r#") != field.key {
+                        writeln!(body, "    #[serde(rename = {:?})]", field.key).unwrap();
+                    }
+                    writeln!(body, "    pub {ident}: {ty},").unwrap();
+                }
+                self.structure(index, &name, &body);
+                name
+            }
+        }
+    }
+
+    /// Adds a struct before the types nested in it, which were added from `index`.
+    fn structure(&mut self, index: usize, name: &str, fields: &str) {
+        let mut item = String::from(STRUCT_DERIVES);
+        writeln!(item, "pub struct {name} {{").unwrap();
+        item.push_str(fields);
+        writeln!(item, "}}").unwrap();
+        self.items.insert(index, item);
+    }
+
+    /// Reserves a type name in the module.
+    fn name(&mut self, name: String) -> String { unique(name, &mut self.names) }
+}
+
+const STRUCT_DERIVES: &str = "#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]\n";
+const ENUM_DERIVES: &str =
+    "#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]\n";
+
+/// Documents the constraints of a field type that the Rust type does not express.
+fn describe(ty: &FieldType) -> Vec<String> {
+    let mut lines = Vec::new();
+    match ty {
+        FieldType::Int64 { is_timestamp, min, max } => {
+            if *is_timestamp {
+                lines.push("Microseconds since the Unix epoch.".to_string());
+            }
+            lines.extend(bounds(min.map(|min| min.to_string()), max.map(|max| max.to_string())));
+        }
+        FieldType::Float64 { is_timestamp, min, max } => {
+            if *is_timestamp {
+                lines.push("Microseconds since the Unix epoch.".to_string());
+            }
+            lines.extend(bounds(min.map(|min| min.to_string()), max.map(|max| max.to_string())));
+        }
+        FieldType::Nullable { item } | FieldType::List { item } => lines.extend(describe(item)),
+        _ => {}
+    }
+    lines
+}
+
+fn bounds(min: Option<String>, max: Option<String>) -> Option<String> {
+    match (min, max) {
+        (Some(min), Some(max)) => Some(format!("Between {min} and {max}.")),
+        (Some(min), None) => Some(format!("At least {min}.")),
+        (None, Some(max)) => Some(format!("At most {max}.")),
+        (None, None) => None,
+    }
+}
+
+/// Appends a number to a name until it is not in `used`, and marks it as used.
+fn unique(name: String, used: &mut BTreeSet<String>) -> String {
+    let mut unique = name.clone();
+    let mut number = 2;
+    while !used.insert(unique.clone()) {
+        unique = format!("{name}{number}");
+        number += 1;
+    }
+    unique
+}
+
+/// Splits an identifier at non-alphanumeric characters and lowercase-to-uppercase transitions.
+fn words(str: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut previous_lower = false;
+    for c in str.chars() {
+        if !c.is_ascii_alphanumeric() {
+            words.extend((!word.is_empty()).then(|| std::mem::take(&mut word)));
+            previous_lower = false;
+            continue;
+        }
+        if c.is_ascii_uppercase() && previous_lower {
+            words.push(std::mem::take(&mut word));
+        }
+        previous_lower = c.is_ascii_lowercase() || c.is_ascii_digit();
+        word.push(c.to_ascii_lowercase());
+    }
+    words.extend((!word.is_empty()).then_some(word));
+    words
+}
+
+fn type_ident(str: &str) -> String {
+    let mut ident: String =
+        words(str).iter().map(|word| word[..1].to_ascii_uppercase() + &word[1..]).collect();
+    if !ident.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        ident.insert(0, 'T');
+    }
+    ident
+}
+
+fn field_ident(str: &str) -> String {
+    let mut ident = words(str).join("_");
+    if !ident.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        ident.insert(0, '_');
+    }
+    if ["crate", "self", "super"].contains(&ident.as_str()) {
+        // these keywords cannot be raw identifiers
+        ident.push('_');
+    } else if KEYWORDS.contains(&ident.as_str()) {
+        ident.insert_str(0, "r#");
+    }
+    ident
+}
+
+fn module_ident(group: &str) -> String { field_ident(group) }
+
+const KEYWORDS: &[&str] = &[
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "crate",
+    "do", "dyn", "else", "enum", "extern", "false", "final", "fn", "for", "if", "impl", "in",
+    "let", "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref",
+    "return", "static", "struct", "super", "trait", "true", "try", "type", "typeof", "unsafe",
+    "unsized", "use", "virtual", "where", "while", "yield",
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The output for the fixture, which `tests/generated.rs` checks to compile.
+    #[test]
+    fn fixture() {
+        let discovery: Discovery =
+            serde_json::from_str(include_str!("../tests/fixtures/discovery.json")).unwrap();
+        let defs: Vec<_> = discovery.apis.values().collect();
+        assert_eq!(generate(&discovery, &defs), include_str!("../tests/fixtures/generated.rs"));
+    }
+
+    #[test]
+    fn idents() {
+        assert_eq!(type_ident("hard-core"), "HardCore");
+        assert_eq!(type_ident("lastSeen"), "LastSeen");
+        assert_eq!(type_ident("2d"), "T2d");
+        assert_eq!(field_ident("lastSeen"), "last_seen");
+        assert_eq!(field_ident("type"), "r#type");
+        assert_eq!(field_ident("self"), "self_");
+        assert_eq!(field_ident("2d"), "_2d");
+        assert_eq!(module_ident("main-x"), module_ident("main_x"));
+    }
+}
@@ -0,0 +1,285 @@
+//! Describes the kinds of a discovery document as JSON Schema and their endpoints as OpenAPI.
+
+use serde_json::{json, Map, Value};
+use webconsole_client::protocol::{Discovery, FieldType, ObjectDef};
+
+const DIALECT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+/// The JSON Schema of the objects of a kind, as returned without field selection.
+pub fn json_schema(def: &ObjectDef) -> Value {
+    let mut schema = object_schema(def);
+    let map = schema.as_object_mut().expect("object schemas are objects");
+    map.insert("$schema".into(), DIALECT.into());
+    map.insert("$id".into(), format!("{}/{}", def.id.group, def.id.kind).into());
+    schema
+}
+
+fn object_schema(def: &ObjectDef) -> Value {
+    let mut schema = empty_object();
+    schema["title"] = format!("{}/{}", def.id.group, def.id.kind).into();
+    insert_property(&mut schema, "_name", json!({ "type": "string" }));
+    for field in def.fields.values() {
+        let mut node = &mut schema;
+        let mut segments = field.path.split('.').peekable();
+        while let Some(segment) = segments.next() {
+            if segments.peek().is_none() {
+                if node["properties"].get(segment).is_none() {
+                    insert_property(node, segment, field_schema(&field.ty));
+                }
+                break;
+            }
+            if node["properties"].get(segment).is_none() {
+                insert_property(node, segment, empty_object());
+            }
+            node = &mut node["properties"][segment];
+            // a field nested under another field is invalid and skipped
+            if node.get("properties").is_none() {
+                break;
+            }
+        }
+    }
+    schema
+}
+
+fn empty_object() -> Value { json!({ "type": "object", "properties": {}, "required": [] }) }
+
+fn insert_property(schema: &mut Value, key: &str, property: Value) {
+    schema["properties"][key] = property;
+    schema["required"].as_array_mut().expect("required is an array").push(key.into());
+}
+
+fn field_schema(ty: &FieldType) -> Value {
+    match ty {
+        FieldType::String {} => json!({ "type": "string" }),
+        FieldType::Int64 { is_timestamp, min, max } => {
+            let mut schema = json!({ "type": "integer", "format": "int64" });
+            number_constraints(
+                &mut schema,
+                *is_timestamp,
+                min.map(Into::into),
+                max.map(Into::into),
+            );
+            schema
+        }
+        FieldType::Float64 { is_timestamp, min, max } => {
+            let mut schema = json!({ "type": "number", "format": "double" });
+            number_constraints(
+                &mut schema,
+                *is_timestamp,
+                min.map(Into::into),
+                max.map(Into::into),
+            );
+            schema
+        }
+        FieldType::Bool {} => json!({ "type": "boolean" }),
+        FieldType::Enum { options } => {
+            let options: Vec<_> = options.values().map(|option| &option.id).collect();
+            json!({ "type": "string", "enum": options })
+        }
+        FieldType::Object { gk } => json!({
+            "type": "string",
+            "description": format!("The name of a `{}/{}` object.", gk.group, gk.kind),
+        }),
+        FieldType::Nullable { item } => {
+            json!({ "anyOf": [field_schema(item), { "type": "null" }] })
+        }
+        FieldType::List { item } => json!({ "type": "array", "items": field_schema(item) }),
+        FieldType::Compound { fields } => {
+            let mut schema = empty_object();
+            for field in fields.values() {
+                insert_property(&mut schema, &field.key, field_schema(&field.ty));
+            }
+            schema
+        }
+    }
+}
+
+fn number_constraints(
+    schema: &mut Value,
+    is_timestamp: bool,
+    min: Option<Value>,
+    max: Option<Value>,
+) {
+    if is_timestamp {
+        schema["description"] = "Microseconds since the Unix epoch.".into();
+    }
+    if let Some(min) = min {
+        schema["minimum"] = min;
+    }
+    if let Some(max) = max {
+        schema["maximum"] = max;
+    }
+}
+
+/// An OpenAPI document describing the list, get and watch endpoints of the kinds.
+pub fn openapi(discovery: &Discovery, defs: &[&ObjectDef]) -> Value {
+    let mut paths = Map::new();
+    let mut schemas = Map::new();
+    for def in defs {
+        let (group, kind) = (&def.id.group, &def.id.kind);
+        let component = format!("{}.{}", component_name(group), component_name(kind));
+        let object = json!({ "$ref": format!("#/components/schemas/{component}") });
+
+        schemas.insert(component.clone(), object_schema(def));
+        schemas.insert(format!("{component}.ListEvent"), list_event_schema(&object));
+
+        let tags = [group];
+        paths.insert(format!("/{group}/{kind}"), json!({
+            "get": {
+                "operationId": format!("list.{component}"),
+                "summary": format!("List or watch the `{group}/{kind}` objects"),
+                "tags": tags,
+                "parameters": [
+                    { "$ref": "#/components/parameters/watch" },
+                    { "$ref": "#/components/parameters/fields" },
+                    { "$ref": "#/components/parameters/limit" },
+                    { "$ref": "#/components/parameters/lastEventId" },
+                ],
+                "responses": {
+                    "200": {
+                        "description": "Without `watch`, one object per line. \
+                            With `watch`, a stream of events whose data are `ListEvent`s.",
+                        "content": {
+                            "application/x-ndjson": { "schema": object },
+                            "text/event-stream": {
+                                "schema": {
+                                    "$ref": format!("#/components/schemas/{component}.ListEvent"),
+                                },
+                            },
+                        },
+                    },
+                    "404": { "description": "The kind does not exist." },
+                },
+            },
+        }));
+        paths.insert(
+            format!("/{group}/{kind}/{{name}}"),
+            json!({
+                "get": {
+                    "operationId": format!("get.{component}"),
+                    "summary": format!("Get or watch a `{group}/{kind}` object"),
+                    "tags": tags,
+                    "parameters": [
+                        {
+                            "name": "name",
+                            "in": "path",
+                            "required": true,
+                            "schema": { "type": "string" },
+                        },
+                        { "$ref": "#/components/parameters/watch" },
+                        { "$ref": "#/components/parameters/fields" },
+                        { "$ref": "#/components/parameters/lastEventId" },
+                    ],
+                    "responses": {
+                        "200": {
+                            "description": "Without `watch`, the object. \
+                                With `watch`, a stream of events whose data are `SingleEvent`s.",
+                            "content": {
+                                "application/json": { "schema": object },
+                                "text/event-stream": {
+                                    "schema": { "$ref": "#/components/schemas/SingleEvent" },
+                                },
+                            },
+                        },
+                        "404": { "description": "The kind or the object does not exist." },
+                    },
+                },
+            }),
+        );
+    }
+    schemas.insert("SingleEvent".into(), single_event_schema());
+
+    let groups: Vec<_> = discovery
+        .groups
+        .values()
+        .filter(|group| defs.iter().any(|def| def.id.group == group.id))
+        .map(|group| json!({ "name": group.id }))
+        .collect();
+
+    json!({
+        "openapi": "3.1.0",
+        "info": { "title": "WebConsole API", "version": env!("CARGO_PKG_VERSION") },
+        "tags": groups,
+        "paths": paths,
+        "components": {
+            "schemas": schemas,
+            "parameters": {
+                "watch": {
+                    "name": "watch",
+                    "in": "query",
+                    "description": "Stream changes as server-sent events instead of returning \
+                        the current state. Any value enables watching, including `?watch`.",
+                    "allowEmptyValue": true,
+                    "schema": { "type": "string" },
+                },
+                "fields": {
+                    "name": "fields",
+                    "in": "query",
+                    "description": "Only return the fields with these paths, \
+                        indexed from 0 as in `fields[0]=a&fields[1]=b`. \
+                        `fields=` selects no fields. All fields are returned if unspecified.",
+                    "style": "deepObject",
+                    "explode": true,
+                    "schema": { "type": "object", "additionalProperties": { "type": "string" } },
+                },
+                "limit": {
+                    "name": "limit",
+                    "in": "query",
+                    "description": "Return at most this many objects. \
+                        Watches with a limit cannot be resumed.",
+                    "schema": { "type": "integer", "minimum": 1, "format": "int32" },
+                },
+                "lastEventId": {
+                    "name": "Last-Event-ID",
+                    "in": "header",
+                    "description": "Resume a watch after this event ID.",
+                    "schema": { "type": "string" },
+                },
+            },
+        },
+    })
+}
+
+/// Allows the characters of component names that OpenAPI accepts.
+fn component_name(id: &str) -> String {
+    id.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || "-_.".contains(c) { c } else { '_' })
+        .collect()
+}
+
+fn event(name: &str, properties: Value) -> Value {
+    let mut schema = json!({
+        "type": "object",
+        "properties": { "event": { "const": name } },
+        "required": ["event"],
+    });
+    for (key, property) in properties.as_object().expect("properties are an object") {
+        insert_property(&mut schema, key, property.clone());
+    }
+    schema
+}
+
+fn list_event_schema(object: &Value) -> Value {
+    json!({
+        "oneOf": [
+            event("Clear", json!({})),
+            event("Resumed", json!({})),
+            event("Added", json!({ "item": object })),
+            event("Removed", json!({ "name": { "type": "string" } })),
+            event("FieldUpdate", json!({
+                "name": { "type": "string" },
+                "field": { "type": "string" },
+                "value": {},
+            })),
+        ],
+    })
+}
+
+fn single_event_schema() -> Value {
+    json!({
+        "oneOf": [
+            event("Resumed", json!({})),
+            event("Update", json!({ "field": { "type": "string" }, "value": {} })),
+        ],
+    })
+}
@@ -0,0 +1,109 @@
+// @generated by webconsole-codegen. Do not edit.
+
+/// The `main-x` group, with display name `Main X`.
+pub mod main_x {
+    use serde::{Deserialize, Serialize};
+
+    /// An object of the `main-x/player` kind, with display name `Player`.
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    pub struct Player {
+        #[serde(rename = "_name")]
+        pub name: String,
+        /// Display name: `Friends`.
+        pub friends: Vec<Option<PlayerRef>>,
+        /// Display name: `Home`.
+        pub home: super::main_x2::WorldRef,
+        /// Display name: `Mode`.
+        pub mode: PlayerMode,
+        /// Display name: `Nickname`.
+        #[serde(rename = "name")]
+        pub name2: String,
+        /// Display name: `Position`.
+        pub pos: PlayerPos,
+        /// Display name: `Self`.
+        #[serde(rename = "self")]
+        pub self_: bool,
+        pub stats: PlayerStats,
+        /// Display name: `Type`.
+        pub r#type: String,
+    }
+
+    impl Player {
+        pub const GROUP: &'static str = "main-x";
+        pub const KIND: &'static str = "player";
+
+        pub fn reference(&self) -> PlayerRef { PlayerRef(self.name.clone()) }
+    }
+
+    /// The name of an object of the `main-x/player` kind.
+    #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+    #[serde(transparent)]
+    pub struct PlayerRef(pub String);
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+    pub enum PlayerMode {
+        /// Display name: `Hardcore`.
+        #[serde(rename = "hard-core")]
+        HardCore,
+        /// Display name: `Survival`.
+        #[serde(rename = "survival")]
+        Survival,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    pub struct PlayerPos {
+        /// Display name: `Dimension`.
+        pub dim: PlayerPosDim,
+        /// Display name: `X`.
+        pub x: f64,
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+    pub enum PlayerPosDim {
+        /// Display name: `Nether`.
+        #[serde(rename = "nether")]
+        Nether,
+        /// Display name: `Overworld`.
+        #[serde(rename = "overworld")]
+        Overworld,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    pub struct PlayerStats {
+        /// Display name: `Health`.
+        ///
+        /// Between 0 and 20.
+        pub health: f64,
+        /// Display name: `Last seen`.
+        ///
+        /// Microseconds since the Unix epoch.
+        #[serde(rename = "lastSeen")]
+        pub last_seen: i64,
+    }
+}
+
+/// The `main_x` group, with display name `Main x`.
+pub mod main_x2 {
+    use serde::{Deserialize, Serialize};
+
+    /// An object of the `main_x/world` kind, with display name `World`.
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    pub struct World {
+        #[serde(rename = "_name")]
+        pub name: String,
+        /// Display name: `Owner`.
+        pub owner: super::main_x::PlayerRef,
+    }
+
+    impl World {
+        pub const GROUP: &'static str = "main_x";
+        pub const KIND: &'static str = "world";
+
+        pub fn reference(&self) -> WorldRef { WorldRef(self.name.clone()) }
+    }
+
+    /// The name of an object of the `main_x/world` kind.
+    #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+    #[serde(transparent)]
+    pub struct WorldRef(pub String);
+}
@@ -0,0 +1,42 @@
+//! Checks that the generated code compiles and reads the objects it describes.
+
+// kept as generated, so that `rust::tests::fixture` can compare it
+#[rustfmt::skip]
+#[allow(dead_code)]
+#[path = "fixtures/generated.rs"]
+mod generated;
+
+use generated::{main_x, main_x2};
+use serde_json::json;
+
+#[test]
+fn deserialize() {
+    let player: main_x::Player = serde_json::from_value(json!({
+        "_name": "alice",
+        "name": "Alice",
+        "type": "human",
+        "self": true,
+        "stats": { "health": 14.5, "lastSeen": 1_700_000_000_000_000_i64 },
+        "mode": "hard-core",
+        "pos": { "x": 1.5, "dim": "nether" },
+        "home": "overworld",
+        "friends": ["bob", null],
+    }))
+    .unwrap();
+
+    assert_eq!(player.name, "alice");
+    assert_eq!(player.name2, "Alice");
+    assert_eq!(player.r#type, "human");
+    assert!(player.self_);
+    assert_eq!(player.stats.last_seen, 1_700_000_000_000_000);
+    assert_eq!(player.mode, main_x::PlayerMode::HardCore);
+    assert_eq!(player.pos.dim, main_x::PlayerPosDim::Nether);
+    assert_eq!(player.home, main_x2::WorldRef("overworld".to_string()));
+    assert_eq!(player.friends, [Some(main_x::PlayerRef("bob".to_string())), None]);
+    assert_eq!(player.reference(), main_x::PlayerRef("alice".to_string()));
+
+    let world: main_x2::World =
+        serde_json::from_value(json!({ "_name": "overworld", "owner": "alice" })).unwrap();
+    assert_eq!(world.owner, player.reference());
+    assert_eq!((main_x2::World::GROUP, main_x2::World::KIND), ("main_x", "world"));
+}
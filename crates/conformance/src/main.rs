@@ -0,0 +1,133 @@
+//! Checks a WebConsole API server against the protocol.
+//!
+//! Prints every failed check and warning, followed by a summary,
+//! and exits with a non-zero status if any check failed.
+
+use std::collections::{BTreeSet, HashMap};
+use std::io;
+use std::time::Duration;
+
+use clap::Parser;
+use futures::TryStreamExt;
+use webconsole_client::protocol::GroupKind;
+use webconsole_client::{select_kinds, Client, Object, Selection};
+
+mod objects;
+mod report;
+mod schema;
+mod types;
+mod watch;
+
+use report::Report;
+use types::ObjectRef;
+
+#[derive(Parser)]
+#[command(version, about)]
+struct Args {
+    /// Base URL of the API server.
+    #[arg(long, env = "WEBCONSOLE_SERVER", default_value = "http://localhost:14875")]
+    server:        String,
+    /// Extra request header in the form `Name: value`, e.g. for an authenticating proxy.
+    #[arg(long = "header", short = 'H')]
+    headers:       Vec<String>,
+    /// Only check these kinds, in the form `group/kind`. All kinds are checked if unspecified.
+    #[arg(long = "kind")]
+    kinds:         Vec<String>,
+    /// How long to follow each watch.
+    #[arg(long, default_value_t = 3.)]
+    watch_seconds: f64,
+    /// Also print the checks that passed.
+    #[arg(long, short)]
+    verbose:       bool,
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+
+    let mut client = Client::new(&args.server);
+    for header in &args.headers {
+        client = client.with_header_line(header)?;
+    }
+
+    let mut report = Report::new(io::stdout().lock(), args.verbose);
+
+    let discovery = match client.discovery().await {
+        Ok(discovery) => discovery,
+        Err(err) => {
+            report.fail("discovery", err);
+            report.summary()?;
+            std::process::exit(1);
+        }
+    };
+    report.pass("discovery", "decodes into the schema");
+
+    schema::check_discovery(&mut report, &discovery);
+    schema::check_locales(&mut report, &client, &discovery).await;
+
+    let defs = select_kinds(&discovery, &args.kinds)?;
+
+    let duration = Duration::from_secs_f64(args.watch_seconds.max(0.));
+    let mut refs = Vec::new();
+    let mut names = HashMap::new();
+    for def in defs {
+        let Some(listed) = objects::check_kind(&mut report, &client, def, &mut refs).await else { continue };
+
+        watch::check_watch_list(&mut report, &client, def, &listed, duration, &mut refs).await;
+        if let Some(first) = listed.first() {
+            watch::check_watch_single(&mut report, &client, def, &first.name, duration, &mut refs)
+                .await;
+        }
+
+        names.insert(def.id.clone(), listed.into_iter().map(|object| object.name).collect());
+    }
+
+    check_refs(&mut report, &client, refs, names).await;
+
+    report.summary()?;
+    if report.failed() {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+/// Checks that object references name existing objects.
+///
+/// Only warns about unknown names, since the objects may have been removed in the meantime.
+async fn check_refs(
+    report: &mut Report<impl io::Write>,
+    client: &Client,
+    refs: Vec<ObjectRef>,
+    mut names: HashMap<GroupKind, BTreeSet<String>>,
+) {
+    let mut unresolved = 0;
+    for object_ref in &refs {
+        if !names.contains_key(&object_ref.gk) {
+            let GroupKind { group, kind } = &object_ref.gk;
+            let listed: Vec<Object> = match client.list(group, kind, &Selection::default()).await {
+                Ok(stream) => stream.try_collect().await.unwrap_or_default(),
+                Err(_) => Vec::new(),
+            };
+            names.insert(
+                object_ref.gk.clone(),
+                listed.into_iter().map(|object| object.name).collect(),
+            );
+        }
+
+        if !names[&object_ref.gk].contains(&object_ref.name) {
+            unresolved += 1;
+            let GroupKind { group, kind } = &object_ref.gk;
+            report.warn(
+                "references",
+                format!(
+                    "{} refers to unknown object {group}/{kind}/{}",
+                    object_ref.path, object_ref.name
+                ),
+            );
+        }
+    }
+
+    if unresolved == 0 {
+        report.pass("references", format!("{} object references resolve", refs.len()));
+    }
+}
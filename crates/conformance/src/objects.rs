@@ -0,0 +1,151 @@
+//! Checks the objects returned by list and get requests.
+
+use std::collections::BTreeSet;
+use std::io::Write;
+
+use futures::TryStreamExt;
+use serde_json::Value;
+use webconsole_client::protocol::{get_json_path, ObjectDef};
+use webconsole_client::{ApiError, Client, Object, Selection};
+
+use crate::report::Report;
+use crate::types::{ObjectRef, ValueChecker};
+
+/// Checks that an object has exactly the fields in `paths` with values of the declared types.
+pub fn check_object(def: &ObjectDef, object: &Object, paths: &[&str], checker: &mut ValueChecker) {
+    let name = &object.name;
+    if !object.fields.is_object() {
+        checker.errors.push(format!("{name}: expected a JSON object, got {}", object.fields));
+        return;
+    }
+
+    for &path in paths {
+        let Some(field) = def.fields.get(path) else { continue };
+        match get_json_path(&object.fields, path) {
+            Some(value) => checker.check(&format!("{name}: {path}"), &field.ty, value),
+            None => checker.errors.push(format!("{name}: missing field {path}")),
+        }
+    }
+
+    let mut extra = Vec::new();
+    undeclared(&object.fields, "", paths, &mut extra);
+    for path in extra {
+        checker.errors.push(format!("{name}: unexpected field {path}"));
+    }
+}
+
+/// Finds the JSON paths that are neither one of `paths` nor a parent of one.
+fn undeclared(value: &Value, prefix: &str, paths: &[&str], extra: &mut Vec<String>) {
+    let Value::Object(map) = value else { return };
+    for (key, value) in map {
+        let path = if prefix.is_empty() { key.clone() } else { format!("{prefix}.{key}") };
+        if paths.contains(&path.as_str()) {
+            continue;
+        }
+
+        let parent = format!("{path}.");
+        if paths.iter().any(|declared| declared.starts_with(&parent)) {
+            undeclared(value, &path, paths, extra);
+        } else {
+            extra.push(path);
+        }
+    }
+}
+
+/// Lists, gets and selects the objects of a kind, returning the listed objects.
+pub async fn check_kind(
+    report: &mut Report<impl Write>,
+    client: &Client,
+    def: &ObjectDef,
+    refs: &mut Vec<ObjectRef>,
+) -> Option<Vec<Object>> {
+    let (group, kind) = (&def.id.group, &def.id.kind);
+    let subject = format!("{group}/{kind}");
+    let all_paths: Vec<&str> = def.fields.values().map(|field| field.path.as_str()).collect();
+
+    let objects: Vec<Object> = match list(client, def, &Selection::default()).await {
+        Ok(objects) => objects,
+        Err(err) => {
+            report.fail(&subject, format!("list: {err}"));
+            return None;
+        }
+    };
+
+    let mut checker = ValueChecker::default();
+    let mut names = BTreeSet::new();
+    for object in &objects {
+        if !names.insert(&object.name) {
+            checker.errors.push(format!("{}: listed twice", object.name));
+        }
+        check_object(def, object, &all_paths, &mut checker);
+    }
+    refs.append(&mut checker.refs);
+    report.check(
+        &subject,
+        format!("list of {} objects matches the schema", objects.len()),
+        &checker.errors,
+    );
+
+    match list(client, def, &Selection { fields: None, limit: Some(1) }).await {
+        Ok(limited) if limited.len() <= 1 => {
+            report.pass(&subject, "limit=1 returns at most 1 object")
+        }
+        Ok(limited) => report.fail(&subject, format!("limit=1 returned {} objects", limited.len())),
+        Err(err) => report.fail(&subject, format!("list with limit: {err}")),
+    }
+
+    if let Some(&path) = all_paths.first() {
+        let selection = Selection { fields: Some(vec![path.to_string()]), limit: None };
+        match list(client, def, &selection).await {
+            Ok(selected) => {
+                let mut checker = ValueChecker::default();
+                for object in &selected {
+                    check_object(def, object, &[path], &mut checker);
+                }
+                report.check(
+                    &subject,
+                    format!("fields={path} returns only {path}"),
+                    &checker.errors,
+                );
+            }
+            Err(err) => report.fail(&subject, format!("list with fields: {err}")),
+        }
+    }
+
+    if let Some(first) = objects.first() {
+        match client.get(group, kind, &first.name, None).await {
+            Ok(object) => {
+                let mut checker = ValueChecker::default();
+                if object.name != first.name {
+                    checker.errors.push(format!("get {} returned {}", first.name, object.name));
+                }
+                check_object(def, &object, &all_paths, &mut checker);
+                report.check(
+                    &subject,
+                    format!("get {} matches the schema", first.name),
+                    &checker.errors,
+                );
+            }
+            Err(err) => report.fail(&subject, format!("get {}: {err}", first.name)),
+        }
+    }
+
+    let missing = "webconsole-conformance-nonexistent";
+    match client.get(group, kind, missing, None).await {
+        Err(ApiError::NotFound { .. }) => {
+            report.pass(&subject, "get of a nonexistent object is 404")
+        }
+        Ok(_) => report.fail(&subject, format!("get {missing} returned an object")),
+        Err(err) => report.fail(&subject, format!("get {missing} should be 404, got: {err}")),
+    }
+
+    Some(objects)
+}
+
+async fn list(
+    client: &Client,
+    def: &ObjectDef,
+    selection: &Selection,
+) -> Result<Vec<Object>, ApiError> {
+    client.list(&def.id.group, &def.id.kind, selection).await?.try_collect().await
+}
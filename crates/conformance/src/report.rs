@@ -0,0 +1,81 @@
+use std::fmt;
+use std::io::{self, Write};
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+    Pass,
+    /// Suspicious, but allowed by the protocol.
+    Warn,
+    Fail,
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Pass => "PASS",
+            Self::Warn => "WARN",
+            Self::Fail => "FAIL",
+        })
+    }
+}
+
+struct Entry {
+    level:   Level,
+    subject: String,
+    message: String,
+}
+
+/// The results of all checks, printed as they are recorded.
+pub struct Report<W> {
+    out:     W,
+    entries: Vec<Entry>,
+    verbose: bool,
+}
+
+impl<W: Write> Report<W> {
+    /// Passed checks are only printed if `verbose` is set.
+    pub fn new(out: W, verbose: bool) -> Self { Self { out, entries: Vec::new(), verbose } }
+
+    pub fn pass(&mut self, subject: &str, message: impl fmt::Display) {
+        self.record(Level::Pass, subject, message);
+    }
+
+    pub fn warn(&mut self, subject: &str, message: impl fmt::Display) {
+        self.record(Level::Warn, subject, message);
+    }
+
+    pub fn fail(&mut self, subject: &str, message: impl fmt::Display) {
+        self.record(Level::Fail, subject, message);
+    }
+
+    /// Records a pass if there are no errors, or a failure for each error.
+    pub fn check(&mut self, subject: &str, passed: impl fmt::Display, errors: &[String]) {
+        if errors.is_empty() {
+            self.pass(subject, passed);
+        }
+        for error in errors {
+            self.fail(subject, error);
+        }
+    }
+
+    fn record(&mut self, level: Level, subject: &str, message: impl fmt::Display) {
+        let entry = Entry { level, subject: subject.to_string(), message: message.to_string() };
+        if self.verbose || level != Level::Pass {
+            // the report is still useful if stdout is closed early, e.g. by `head`
+            _ = writeln!(self.out, "{}  {}  {}", entry.level, entry.subject, entry.message);
+        }
+        self.entries.push(entry);
+    }
+
+    fn count(&self, level: Level) -> usize {
+        self.entries.iter().filter(|entry| entry.level == level).count()
+    }
+
+    pub fn failed(&self) -> bool { self.count(Level::Fail) > 0 }
+
+    pub fn summary(&mut self) -> io::Result<()> {
+        let (pass, warn, fail) =
+            (self.count(Level::Pass), self.count(Level::Warn), self.count(Level::Fail));
+        writeln!(self.out, "{pass} passed, {warn} warnings, {fail} failed")
+    }
+}
@@ -0,0 +1,154 @@
+//! Checks the discovery document and the translations it refers to.
+
+use std::collections::BTreeSet;
+use std::io::Write;
+
+use fluent_syntax::ast;
+use webconsole_client::protocol::{FieldType, GroupKindDyn, GroupKindRef};
+use webconsole_client::{Client, Discovery};
+
+use crate::report::Report;
+
+pub fn check_discovery(report: &mut Report<impl Write>, discovery: &Discovery) {
+    for def in &discovery.apis {
+        let subject = format!("{}/{}", def.id.group, def.id.kind);
+        let mut errors = Vec::new();
+
+        if discovery.groups.get(&def.id.group).is_none() {
+            errors.push(format!("group {:?} is not declared", def.id.group));
+        }
+
+        for field in &def.fields {
+            check_type(discovery, &field.path, &field.ty, &mut errors);
+
+            // a value cannot be both a leaf and an object of subfields
+            let prefix = format!("{}.", field.path);
+            for other in &def.fields {
+                if other.path.starts_with(&prefix) {
+                    errors
+                        .push(format!("field {} is nested under field {}", other.path, field.path));
+                }
+            }
+        }
+
+        report.check(&subject, "schema is consistent", &errors);
+    }
+}
+
+fn check_type(discovery: &Discovery, path: &str, ty: &FieldType, errors: &mut Vec<String>) {
+    match ty {
+        FieldType::String {} | FieldType::Bool {} => {}
+        FieldType::Int64 { min: Some(min), max: Some(max), .. } if min > max => {
+            errors.push(format!("{path}: minimum {min} is above maximum {max}"));
+        }
+        FieldType::Float64 { min: Some(min), max: Some(max), .. } if min > max => {
+            errors.push(format!("{path}: minimum {min} is above maximum {max}"));
+        }
+        FieldType::Int64 { .. } | FieldType::Float64 { .. } => {}
+        FieldType::Enum { options } => {
+            if options.is_empty() {
+                errors.push(format!("{path}: enum has no options"));
+            }
+        }
+        FieldType::Object { gk } => {
+            let target = GroupKindRef { group: &gk.group, kind: &gk.kind };
+            if discovery.apis.get(&target as &dyn GroupKindDyn).is_none() {
+                errors.push(format!("{path}: refers to undeclared kind {}/{}", gk.group, gk.kind));
+            }
+        }
+        FieldType::Nullable { item } => {
+            if matches!(**item, FieldType::Nullable { .. }) {
+                errors.push(format!("{path}: nullable of nullable cannot be distinguished"));
+            }
+            check_type(discovery, path, item, errors);
+        }
+        FieldType::List { item } => check_type(discovery, &format!("{path}[]"), item, errors),
+        FieldType::Compound { fields } => {
+            for field in fields {
+                check_type(discovery, &format!("{path}.{}", field.key), &field.ty, errors);
+            }
+        }
+    }
+}
+
+/// The translation keys used by the discovery document.
+fn i18n_keys(discovery: &Discovery) -> BTreeSet<&str> {
+    fn type_keys<'t>(ty: &'t FieldType, keys: &mut BTreeSet<&'t str>) {
+        match ty {
+            FieldType::Enum { options } => {
+                keys.extend(options.values().map(|option| option.i18n.as_str()));
+            }
+            FieldType::Nullable { item } | FieldType::List { item } => type_keys(item, keys),
+            FieldType::Compound { fields } => {
+                for field in fields {
+                    keys.insert(&field.name);
+                    type_keys(&field.ty, keys);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut keys = BTreeSet::new();
+    keys.extend(discovery.groups.values().map(|group| group.display_name.as_str()));
+    for def in &discovery.apis {
+        keys.insert(&def.display_name);
+        for field in &def.fields {
+            keys.insert(&field.display_name);
+            type_keys(&field.ty, &mut keys);
+        }
+    }
+    keys
+}
+
+pub async fn check_locales(
+    report: &mut Report<impl Write>,
+    client: &Client,
+    discovery: &Discovery,
+) {
+    let locales = match client.locales().await {
+        Ok(locales) => locales,
+        Err(err) => return report.fail("locales", err),
+    };
+    if locales.is_empty() {
+        report.warn("locales", "the server provides no translations");
+    }
+
+    let keys = i18n_keys(discovery);
+    for locale in locales {
+        let subject = format!("locale {locale}");
+        let source = match client.locale_file(&locale).await {
+            Ok(source) => source,
+            Err(err) => {
+                report.fail(&subject, err);
+                continue;
+            }
+        };
+
+        let resource = match fluent_syntax::parser::parse(source.as_str()) {
+            Ok(resource) => resource,
+            Err((resource, errs)) => {
+                for err in errs {
+                    report.fail(&subject, format!("syntax error: {err}"));
+                }
+                resource
+            }
+        };
+        let messages: BTreeSet<&str> = resource
+            .body
+            .iter()
+            .filter_map(|entry| match entry {
+                ast::Entry::Message(message) => Some(message.id.name),
+                _ => None,
+            })
+            .collect();
+
+        let missing: Vec<_> = keys.iter().filter(|key| !messages.contains(*key)).collect();
+        if missing.is_empty() {
+            report.pass(&subject, "translates every key in discovery");
+        }
+        for key in missing {
+            report.warn(&subject, format!("no translation for {key}"));
+        }
+    }
+}
@@ -0,0 +1,116 @@
+//! Checks field values against their declared [`FieldType`].
+
+use serde_json::Value;
+use webconsole_client::protocol::{FieldType, GroupKind};
+
+/// A reference from a field value to an object of another kind.
+pub struct ObjectRef {
+    pub path: String,
+    pub gk:   GroupKind,
+    pub name: String,
+}
+
+/// Collects the problems of the values checked against their types.
+#[derive(Default)]
+pub struct ValueChecker {
+    pub errors: Vec<String>,
+    /// References to other objects, to be resolved by the caller
+    /// since objects may be removed between requests.
+    pub refs:   Vec<ObjectRef>,
+}
+
+impl ValueChecker {
+    /// Checks `value` at `path` against `ty`.
+    pub fn check(&mut self, path: &str, ty: &FieldType, value: &Value) {
+        match ty {
+            FieldType::String {} => {
+                if !value.is_string() {
+                    self.mismatch(path, "a string", value);
+                }
+            }
+            FieldType::Int64 { min, max, .. } => match value.as_i64() {
+                Some(number) => self.check_range(path, number, *min, *max),
+                None => self.mismatch(path, "a 64-bit integer", value),
+            },
+            FieldType::Float64 { min, max, .. } => match value.as_f64() {
+                Some(number) => self.check_range(path, number, *min, *max),
+                None => self.mismatch(path, "a number", value),
+            },
+            FieldType::Bool {} => {
+                if !value.is_boolean() {
+                    self.mismatch(path, "a bool", value);
+                }
+            }
+            FieldType::Enum { options } => match value.as_str() {
+                Some(id) if options.get(id).is_some() => {}
+                Some(id) => self.errors.push(format!("{path}: {id:?} is not an enum option")),
+                None => self.mismatch(path, "an enum option id", value),
+            },
+            FieldType::Object { gk } => match value.as_str() {
+                Some(name) => self.refs.push(ObjectRef {
+                    path: path.to_string(),
+                    gk:   gk.clone(),
+                    name: name.to_string(),
+                }),
+                None => self.mismatch(path, "an object name", value),
+            },
+            FieldType::Nullable { item } => {
+                if !value.is_null() {
+                    self.check(path, item, value);
+                }
+            }
+            FieldType::List { item } => match value {
+                Value::Array(items) => {
+                    for (i, value) in items.iter().enumerate() {
+                        self.check(&format!("{path}[{i}]"), item, value);
+                    }
+                }
+                _ => self.mismatch(path, "a list", value),
+            },
+            FieldType::Compound { fields } => {
+                let empty = serde_json::Map::new();
+                let map = match value {
+                    Value::Object(map) => map,
+                    // PHP encodes empty associative arrays as `[]`
+                    Value::Array(items) if items.is_empty() => &empty,
+                    _ => return self.mismatch(path, "a compound object", value),
+                };
+
+                for field in fields {
+                    match map.get(&field.key) {
+                        Some(value) => {
+                            self.check(&format!("{path}.{}", field.key), &field.ty, value)
+                        }
+                        None => {
+                            self.errors.push(format!("{path}: missing subfield {:?}", field.key))
+                        }
+                    }
+                }
+                for key in map.keys() {
+                    if fields.get(key).is_none() {
+                        self.errors.push(format!("{path}: undeclared subfield {key:?}"));
+                    }
+                }
+            }
+        }
+    }
+
+    fn check_range<T: PartialOrd + std::fmt::Display>(
+        &mut self,
+        path: &str,
+        number: T,
+        min: Option<T>,
+        max: Option<T>,
+    ) {
+        if let Some(min) = min.filter(|min| number < *min) {
+            self.errors.push(format!("{path}: {number} is below the minimum {min}"));
+        }
+        if let Some(max) = max.filter(|max| number > *max) {
+            self.errors.push(format!("{path}: {number} is above the maximum {max}"));
+        }
+    }
+
+    fn mismatch(&mut self, path: &str, expect: &str, value: &Value) {
+        self.errors.push(format!("{path}: expected {expect}, got {value}"));
+    }
+}
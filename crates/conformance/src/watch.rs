@@ -0,0 +1,249 @@
+//! Checks the event sequences of watch streams.
+
+use std::collections::BTreeSet;
+use std::io::Write;
+use std::time::Duration;
+
+use futures::stream::BoxStream;
+use futures::StreamExt;
+use tokio::time::Instant;
+use webconsole_client::protocol::ObjectDef;
+use webconsole_client::{
+    ApiError, Client, Object, ResumeToken, Selection, WatchListEvent, WatchSingleEvent,
+};
+
+use crate::objects::check_object;
+use crate::report::Report;
+use crate::types::{ObjectRef, ValueChecker};
+
+/// The events received from a watch within the time limit.
+struct Collected<T> {
+    events: Vec<T>,
+    /// Whether the server closed the stream before the time limit.
+    ended:  bool,
+    error:  Option<ApiError>,
+}
+
+async fn collect<T>(
+    mut stream: BoxStream<'static, Result<T, ApiError>>,
+    duration: Duration,
+) -> Collected<T> {
+    let deadline = Instant::now() + duration;
+    let mut collected = Collected { events: Vec::new(), ended: false, error: None };
+    loop {
+        match tokio::time::timeout_at(deadline, stream.next()).await {
+            Err(_) => return collected,
+            Ok(None) => {
+                collected.ended = true;
+                return collected;
+            }
+            Ok(Some(Ok(event))) => collected.events.push(event),
+            Ok(Some(Err(err))) => {
+                collected.error = Some(err);
+                return collected;
+            }
+        }
+    }
+}
+
+/// Watches a kind and checks that the events describe a coherent list.
+pub async fn check_watch_list(
+    report: &mut Report<impl Write>,
+    client: &Client,
+    def: &ObjectDef,
+    listed: &[Object],
+    duration: Duration,
+    refs: &mut Vec<ObjectRef>,
+) {
+    let (group, kind) = (&def.id.group, &def.id.kind);
+    let subject = format!("{group}/{kind}");
+    let all_paths: Vec<&str> = def.fields.values().map(|field| field.path.as_str()).collect();
+
+    let resume = ResumeToken::default();
+    let stream = match client.watch_list(group, kind, &Selection::default(), &resume).await {
+        Ok(stream) => stream,
+        Err(err) => return report.fail(&subject, format!("watch: {err}")),
+    };
+    let collected = collect(stream, duration).await;
+
+    let mut checker = ValueChecker::default();
+    let mut names = BTreeSet::new();
+    // the names added before the first removal or update are the initial objects
+    let mut initial = None;
+    for (i, event) in collected.events.iter().enumerate() {
+        match event {
+            WatchListEvent::Clear => {
+                names.clear();
+            }
+            WatchListEvent::Resumed if i == 0 => {
+                checker.errors.push(String::from("resumed a watch that sent no Last-Event-ID"));
+            }
+            WatchListEvent::Resumed => {
+                checker.errors.push(String::from("resumed in the middle of a watch"))
+            }
+            WatchListEvent::Added { item } => {
+                if !names.insert(item.name.clone()) {
+                    checker.errors.push(format!("added {} twice", item.name));
+                }
+                check_object(def, item, &all_paths, &mut checker);
+            }
+            WatchListEvent::Removed { name } => {
+                initial.get_or_insert_with(|| names.clone());
+                if !names.remove(name) {
+                    checker.errors.push(format!("removed unknown object {name}"));
+                }
+            }
+            WatchListEvent::FieldUpdate { name, field, value } => {
+                initial.get_or_insert_with(|| names.clone());
+                if !names.contains(name) {
+                    checker.errors.push(format!("updated unknown object {name}"));
+                }
+                match def.fields.get(field) {
+                    Some(field) => {
+                        checker.check(&format!("{name}: {}", field.path), &field.ty, value)
+                    }
+                    None => {
+                        checker.errors.push(format!("{name}: updated undeclared field {field}"))
+                    }
+                }
+            }
+        }
+    }
+
+    if !matches!(collected.events.first(), Some(WatchListEvent::Clear)) {
+        checker.errors.push(String::from("watch does not start with Clear"));
+    }
+    if let Some(err) = collected.error {
+        checker.errors.push(format!("watch: {err}"));
+    }
+    refs.append(&mut checker.refs);
+    report.check(
+        &subject,
+        format!("watch sent {} coherent events", collected.events.len()),
+        &checker.errors,
+    );
+
+    if collected.ended {
+        report.warn(&subject, "the server closed the watch before the time limit");
+    }
+
+    // objects may change between the list and the watch, so a mismatch is only suspicious
+    let initial = initial.unwrap_or(names);
+    let listed: BTreeSet<_> = listed.iter().map(|object| object.name.clone()).collect();
+    for name in listed.difference(&initial) {
+        report.warn(&subject, format!("{name} was listed but not added by the watch"));
+    }
+    for name in initial.difference(&listed) {
+        report.warn(&subject, format!("{name} was added by the watch but not listed"));
+    }
+
+    check_resume(
+        report,
+        &subject,
+        &resume,
+        client.watch_list(group, kind, &Selection::default(), &resume).await,
+        |event| match event {
+            WatchListEvent::Resumed => Some(true),
+            WatchListEvent::Clear => Some(false),
+            _ => None,
+        },
+    )
+    .await;
+}
+
+/// Watches an object and checks that the updates are for its declared fields.
+pub async fn check_watch_single(
+    report: &mut Report<impl Write>,
+    client: &Client,
+    def: &ObjectDef,
+    name: &str,
+    duration: Duration,
+    refs: &mut Vec<ObjectRef>,
+) {
+    let (group, kind) = (&def.id.group, &def.id.kind);
+    let subject = format!("{group}/{kind}/{name}");
+
+    let resume = ResumeToken::default();
+    let stream = match client.watch_single(group, kind, name, None, &resume).await {
+        Ok(stream) => stream,
+        Err(err) => return report.fail(&subject, format!("watch: {err}")),
+    };
+    let collected = collect(stream, duration).await;
+
+    let mut checker = ValueChecker::default();
+    let mut updated = BTreeSet::new();
+    for (i, event) in collected.events.iter().enumerate() {
+        match event {
+            WatchSingleEvent::Resumed if i == 0 => {
+                checker.errors.push(String::from("resumed a watch that sent no Last-Event-ID"));
+            }
+            WatchSingleEvent::Resumed => {
+                checker.errors.push(String::from("resumed in the middle of a watch"))
+            }
+            WatchSingleEvent::Update { field, value } => match def.fields.get(field) {
+                Some(field) => {
+                    updated.insert(field.path.as_str());
+                    checker.check(&field.path, &field.ty, value);
+                }
+                None => checker.errors.push(format!("updated undeclared field {field}")),
+            },
+        }
+    }
+    if let Some(err) = collected.error {
+        checker.errors.push(format!("watch: {err}"));
+    }
+    refs.append(&mut checker.refs);
+    report.check(
+        &subject,
+        format!("watch sent {} valid updates", collected.events.len()),
+        &checker.errors,
+    );
+
+    for field in &def.fields {
+        if !updated.contains(field.path.as_str()) {
+            report
+                .warn(&subject, format!("watch did not send the current value of {}", field.path));
+        }
+    }
+
+    let reopened = client.watch_single(group, kind, name, None, &resume).await;
+    check_resume(report, &subject, &resume, reopened, |event| match event {
+        WatchSingleEvent::Resumed => Some(true),
+        WatchSingleEvent::Update { .. } => Some(false),
+    })
+    .await;
+}
+
+/// Checks that a watch reopened with the last event ID is resumed or restarted.
+///
+/// `classify` tells whether the first event resumes the watch,
+/// restarts it, or is invalid as a first event.
+async fn check_resume<T>(
+    report: &mut Report<impl Write>,
+    subject: &str,
+    resume: &ResumeToken,
+    reopened: Result<BoxStream<'static, Result<T, ApiError>>, ApiError>,
+    classify: impl Fn(&T) -> Option<bool>,
+) {
+    let Some(id) = resume.get() else {
+        report.warn(subject, "the server sends no event IDs, so watches cannot be resumed");
+        return;
+    };
+
+    let mut stream = match reopened {
+        Ok(stream) => stream,
+        Err(err) => return report.fail(subject, format!("resume from {id}: {err}")),
+    };
+    match tokio::time::timeout(Duration::from_secs(5), stream.next()).await {
+        Ok(Some(Ok(event))) => match classify(&event) {
+            Some(true) => report.pass(subject, format!("resumes from event ID {id}")),
+            Some(false) => report.warn(subject, format!("restarted instead of resuming from {id}")),
+            None => {
+                report.fail(subject, format!("resuming from {id} started with an invalid event"))
+            }
+        },
+        Ok(Some(Err(err))) => report.fail(subject, format!("resume from {id}: {err}")),
+        Ok(None) => report.fail(subject, format!("resuming from {id} closed the stream")),
+        Err(_) => report.fail(subject, format!("resuming from {id} sent nothing within 5 seconds")),
+    }
+}
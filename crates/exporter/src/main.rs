@@ -0,0 +1,116 @@
+//! Exports the numeric fields of watched kinds as Prometheus gauges.
+
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+
+use anyhow::Context as _;
+use axum::extract::State as StateExt;
+use axum::http::header;
+use axum::response::IntoResponse;
+use axum::routing::get;
+use axum::Router;
+use clap::Parser;
+use futures::StreamExt;
+use webconsole_client::protocol::ObjectDef;
+use webconsole_client::{select_kinds, Client, ObjectStore, Retrying, Selection};
+
+mod metrics;
+
+#[derive(Parser)]
+#[command(version, about)]
+struct Args {
+    /// Base URL of the API server.
+    #[arg(long, env = "WEBCONSOLE_SERVER", default_value = "http://localhost:14875")]
+    server:  String,
+    /// Extra request header in the form `Name: value`, e.g. for an authenticating proxy.
+    #[arg(long = "header", short = 'H')]
+    headers: Vec<String>,
+    /// Only export these kinds, in the form `group/kind`. All kinds are exported if unspecified.
+    #[arg(long = "kind")]
+    kinds:   Vec<String>,
+    /// Address to serve `/metrics` on.
+    #[arg(long, default_value = "127.0.0.1:9775")]
+    listen:  SocketAddr,
+    /// Prefix of all metric names.
+    #[arg(long, default_value = "webconsole")]
+    prefix:  String,
+}
+
+struct Kind {
+    def:   ObjectDef,
+    store: ObjectStore,
+    /// Whether the watch is open, in which case `store` is up to date.
+    up:    bool,
+}
+
+struct State {
+    prefix: String,
+    kinds:  Vec<Mutex<Kind>>,
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+
+    let mut client = Client::new(&args.server);
+    for header in &args.headers {
+        client = client.with_header_line(header)?;
+    }
+
+    let discovery = client.discovery().await.context("fetch discovery")?;
+    let defs = select_kinds(&discovery, &args.kinds)?;
+
+    let kinds = defs
+        .into_iter()
+        .map(|def| {
+            Mutex::new(Kind { def: def.clone(), store: ObjectStore::default(), up: false })
+        })
+        .collect();
+    let state = Arc::new(State { prefix: args.prefix, kinds });
+    for index in 0..state.kinds.len() {
+        tokio::spawn(watch(client.clone(), state.clone(), index));
+    }
+
+    let router = Router::new().route("/metrics", get(metrics)).with_state(state);
+    eprintln!("serving metrics on http://{}/metrics", args.listen);
+    axum::Server::try_bind(&args.listen)
+        .with_context(|| format!("bind {}", args.listen))?
+        .serve(router.into_make_service())
+        .await?;
+
+    Ok(())
+}
+
+/// Keeps the objects of a kind up to date, reopening the watch whenever it fails.
+async fn watch(client: Client, state: Arc<State>, index: usize) {
+    let kind = &state.kinds[index];
+    let (group, kind_name) = {
+        let kind = kind.lock().expect("kind lock is poisoned");
+        (kind.def.id.group.clone(), kind.def.id.kind.clone())
+    };
+    let mut events = client.watch_list_retrying(&group, &kind_name, Selection::default());
+
+    while let Some(item) = events.next().await {
+        let mut kind = kind.lock().expect("kind lock is poisoned");
+        match item {
+            Retrying::Event(event) => {
+                kind.up = true;
+                kind.store.apply(&event);
+            }
+            Retrying::Failed(err) => {
+                kind.up = false;
+                eprintln!("watch {group}/{kind_name}: {err}");
+            }
+        }
+    }
+}
+
+async fn metrics(StateExt(state): StateExt<Arc<State>>) -> impl IntoResponse {
+    let mut exposition = metrics::Exposition::new(&state.prefix);
+    for kind in &state.kinds {
+        let kind = kind.lock().expect("kind lock is poisoned");
+        exposition.add_kind(&kind.def, &kind.store, kind.up);
+    }
+
+    ([(header::CONTENT_TYPE, "text/plain; version=0.0.4")], exposition.render())
+}
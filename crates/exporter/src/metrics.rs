@@ -0,0 +1,266 @@
+//! Renders the watched objects in the Prometheus text exposition format.
+//!
+//! Each numeric field becomes a gauge named `<prefix>_field_<field path>`,
+//! with the group, kind and object name in the `group`, `kind` and `name` labels.
+//! Bools are exported as 0 or 1, and enums as one sample per option
+//! with the option ID in the `option` label, of which only the current one is 1.
+//! Compound fields are flattened into one gauge per numeric subfield.
+//! Other types, and null values, are not exported.
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+
+use serde_json::Value;
+use webconsole_client::protocol::{get_json_path, FieldType, ObjectDef};
+use webconsole_client::ObjectStore;
+
+/// The samples of one metric family.
+struct Family {
+    help:    String,
+    samples: Vec<(Vec<(&'static str, String)>, f64)>,
+}
+
+pub struct Exposition {
+    prefix:   String,
+    families: BTreeMap<String, Family>,
+}
+
+impl Exposition {
+    pub fn new(prefix: &str) -> Self {
+        Self { prefix: sanitize(prefix), families: BTreeMap::new() }
+    }
+
+    fn sample(
+        &mut self,
+        name: &str,
+        help: impl FnOnce() -> String,
+        labels: Vec<(&'static str, String)>,
+        value: f64,
+    ) {
+        let name = format!("{}_{}", self.prefix, sanitize(name));
+        let family = self
+            .families
+            .entry(name)
+            .or_insert_with(|| Family { help: help(), samples: Vec::new() });
+        family.samples.push((labels, value));
+    }
+
+    /// Adds the gauges of a kind and its object count.
+    ///
+    /// `up` tells whether the watch is currently open and the objects are up to date.
+    pub fn add_kind(&mut self, def: &ObjectDef, store: &ObjectStore, up: bool) {
+        let (group, kind) = (&def.id.group, &def.id.kind);
+        let kind_labels = || vec![("group", group.clone()), ("kind", kind.clone())];
+
+        self.sample(
+            "objects",
+            || String::from("Number of objects of a kind."),
+            kind_labels(),
+            store.len() as f64,
+        );
+        self.sample(
+            "watch_up",
+            || String::from("Whether the watch of a kind is connected."),
+            kind_labels(),
+            if up { 1. } else { 0. },
+        );
+
+        for field in def.fields.values() {
+            // kinds with the same field share the family, which is told apart by the labels
+            let metric = format!("field_{}", field.path);
+            let help = format!("Field {} of an object.", field.path);
+            for object in store.objects().values() {
+                let Some(value) = get_json_path(&object.fields, &field.path) else { continue };
+                let mut labels = kind_labels();
+                labels.push(("name", object.name.clone()));
+                self.add_value(&metric, &help, &labels, &field.ty, value);
+            }
+        }
+    }
+
+    fn add_value(
+        &mut self,
+        metric: &str,
+        help: &str,
+        labels: &[(&'static str, String)],
+        ty: &FieldType,
+        value: &Value,
+    ) {
+        match ty {
+            FieldType::Int64 { is_timestamp: true, .. }
+            | FieldType::Float64 { is_timestamp: true, .. } => {
+                // timestamps are in microseconds like on the site, but Prometheus expects seconds
+                if let Some(micros) = value.as_f64() {
+                    let metric = format!("{metric}_timestamp_seconds");
+                    self.sample(&metric, || help.to_string(), labels.to_vec(), micros / 1e6);
+                }
+            }
+            FieldType::Int64 { .. } | FieldType::Float64 { .. } => {
+                if let Some(number) = value.as_f64() {
+                    self.sample(metric, || help.to_string(), labels.to_vec(), number);
+                }
+            }
+            FieldType::Bool {} => {
+                if let Some(bool) = value.as_bool() {
+                    self.sample(
+                        metric,
+                        || help.to_string(),
+                        labels.to_vec(),
+                        if bool { 1. } else { 0. },
+                    );
+                }
+            }
+            FieldType::Enum { options } => {
+                let Some(current) = value.as_str() else { return };
+                for option in options.values() {
+                    let mut labels = labels.to_vec();
+                    labels.push(("option", option.id.clone()));
+                    let value = if option.id == current { 1. } else { 0. };
+                    self.sample(metric, || help.to_string(), labels, value);
+                }
+            }
+            FieldType::Nullable { item } => self.add_value(metric, help, labels, item, value),
+            FieldType::Compound { fields } => {
+                let Value::Object(map) = value else { return };
+                for field in fields.values() {
+                    if let Some(value) = map.get(&field.key) {
+                        let metric = format!("{metric}_{}", field.key);
+                        self.add_value(&metric, help, labels, &field.ty, value);
+                    }
+                }
+            }
+            FieldType::String {} | FieldType::Object { .. } | FieldType::List { .. } => {}
+        }
+    }
+
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        for (name, family) in &self.families {
+            _ = writeln!(out, "# HELP {name} {}", escape_help(&family.help));
+            _ = writeln!(out, "# TYPE {name} gauge");
+            for (labels, value) in &family.samples {
+                let labels: Vec<_> = labels
+                    .iter()
+                    .map(|(key, value)| format!("{key}=\"{}\"", escape_label(value)))
+                    .collect();
+                _ = writeln!(out, "{name}{{{}}} {}", labels.join(","), format_value(*value));
+            }
+        }
+        out
+    }
+}
+
+/// Replaces the characters that are not allowed in metric names.
+fn sanitize(name: &str) -> String {
+    let mut sanitized: String = name
+        .chars()
+        .map(|char| if char.is_ascii_alphanumeric() || char == '_' { char } else { '_' })
+        .collect();
+    if sanitized.starts_with(|char: char| char.is_ascii_digit()) {
+        sanitized.insert(0, '_');
+    }
+    sanitized
+}
+
+fn escape_help(help: &str) -> String { help.replace('\\', "\\\\").replace('\n', "\\n") }
+
+fn escape_label(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+fn format_value(value: f64) -> String {
+    if value.is_nan() {
+        String::from("NaN")
+    } else if value.is_infinite() {
+        String::from(if value > 0. { "+Inf" } else { "-Inf" })
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use webconsole_client::protocol::{Object, WatchListEvent};
+
+    use super::*;
+
+    fn kind(group: &str, kind: &str, fields: Value, objects: &[(&str, Value)]) -> String {
+        let def: ObjectDef = serde_json::from_value(json!({
+            "group": group,
+            "kind": kind,
+            "display_name": kind,
+            "fields": fields,
+        }))
+        .unwrap();
+        let mut store = ObjectStore::default();
+        for (name, fields) in objects {
+            let item = Object { name: name.to_string(), fields: fields.clone() };
+            store.apply(&WatchListEvent::Added { item });
+        }
+        let mut exposition = Exposition::new("wc");
+        exposition.add_kind(&def, &store, true);
+        exposition.render()
+    }
+
+    #[test]
+    fn enum_one_hot() {
+        let fields = json!([{
+            "path": "mode",
+            "display_name": "Mode",
+            "type": {
+                "type": "enum",
+                "options": [{ "id": "creative", "i18n": "c" }, { "id": "survival", "i18n": "s" }],
+            },
+        }]);
+        let out = kind("main", "player", fields, &[("alice", json!({ "mode": "survival" }))]);
+        assert!(out.contains(
+            "wc_field_mode{group=\"main\",kind=\"player\",name=\"alice\",option=\"creative\"} \
+             0\nwc_field_mode{group=\"main\",kind=\"player\",name=\"alice\",option=\"survival\"} \
+             1\n"
+        ));
+    }
+
+    #[test]
+    fn timestamps_in_seconds() {
+        let fields = json!([
+            { "path": "seen", "display_name": "Seen", "type": { "type": "int64", "is_timestamp": true } },
+            { "path": "pos", "display_name": "Pos", "type": { "type": "compound", "fields": [
+                { "key": "x", "name": "X", "type": { "type": "float64" } },
+            ] } },
+        ]);
+        let objects = [("alice", json!({ "seen": 1_500_000, "pos": { "x": 2.5 } }))];
+        assert_eq!(
+            kind("main", "player", fields, &objects),
+            "# HELP wc_field_pos_x Field pos of an object.\n# TYPE wc_field_pos_x \
+             gauge\nwc_field_pos_x{group=\"main\",kind=\"player\",name=\"alice\"} 2.5\n# HELP \
+             wc_field_seen_timestamp_seconds Field seen of an object.\n# TYPE \
+             wc_field_seen_timestamp_seconds \
+             gauge\nwc_field_seen_timestamp_seconds{group=\"main\",kind=\"player\",name=\"alice\"\
+             } 1.5\n# HELP wc_objects Number of objects of a kind.\n# TYPE wc_objects \
+             gauge\nwc_objects{group=\"main\",kind=\"player\"} 1\n# HELP wc_watch_up Whether the \
+             watch of a kind is connected.\n# TYPE wc_watch_up \
+             gauge\nwc_watch_up{group=\"main\",kind=\"player\"} 1\n"
+        );
+    }
+
+    #[test]
+    fn escaping() {
+        let fields = json!([
+            { "path": "a-b\nc", "display_name": "x", "type": { "type": "bool" } },
+        ]);
+        let out = kind("a\\b", "k\"", fields, &[("line\nbreak", json!({ "a-b\nc": true }))]);
+        assert!(out.contains("# HELP wc_field_a_b_c Field a-b\\nc of an object.\n"));
+        assert!(out
+            .contains("wc_field_a_b_c{group=\"a\\\\b\",kind=\"k\\\"\",name=\"line\\nbreak\"} 1\n"));
+    }
+
+    #[test]
+    fn similar_kinds_have_distinct_series() {
+        let fields = json!([{ "path": "hp", "display_name": "HP", "type": { "type": "float64" } }]);
+        let a = kind("a-b", "c", fields.clone(), &[("x", json!({ "hp": 1 }))]);
+        let b = kind("a_b", "c", fields, &[("x", json!({ "hp": 1 }))]);
+        assert!(a.contains("wc_field_hp{group=\"a-b\",kind=\"c\",name=\"x\"} 1\n"));
+        assert!(b.contains("wc_field_hp{group=\"a_b\",kind=\"c\",name=\"x\"} 1\n"));
+    }
+}
@@ -0,0 +1,232 @@
+//! The audit log of the requests through the gateway.
+//!
+//! Each request is logged as a JSON line in `audit.jsonl` once its response has been streamed,
+//! so a watch is logged when it ends, with the number of bytes sent to the client.
+//! When the file exceeds the size limit, it is renamed with the time of rotation
+//! and only the most recent rotated files are kept.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write as _};
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::thread;
+use std::time::Instant;
+
+use axum::body::{BoxBody, Bytes, HttpBody};
+use axum::http::{header, HeaderMap};
+use axum::response::Response;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
+
+use crate::config::Permission;
+
+pub const CURRENT_FILE: &str = "audit.jsonl";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Entry {
+    /// When the request was received.
+    pub time:        DateTime<Utc>,
+    /// Name of the authenticated client, if any.
+    pub client:      Option<String>,
+    pub method:      String,
+    /// Path and query string as requested.
+    pub path:        String,
+    /// The permission checked for object requests.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub access:      Option<Permission>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group:       Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kind:        Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name:        Option<String>,
+    /// The requested fields, or `None` if all fields were requested.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fields:      Option<Vec<String>>,
+    pub status:      u16,
+    pub duration_ms: f64,
+    /// Bytes of the response body sent to the client.
+    pub bytes:       u64,
+    /// Whether the whole response was sent, which is `false` for watches closed by the client.
+    pub complete:    bool,
+}
+
+impl Entry {
+    pub fn new(method: String, path: String) -> Self {
+        Self {
+            time: Utc::now(),
+            client: None,
+            method,
+            path,
+            access: None,
+            group: None,
+            kind: None,
+            name: None,
+            fields: None,
+            status: 0,
+            duration_ms: 0.,
+            bytes: 0,
+            complete: false,
+        }
+    }
+}
+
+/// Writes entries on a separate thread.
+#[derive(Clone)]
+pub struct Log {
+    entries: mpsc::UnboundedSender<Entry>,
+}
+
+impl Log {
+    pub fn open(dir: PathBuf, max_bytes: u64, keep: usize) -> io::Result<Self> {
+        fs::create_dir_all(&dir)?;
+        let mut writer = Writer::open(dir, max_bytes, keep)?;
+        let (entries, mut rx) = mpsc::unbounded_channel::<Entry>();
+        thread::spawn(move || {
+            while let Some(entry) = rx.blocking_recv() {
+                if let Err(err) = writer.write(&entry) {
+                    eprintln!("failed to write audit log: {err}");
+                }
+            }
+        });
+        Ok(Self { entries })
+    }
+
+    /// Logs the request once the response body has been sent or dropped.
+    pub fn wrap(&self, resp: Response, mut entry: Entry, start: Instant) -> Response {
+        entry.status = resp.status().as_u16();
+        let length = resp.headers().get(header::CONTENT_LENGTH);
+        let length = length.and_then(|length| length.to_str().ok()?.parse().ok());
+        resp.map(|inner| {
+            axum::body::boxed(Audited {
+                inner,
+                log: self.clone(),
+                entry: Some(entry),
+                start,
+                length,
+                complete: false,
+            })
+        })
+    }
+}
+
+struct Writer {
+    dir:       PathBuf,
+    max_bytes: u64,
+    keep:      usize,
+    file:      File,
+    size:      u64,
+}
+
+impl Writer {
+    fn open(dir: PathBuf, max_bytes: u64, keep: usize) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(dir.join(CURRENT_FILE))?;
+        let size = file.metadata()?.len();
+        Ok(Self { dir, max_bytes, keep, file, size })
+    }
+
+    fn write(&mut self, entry: &Entry) -> io::Result<()> {
+        let mut line = serde_json::to_vec(entry)?;
+        line.push(b'\n');
+        if self.size > 0 && self.size + line.len() as u64 > self.max_bytes {
+            self.rotate()?;
+        }
+        self.file.write_all(&line)?;
+        self.size += line.len() as u64;
+        Ok(())
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        let rotated = format!("audit-{}.jsonl", Utc::now().format("%Y%m%dT%H%M%S%.6fZ"));
+        fs::rename(self.dir.join(CURRENT_FILE), self.dir.join(rotated))?;
+        self.file =
+            OpenOptions::new().create(true).append(true).open(self.dir.join(CURRENT_FILE))?;
+        self.size = 0;
+
+        let files = files(&self.dir)?;
+        let rotated = &files[..files.len() - 1];
+        for old in &rotated[..rotated.len().saturating_sub(self.keep)] {
+            fs::remove_file(old)?;
+        }
+        Ok(())
+    }
+}
+
+/// The log files in a directory, oldest first.
+pub fn files(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut rotated = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let name = entry?.file_name();
+        let Some(name) = name.to_str() else { continue };
+        // the timestamp in the names sorts chronologically
+        if name.starts_with("audit-") && name.ends_with(".jsonl") {
+            rotated.push(dir.join(name));
+        }
+    }
+    rotated.sort();
+
+    let current = dir.join(CURRENT_FILE);
+    if current.exists() {
+        rotated.push(current);
+    }
+    Ok(rotated)
+}
+
+/// A response body that counts the bytes sent and logs the request when it is dropped.
+struct Audited {
+    inner:    BoxBody,
+    log:      Log,
+    entry:    Option<Entry>,
+    start:    Instant,
+    /// The `Content-Length` of the response, after which hyper stops polling the body.
+    length:   Option<u64>,
+    /// Whether the body returned its end.
+    complete: bool,
+}
+
+impl HttpBody for Audited {
+    type Data = Bytes;
+    type Error = axum::Error;
+
+    fn poll_data(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Bytes, axum::Error>>> {
+        let poll = Pin::new(&mut self.inner).poll_data(cx);
+        match &poll {
+            Poll::Ready(Some(Ok(data))) => {
+                let len = data.len() as u64;
+                if let Some(entry) = &mut self.entry {
+                    entry.bytes += len;
+                }
+            }
+            Poll::Ready(None) => self.complete = true,
+            Poll::Ready(Some(Err(_))) | Poll::Pending => {}
+        }
+        poll
+    }
+
+    fn poll_trailers(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<Option<HeaderMap>, axum::Error>> {
+        Pin::new(&mut self.inner).poll_trailers(cx)
+    }
+
+    fn is_end_stream(&self) -> bool { self.inner.is_end_stream() }
+
+    fn size_hint(&self) -> http_body::SizeHint { self.inner.size_hint() }
+}
+
+impl Drop for Audited {
+    fn drop(&mut self) {
+        if let Some(mut entry) = self.entry.take() {
+            entry.duration_ms = self.start.elapsed().as_secs_f64() * 1000.;
+            entry.complete =
+                self.complete || self.inner.is_end_stream() || self.length == Some(entry.bytes);
+            _ = self.log.entries.send(entry);
+        }
+    }
+}
@@ -0,0 +1,221 @@
+//! The clients allowed through the gateway and their permissions.
+//!
+//! ```toml
+//! [[clients]]
+//! name = "dashboard"
+//! token = "secret"
+//! # Kinds are matched as `group/kind`, `group/*` or `*`,
+//! # and a client has the permissions of all patterns matching a kind.
+//! kinds = { "main/player" = ["read", "watch"], "main/log-message" = ["watch"] }
+//!
+//! [[clients]]
+//! name = "admin"
+//! basic = { username = "admin", password = "hunter2" }
+//! kinds = { "*" = ["read", "watch", "write"] }
+//! ```
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use anyhow::Context as _;
+use base64::Engine as _;
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    pub clients: Vec<ClientConfig>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ClientConfig {
+    /// Identifies the client in logs.
+    pub name:  String,
+    /// Accepted in the `Authorization: Bearer <token>` header.
+    pub token: Option<String>,
+    /// Accepted in the `Authorization: Basic <credentials>` header.
+    pub basic: Option<BasicCredentials>,
+    #[serde(default)]
+    pub kinds: BTreeMap<String, Vec<Permission>>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BasicCredentials {
+    pub username: String,
+    pub password: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Permission {
+    /// List and get objects.
+    Read,
+    /// Watch objects.
+    Watch,
+    /// Any request other than `GET`.
+    Write,
+}
+
+impl Config {
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let source =
+            std::fs::read_to_string(path).with_context(|| format!("read {}", path.display()))?;
+        let config: Self =
+            toml::from_str(&source).with_context(|| format!("parse {}", path.display()))?;
+
+        for client in &config.clients {
+            anyhow::ensure!(
+                client.token.is_some() || client.basic.is_some(),
+                "client {:?} has neither a token nor basic credentials",
+                client.name,
+            );
+            for pattern in client.kinds.keys() {
+                anyhow::ensure!(
+                    pattern == "*" || pattern.split_once('/').is_some(),
+                    "invalid kind pattern {pattern:?} of client {:?}",
+                    client.name,
+                );
+            }
+        }
+
+        Ok(config)
+    }
+
+    /// Finds the client authenticated by the value of an `Authorization` header.
+    pub fn authenticate(&self, authorization: &str) -> Option<&ClientConfig> {
+        let (scheme, credentials) = authorization.split_once(' ')?;
+        let credentials = credentials.trim();
+        if scheme.eq_ignore_ascii_case("bearer") {
+            self.clients.iter().find(|client| {
+                client.token.as_ref().map_or(false, |token| secure_eq(token, credentials))
+            })
+        } else if scheme.eq_ignore_ascii_case("basic") {
+            let decoded = base64::engine::general_purpose::STANDARD.decode(credentials).ok()?;
+            let decoded = String::from_utf8(decoded).ok()?;
+            let (username, password) = decoded.split_once(':')?;
+            self.clients.iter().find(|client| {
+                client.basic.as_ref().map_or(false, |basic| {
+                    // evaluate both comparisons to not reveal which one failed
+                    secure_eq(&basic.username, username) & secure_eq(&basic.password, password)
+                })
+            })
+        } else {
+            None
+        }
+    }
+}
+
+impl ClientConfig {
+    pub fn permits(&self, group: &str, kind: &str, permission: Permission) -> bool {
+        self.kinds.iter().any(|(pattern, permissions)| {
+            let matches = match pattern.split_once('/') {
+                _ if pattern == "*" => true,
+                Some((pattern_group, "*")) => pattern_group == group,
+                Some((pattern_group, pattern_kind)) => {
+                    pattern_group == group && pattern_kind == kind
+                }
+                None => false,
+            };
+            matches && permissions.contains(&permission)
+        })
+    }
+
+    /// Whether the client may access the kind at all, in which case it is listed in discovery.
+    pub fn sees(&self, group: &str, kind: &str) -> bool {
+        [Permission::Read, Permission::Watch, Permission::Write]
+            .into_iter()
+            .any(|permission| self.permits(group, kind, permission))
+    }
+}
+
+/// Compares secrets in constant time for strings of the same length.
+fn secure_eq(expected: &str, actual: &str) -> bool {
+    expected.len() == actual.len()
+        && expected.bytes().zip(actual.bytes()).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> Config {
+        toml::from_str(
+            r#"
+            [[clients]]
+            name = "dashboard"
+            token = "secret"
+            kinds = { "main/player" = ["read", "watch"], "main/log-message" = ["watch"] }
+
+            [[clients]]
+            name = "admin"
+            basic = { username = "admin", password = "hunter2" }
+            kinds = { "*" = ["read", "watch", "write"] }
+
+            [[clients]]
+            name = "ops"
+            token = "ops-token"
+            kinds = { "ops/*" = ["read"] }
+            "#,
+        )
+        .unwrap()
+    }
+
+    fn authenticate<'t>(config: &'t Config, authorization: &str) -> Option<&'t str> {
+        config.authenticate(authorization).map(|client| client.name.as_str())
+    }
+
+    #[test]
+    fn authenticate_bearer() {
+        let config = config();
+        assert_eq!(authenticate(&config, "Bearer secret"), Some("dashboard"));
+        assert_eq!(authenticate(&config, "bearer  ops-token "), Some("ops"));
+        assert_eq!(authenticate(&config, "Bearer secre"), None);
+        assert_eq!(authenticate(&config, "Bearer "), None);
+        assert_eq!(authenticate(&config, "Token secret"), None);
+        assert_eq!(authenticate(&config, "secret"), None);
+    }
+
+    #[test]
+    fn authenticate_basic() {
+        let config = config();
+        let encode = |credentials: &str| {
+            format!("Basic {}", base64::engine::general_purpose::STANDARD.encode(credentials))
+        };
+        assert_eq!(authenticate(&config, &encode("admin:hunter2")), Some("admin"));
+        assert_eq!(authenticate(&config, &encode("admin:hunter3")), None);
+        assert_eq!(authenticate(&config, &encode("root:hunter2")), None);
+        assert_eq!(authenticate(&config, &encode("adminhunter2")), None);
+        assert_eq!(authenticate(&config, "Basic not-base64!"), None);
+        // a token is not accepted as basic credentials
+        assert_eq!(authenticate(&config, &encode("secret")), None);
+    }
+
+    #[test]
+    fn permits_patterns() {
+        let config = config();
+        let [dashboard, admin, ops] = &config.clients[..] else { panic!("three clients") };
+
+        assert!(dashboard.permits("main", "player", Permission::Read));
+        assert!(dashboard.permits("main", "player", Permission::Watch));
+        assert!(!dashboard.permits("main", "player", Permission::Write));
+        assert!(!dashboard.permits("main", "log-message", Permission::Read));
+        assert!(dashboard.permits("main", "log-message", Permission::Watch));
+        assert!(!dashboard.sees("main", "world"));
+
+        assert!(admin.permits("any", "kind", Permission::Write));
+
+        assert!(ops.permits("ops", "anything", Permission::Read));
+        assert!(!ops.permits("ops", "anything", Permission::Watch));
+        assert!(ops.sees("ops", "anything"));
+        assert!(!ops.sees("main", "ops"));
+    }
+
+    #[test]
+    fn secure_eq_compares_contents() {
+        assert!(secure_eq("abc", "abc"));
+        assert!(!secure_eq("abc", "abd"));
+        assert!(!secure_eq("abc", "ab"));
+    }
+}
@@ -0,0 +1,75 @@
+//! An authenticating reverse proxy that enforces per-kind ACLs in front of the API server.
+
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use anyhow::Context as _;
+use clap::Parser;
+
+mod audit;
+mod config;
+mod proxy;
+mod query;
+
+#[derive(Parser)]
+#[command(version, about)]
+enum Command {
+    /// Serves the gateway.
+    Serve(ServeArgs),
+    /// Searches the audit log.
+    ///
+    /// Responses that were not sent completely, such as watches closed by the client,
+    /// are marked with `*` after their size.
+    Audit(query::Args),
+}
+
+#[derive(clap::Args)]
+struct ServeArgs {
+    /// Path of the configuration file listing the clients and their permissions.
+    config:          PathBuf,
+    /// Base URL of the API server.
+    #[arg(long, env = "WEBCONSOLE_SERVER", default_value = "http://localhost:14875")]
+    server:          String,
+    /// Address to serve the gateway on.
+    #[arg(long, default_value = "127.0.0.1:14876")]
+    listen:          SocketAddr,
+    /// Directory of the audit log.
+    #[arg(long, default_value = "audit")]
+    audit_dir:       PathBuf,
+    /// Size in bytes above which the audit log is rotated.
+    #[arg(long, default_value_t = 16 << 20)]
+    audit_max_bytes: u64,
+    /// Number of rotated audit log files to keep.
+    #[arg(long, default_value_t = 10)]
+    audit_keep:      usize,
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    match Command::parse() {
+        Command::Serve(args) => serve(args).await,
+        Command::Audit(args) => query::run(&args),
+    }
+}
+
+async fn serve(args: ServeArgs) -> anyhow::Result<()> {
+    let config = config::Config::load(&args.config)?;
+    let audit = audit::Log::open(args.audit_dir.clone(), args.audit_max_bytes, args.audit_keep)
+        .with_context(|| format!("open audit log in {}", args.audit_dir.display()))?;
+
+    let gateway = Arc::new(proxy::Gateway {
+        config,
+        upstream: args.server.trim_end_matches('/').to_string(),
+        http: reqwest::Client::new(),
+        audit,
+    });
+
+    eprintln!("forwarding http://{} to {}", args.listen, gateway.upstream);
+    axum::Server::try_bind(&args.listen)
+        .with_context(|| format!("bind {}", args.listen))?
+        .serve(proxy::router(gateway).into_make_service())
+        .await?;
+
+    Ok(())
+}
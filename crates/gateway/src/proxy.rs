@@ -0,0 +1,313 @@
+//! Authenticates requests, checks them against the ACLs and forwards them to the API server.
+
+use std::sync::Arc;
+use std::time::Instant;
+
+use axum::body::Body;
+use axum::extract::State as StateExt;
+use axum::http::{header, HeaderMap, HeaderValue, Method, Request, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::Router;
+use tower_http::cors::{AllowHeaders, Any, CorsLayer};
+use webconsole_protocol::{object_path, parse_object_query};
+use webconsole_proxy::{copy_headers, error, stream_response, url_decode};
+
+use crate::audit;
+use crate::config::{ClientConfig, Config, Permission};
+
+pub struct Gateway {
+    pub config:   Config,
+    /// Base URL of the API server, without the trailing slash.
+    pub upstream: String,
+    pub http:     reqwest::Client,
+    pub audit:    audit::Log,
+}
+
+pub fn router(gateway: Arc<Gateway>) -> Router {
+    // a wildcard in `Access-Control-Allow-Headers` does not cover `Authorization`
+    let cors = CorsLayer::new()
+        .allow_origin(Any)
+        .allow_methods(Any)
+        .allow_headers(AllowHeaders::mirror_request());
+    Router::new().fallback(handle).layer(cors).with_state(gateway)
+}
+
+/// What a request accesses, decided the same way as the plugin routes requests.
+#[derive(Debug, PartialEq)]
+enum Target {
+    Discovery,
+    /// Locales, which every authenticated client may read.
+    Locales,
+    Kind {
+        group:      String,
+        kind:       String,
+        name:       Option<String>,
+        permission: Permission,
+        /// The requested fields, or `None` if all fields were requested.
+        fields:     Option<Vec<String>>,
+        /// The path and query string to forward.
+        ///
+        /// The query string of reads and watches is rebuilt from the parsed parameters,
+        /// so that the API server cannot interpret it differently from the gateway.
+        forward:    String,
+    },
+    /// A query string that cannot be parsed.
+    BadQuery(String),
+    NotFound,
+}
+
+impl Target {
+    /// Classifies a request by its url-decoded path and its raw path and query string.
+    fn of(method: &Method, path: &str, raw_path: &str, query: Option<&str>) -> Self {
+        if method == Method::GET {
+            if path == "/discovery" {
+                return Self::Discovery;
+            }
+            if path == "/locales" || path.ends_with(".ftl") {
+                return Self::Locales;
+            }
+        }
+
+        let mut parts = path.trim_start_matches('/').splitn(3, '/');
+        let (Some(group), Some(kind)) = (parts.next(), parts.next()) else { return Self::NotFound };
+        let (group, kind) = (group.to_string(), kind.to_string());
+        let name = parts.next().map(str::to_string);
+
+        if method != Method::GET {
+            let forward = match query {
+                Some(query) => format!("{raw_path}?{query}"),
+                None => raw_path.to_string(),
+            };
+            let permission = Permission::Write;
+            return Self::Kind { group, kind, name, permission, fields: None, forward };
+        }
+
+        let (watch, selection) = match parse_object_query(query.unwrap_or_default()) {
+            Ok(query) => query,
+            Err(err) => return Self::BadQuery(err.to_string()),
+        };
+        // also check the forms of `watch` that only PHP recognizes
+        let watch = watch || is_watch(query);
+        let permission = if watch { Permission::Watch } else { Permission::Read };
+        let forward = object_path(raw_path, watch, &selection);
+        Self::Kind { group, kind, name, permission, fields: selection.fields, forward }
+    }
+}
+
+/// Whether the query string contains the `watch` parameter, in any form that PHP accepts.
+///
+/// PHP strips leading spaces from parameter names and ignores any `[...]` suffix.
+fn is_watch(query: Option<&str>) -> bool {
+    query.unwrap_or_default().split('&').any(|pair| {
+        let key = url_decode(pair.split_once('=').map_or(pair, |(key, _)| key));
+        key.trim_start_matches(' ').split('[').next() == Some("watch")
+    })
+}
+
+async fn handle(StateExt(gateway): StateExt<Arc<Gateway>>, req: Request<Body>) -> Response {
+    let start = Instant::now();
+    let path = req.uri().path_and_query().map_or("/", |path| path.as_str());
+    let mut entry = audit::Entry::new(req.method().to_string(), path.to_string());
+    let resp = authorize(&gateway, req, &mut entry).await;
+    gateway.audit.wrap(resp, entry, start)
+}
+
+async fn authorize(gateway: &Gateway, req: Request<Body>, entry: &mut audit::Entry) -> Response {
+    let authorization =
+        req.headers().get(header::AUTHORIZATION).and_then(|value| value.to_str().ok());
+    let Some(client) = authorization.and_then(|value| gateway.config.authenticate(value)) else {
+        let mut resp = error(StatusCode::UNAUTHORIZED);
+        // not `Basic`, which would make browsers prompt for credentials instead of the site
+        let challenge = HeaderValue::from_static("Bearer realm=\"webconsole\"");
+        resp.headers_mut().insert(header::WWW_AUTHENTICATE, challenge);
+        return resp;
+    };
+    entry.client = Some(client.name.clone());
+
+    let path = url_decode(req.uri().path());
+    match Target::of(req.method(), &path, req.uri().path(), req.uri().query()) {
+        Target::Discovery => discovery(gateway, client, req).await,
+        Target::Locales => {
+            let path = req.uri().path_and_query().map_or("/", |path| path.as_str()).to_string();
+            forward(gateway, req, &path).await
+        }
+        Target::Kind { group, kind, name, permission, fields, forward: path } => {
+            entry.fields = fields;
+            entry.access = Some(permission);
+            entry.group = Some(group.clone());
+            entry.kind = Some(kind.clone());
+            entry.name = name;
+
+            if client.permits(&group, &kind, permission) {
+                forward(gateway, req, &path).await
+            } else if client.sees(&group, &kind) {
+                error(StatusCode::FORBIDDEN)
+            } else {
+                // do not reveal that the kind exists
+                error(StatusCode::NOT_FOUND)
+            }
+        }
+        Target::BadQuery(message) => (StatusCode::BAD_REQUEST, message).into_response(),
+        Target::NotFound => error(StatusCode::NOT_FOUND),
+    }
+}
+
+/// Sends a request to `path` on the API server with the method, headers and body of `req`.
+async fn send(
+    gateway: &Gateway,
+    req: Request<Body>,
+    path: &str,
+) -> Result<reqwest::Response, Response> {
+    let mut headers = HeaderMap::new();
+    copy_headers(req.headers(), &mut headers);
+
+    let upstream = gateway
+        .http
+        .request(req.method().clone(), format!("{}{path}", gateway.upstream))
+        .headers(headers)
+        .body(reqwest::Body::wrap_stream(req.into_body()));
+    upstream.send().await.map_err(|err| {
+        (StatusCode::BAD_GATEWAY, format!("cannot reach the API server: {err}")).into_response()
+    })
+}
+
+/// Streams the response of the API server, which is necessary for watches.
+async fn forward(gateway: &Gateway, req: Request<Body>, path: &str) -> Response {
+    let upstream = match send(gateway, req, path).await {
+        Ok(upstream) => upstream,
+        Err(resp) => return resp,
+    };
+
+    stream_response(upstream)
+}
+
+/// Forwards the discovery document with only the kinds that the client can access.
+///
+/// The WebSocket transport is not advertised since the gateway does not filter it.
+async fn discovery(gateway: &Gateway, client: &ClientConfig, req: Request<Body>) -> Response {
+    let upstream = match send(gateway, req, "/discovery").await {
+        Ok(upstream) => upstream,
+        Err(resp) => return resp,
+    };
+    if !upstream.status().is_success() {
+        return error(StatusCode::BAD_GATEWAY);
+    }
+    let body = match upstream.bytes().await {
+        Ok(body) => body,
+        Err(err) => {
+            return (StatusCode::BAD_GATEWAY, format!("cannot read discovery: {err}"))
+                .into_response();
+        }
+    };
+    let mut discovery: serde_json::Value = match serde_json::from_slice(&body) {
+        Ok(discovery) => discovery,
+        Err(err) => {
+            return (StatusCode::BAD_GATEWAY, format!("invalid discovery document: {err}"))
+                .into_response();
+        }
+    };
+
+    // filter the JSON instead of `Discovery` to keep fields unknown to this version
+    let str_field = |value: &serde_json::Value, key| {
+        value.get(key).and_then(|value| value.as_str()).unwrap_or_default().to_string()
+    };
+    let mut groups = Vec::new();
+    if let Some(apis) = discovery.get_mut("apis").and_then(|apis| apis.as_array_mut()) {
+        apis.retain(|api| client.sees(&str_field(api, "group"), &str_field(api, "kind")));
+        groups.extend(apis.iter().map(|api| str_field(api, "group")));
+    }
+    if let Some(all) = discovery.get_mut("groups").and_then(|groups| groups.as_array_mut()) {
+        all.retain(|group| groups.contains(&str_field(group, "id")));
+    }
+    if let Some(transports) = discovery.get_mut("transports").and_then(|t| t.as_object_mut()) {
+        transports.remove("websocket");
+    }
+
+    axum::Json(discovery).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn target(method: Method, path_and_query: &str) -> Target {
+        let (raw_path, query) = match path_and_query.split_once('?') {
+            Some((path, query)) => (path, Some(query)),
+            None => (path_and_query, None),
+        };
+        Target::of(&method, &url_decode(raw_path), raw_path, query)
+    }
+
+    fn kind(permission: Permission, fields: Option<&[&str]>, forward: &str) -> Target {
+        Target::Kind {
+            group: "main".into(),
+            kind: "player".into(),
+            name: None,
+            permission,
+            fields: fields.map(|fields| fields.iter().map(|field| field.to_string()).collect()),
+            forward: forward.into(),
+        }
+    }
+
+    #[test]
+    fn target_of_discovery_and_locales() {
+        assert_eq!(target(Method::GET, "/discovery"), Target::Discovery);
+        assert_eq!(target(Method::GET, "/locales"), Target::Locales);
+        assert_eq!(target(Method::GET, "/en.ftl"), Target::Locales);
+        assert_eq!(target(Method::GET, "/main"), Target::NotFound);
+    }
+
+    #[test]
+    fn target_of_read() {
+        assert_eq!(
+            target(Method::GET, "/main/player"),
+            kind(Permission::Read, None, "/main/player")
+        );
+        assert_eq!(
+            target(Method::GET, "/main/player?fields[]=entity.health&limit=2"),
+            kind(
+                Permission::Read,
+                Some(&["entity.health"]),
+                "/main/player?fields[0]=entity.health&limit=2",
+            ),
+        );
+        assert!(matches!(target(Method::GET, "/main/player?limit=x"), Target::BadQuery(_)));
+    }
+
+    #[test]
+    fn target_of_watch() {
+        let watch = kind(Permission::Watch, None, "/main/player?watch=true");
+        for query in ["watch", "watch=", "watch=1", "watch[]=1", "+watch=1", "%20watch=1"] {
+            assert_eq!(target(Method::GET, &format!("/main/player?{query}")), watch, "{query}");
+        }
+    }
+
+    #[test]
+    fn target_of_single_object() {
+        let Target::Kind { name, forward, .. } = target(Method::GET, "/main/player/a%20b?watch")
+        else {
+            panic!("not a kind");
+        };
+        assert_eq!(name.as_deref(), Some("a b"));
+        assert_eq!(forward, "/main/player/a%20b?watch=true");
+    }
+
+    #[test]
+    fn target_of_write() {
+        assert_eq!(
+            target(Method::POST, "/main/player?watch"),
+            kind(Permission::Write, None, "/main/player?watch"),
+        );
+    }
+
+    #[test]
+    fn is_watch_accepts_php_forms() {
+        for query in ["watch", "a=1&watch=0", "watch[x]=1", "+watch", "%20%20watch=1"] {
+            assert!(is_watch(Some(query)), "{query}");
+        }
+        for query in ["", "watches", "x=watch", "wat%63h2", "_watch"] {
+            assert!(!is_watch(Some(query)), "{query}");
+        }
+        assert!(!is_watch(None));
+    }
+}
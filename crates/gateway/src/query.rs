@@ -0,0 +1,89 @@
+//! Searches the audit log.
+
+use std::fs::File;
+use std::io::{BufRead as _, BufReader};
+use std::path::PathBuf;
+
+use anyhow::Context as _;
+use chrono::{DateTime, Utc};
+
+use crate::audit::{self, Entry};
+
+#[derive(clap::Args)]
+pub struct Args {
+    /// Directory of the audit log.
+    #[arg(long, default_value = "audit")]
+    audit_dir: PathBuf,
+    /// Only show requests of this client.
+    #[arg(long)]
+    client:    Option<String>,
+    /// Only show requests of an object or kind, in the form `group/kind` or `group/kind/name`.
+    #[arg(long)]
+    object:    Option<String>,
+    /// Only show requests received at or after this RFC 3339 time.
+    #[arg(long)]
+    since:     Option<DateTime<Utc>>,
+    /// Only show requests received before this RFC 3339 time.
+    #[arg(long)]
+    until:     Option<DateTime<Utc>>,
+    /// Print the matching entries as JSON lines instead of a table.
+    #[arg(long)]
+    json:      bool,
+}
+
+impl Args {
+    fn matches(&self, entry: &Entry) -> bool {
+        if let Some(client) = &self.client {
+            if entry.client.as_ref() != Some(client) {
+                return false;
+            }
+        }
+        if let Some(object) = &self.object {
+            let mut parts = object.splitn(3, '/');
+            let expected = [&entry.group, &entry.kind, &entry.name];
+            if !expected.iter().all(|actual| match parts.next() {
+                Some(part) => actual.as_deref() == Some(part),
+                None => true,
+            }) {
+                return false;
+            }
+        }
+        self.since.map_or(true, |since| entry.time >= since)
+            && self.until.map_or(true, |until| entry.time < until)
+    }
+}
+
+pub fn run(args: &Args) -> anyhow::Result<()> {
+    let files = audit::files(&args.audit_dir)
+        .with_context(|| format!("read {}", args.audit_dir.display()))?;
+
+    for path in files {
+        let file = File::open(&path).with_context(|| format!("open {}", path.display()))?;
+        for (number, line) in BufReader::new(file).lines().enumerate() {
+            let line = line.with_context(|| format!("read {}", path.display()))?;
+            let entry: Entry = serde_json::from_str(&line)
+                .with_context(|| format!("parse {}:{}", path.display(), number + 1))?;
+            if !args.matches(&entry) {
+                continue;
+            }
+
+            if args.json {
+                println!("{line}");
+            } else {
+                println!(
+                    "{}  {:<16} {:<6} {:>3} {:>9.1}ms {:>9}B{}  {}",
+                    entry.time.format("%Y-%m-%d %H:%M:%S%.3f"),
+                    entry.client.as_deref().unwrap_or("-"),
+                    entry.method,
+                    entry.status,
+                    entry.duration_ms,
+                    entry.bytes,
+                    if entry.complete { " " } else { "*" },
+                    entry.path,
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
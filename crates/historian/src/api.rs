@@ -0,0 +1,124 @@
+//! The query API.
+//!
+//! - `GET /changes/{group}/{kind}` lists the recorded changes of a kind,
+//!   optionally filtered by `object` and `event`.
+//! - `GET /series/{group}/{kind}/{name}?field=...` returns the values of a field of an object.
+//!
+//! Both accept `from` and `to` as RFC 3339 dates or microseconds since the Unix epoch,
+//! defaulting to the whole history.
+
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use axum::extract::{Path, Query, State as StateExt};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use serde::Deserialize;
+
+use crate::db::{self, EventType};
+
+/// The default and maximum number of changes returned by `/changes`.
+const MAX_CHANGES: u32 = 10000;
+
+pub fn router(database: PathBuf) -> Router {
+    Router::new()
+        .route("/changes/:group/:kind", get(changes))
+        .route("/series/:group/:kind/:name", get(series))
+        .with_state(Arc::new(database))
+}
+
+fn parse_range(from: Option<&str>, to: Option<&str>) -> Result<(i64, i64), Response> {
+    let from = from.map(parse_time).transpose()?.unwrap_or(i64::MIN);
+    let to = to.map(parse_time).transpose()?.unwrap_or(i64::MAX);
+    Ok((from, to))
+}
+
+fn parse_time(str: &str) -> Result<i64, Response> {
+    if let Ok(micros) = str.parse() {
+        return Ok(micros);
+    }
+    match chrono::DateTime::parse_from_rfc3339(str) {
+        Ok(time) => Ok(time.timestamp_micros()),
+        Err(err) => Err(bad_request(format!("invalid time {str:?}: {err}"))),
+    }
+}
+
+fn bad_request(message: String) -> Response { (StatusCode::BAD_REQUEST, message).into_response() }
+
+/// Runs a query on a new read-only connection without blocking the runtime.
+async fn query<T: serde::Serialize + Send + 'static>(
+    database: Arc<PathBuf>,
+    f: impl FnOnce(&rusqlite::Connection) -> rusqlite::Result<T> + Send + 'static,
+) -> Response {
+    let result = tokio::task::spawn_blocking(move || f(&db::open_reader(&database)?)).await;
+    match result {
+        Ok(Ok(value)) => Json(value).into_response(),
+        Ok(Err(err)) => (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()).into_response(),
+        Err(err) => (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()).into_response(),
+    }
+}
+
+#[derive(Deserialize)]
+struct ChangesQuery {
+    from:   Option<String>,
+    to:     Option<String>,
+    object: Option<String>,
+    event:  Option<String>,
+    limit:  Option<u32>,
+}
+
+async fn changes(
+    StateExt(database): StateExt<Arc<PathBuf>>,
+    Path((group, kind)): Path<(String, String)>,
+    Query(params): Query<ChangesQuery>,
+) -> Response {
+    let (from, to) = match parse_range(params.from.as_deref(), params.to.as_deref()) {
+        Ok(range) => range,
+        Err(resp) => return resp,
+    };
+    let event = match params.event.as_deref() {
+        Some(name) => match EventType::parse(name) {
+            Some(event) => Some(event),
+            None => return bad_request(format!("unknown event {name:?}")),
+        },
+        None => None,
+    };
+    let limit = params.limit.unwrap_or(MAX_CHANGES).min(MAX_CHANGES);
+
+    query(database, move |conn| {
+        let filter = db::Filter {
+            group: &group,
+            kind: &kind,
+            object: params.object.as_deref(),
+            event,
+            from,
+            to,
+            limit,
+        };
+        db::changes(conn, &filter)
+    })
+    .await
+}
+
+#[derive(Deserialize)]
+struct SeriesQuery {
+    from:  Option<String>,
+    to:    Option<String>,
+    field: String,
+}
+
+async fn series(
+    StateExt(database): StateExt<Arc<PathBuf>>,
+    Path((group, kind, name)): Path<(String, String, String)>,
+    Query(params): Query<SeriesQuery>,
+) -> Response {
+    let (from, to) = match parse_range(params.from.as_deref(), params.to.as_deref()) {
+        Ok(range) => range,
+        Err(resp) => return resp,
+    };
+
+    query(database, move |conn| db::series(conn, &group, &kind, &name, &params.field, from, to))
+        .await
+}
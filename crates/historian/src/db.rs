@@ -0,0 +1,307 @@
+//! Storage of the recorded changes in SQLite.
+//!
+//! Every change is a row of the `changes` table:
+//!
+//! | `event`        | `object` | `field`    | `value`                      |
+//! |----------------|----------|------------|------------------------------|
+//! | `added`        | name     | `NULL`     | all fields of the object     |
+//! | `update`       | name     | field path | the new value of the field   |
+//! | `removed`      | name     | `NULL`     | `NULL`                       |
+//! | `clear`        | `NULL`   | `NULL`     | `NULL`                       |
+//! | `disconnected` | `NULL`   | `NULL`     | `NULL`                       |
+//! | `resumed`      | `NULL`   | `NULL`     | `NULL`                       |
+//!
+//! `clear` means that the server restarted the watch and re-adds all objects.
+//! `disconnected` means that the watch failed and is being reopened,
+//! and `resumed` that the server replayed the changes missed since then,
+//! so the fields keep their values across the gap.
+//! If the watch cannot be resumed, `clear` follows instead.
+
+use std::path::Path;
+
+use rusqlite::{params, Connection, OpenFlags, OptionalExtension, Row};
+use serde::Serialize;
+use serde_json::Value;
+use webconsole_client::protocol::get_json_path;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS changes (
+    time   INTEGER NOT NULL,
+    grp    TEXT NOT NULL,
+    kind   TEXT NOT NULL,
+    object TEXT,
+    event  TEXT NOT NULL,
+    field  TEXT,
+    value  TEXT
+);
+CREATE INDEX IF NOT EXISTS changes_object ON changes (grp, kind, object, time);
+CREATE INDEX IF NOT EXISTS changes_time ON changes (grp, kind, time);
+";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventType {
+    Added,
+    Update,
+    Removed,
+    Clear,
+    Disconnected,
+    Resumed,
+}
+
+impl EventType {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Added => "added",
+            Self::Update => "update",
+            Self::Removed => "removed",
+            Self::Clear => "clear",
+            Self::Disconnected => "disconnected",
+            Self::Resumed => "resumed",
+        }
+    }
+
+    pub fn parse(str: &str) -> Option<Self> {
+        [Self::Added, Self::Update, Self::Removed, Self::Clear, Self::Disconnected, Self::Resumed]
+            .into_iter()
+            .find(|ty| ty.as_str() == str)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Change {
+    /// Microseconds since the Unix epoch when the historian received the change.
+    pub time:   i64,
+    pub group:  String,
+    pub kind:   String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub object: Option<String>,
+    pub event:  EventType,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub field:  Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value:  Option<Value>,
+}
+
+impl Change {
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        let event: String = row.get("event")?;
+        let value: Option<String> = row.get("value")?;
+        Ok(Self {
+            time:   row.get("time")?,
+            group:  row.get("grp")?,
+            kind:   row.get("kind")?,
+            object: row.get("object")?,
+            event:  EventType::parse(&event).unwrap_or(EventType::Disconnected),
+            field:  row.get("field")?,
+            value:  value.and_then(|value| serde_json::from_str(&value).ok()),
+        })
+    }
+
+    /// The value of a field right after this change,
+    /// or `None` if the change does not affect the field.
+    fn field_value(&self, field: &str) -> Option<Value> {
+        match self.event {
+            EventType::Added => Some(
+                self.value
+                    .as_ref()
+                    .and_then(|fields| get_json_path(fields, field))
+                    .cloned()
+                    .unwrap_or(Value::Null),
+            ),
+            EventType::Update if self.field.as_deref() == Some(field) => {
+                Some(self.value.clone().unwrap_or(Value::Null))
+            }
+            EventType::Update | EventType::Disconnected | EventType::Resumed => None,
+            EventType::Removed | EventType::Clear => Some(Value::Null),
+        }
+    }
+}
+
+pub fn open_writer(path: &Path) -> rusqlite::Result<Connection> {
+    let conn = Connection::open(path)?;
+    // WAL lets the query API read while changes are being written
+    conn.pragma_update(None, "journal_mode", "WAL")?;
+    conn.pragma_update(None, "synchronous", "NORMAL")?;
+    conn.execute_batch(SCHEMA)?;
+    Ok(conn)
+}
+
+pub fn open_reader(path: &Path) -> rusqlite::Result<Connection> {
+    Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+}
+
+/// Writes a batch of changes in one transaction.
+pub fn insert(conn: &mut Connection, changes: &[Change]) -> rusqlite::Result<()> {
+    let tx = conn.transaction()?;
+    {
+        let mut stmt = tx.prepare_cached(
+            "INSERT INTO changes (time, grp, kind, object, event, field, value)
+             VALUES (?, ?, ?, ?, ?, ?, ?)",
+        )?;
+        for change in changes {
+            stmt.execute(params![
+                change.time,
+                change.group,
+                change.kind,
+                change.object,
+                change.event.as_str(),
+                change.field,
+                change.value.as_ref().map(Value::to_string),
+            ])?;
+        }
+    }
+    tx.commit()
+}
+
+/// Selects the changes of a kind in a time range.
+pub struct Filter<'a> {
+    pub group:  &'a str,
+    pub kind:   &'a str,
+    /// Only changes of this object and changes of the whole kind.
+    pub object: Option<&'a str>,
+    pub event:  Option<EventType>,
+    pub from:   i64,
+    pub to:     i64,
+    pub limit:  u32,
+}
+
+pub fn changes(conn: &Connection, filter: &Filter) -> rusqlite::Result<Vec<Change>> {
+    let mut stmt = conn.prepare_cached(
+        "SELECT * FROM changes
+         WHERE grp = ?1 AND kind = ?2
+             AND (?3 IS NULL OR object = ?3 OR object IS NULL)
+             AND (?4 IS NULL OR event = ?4)
+             AND time >= ?5 AND time <= ?6
+         ORDER BY time, rowid
+         LIMIT ?7",
+    )?;
+    let rows = stmt.query_map(
+        params![
+            filter.group,
+            filter.kind,
+            filter.object,
+            filter.event.map(EventType::as_str),
+            filter.from,
+            filter.to,
+            filter.limit,
+        ],
+        Change::from_row,
+    )?;
+    rows.collect()
+}
+
+#[derive(Debug, Serialize)]
+pub struct Point {
+    pub time:  i64,
+    pub event: EventType,
+    /// `null` if the object does not exist or the value is unknown after this change.
+    pub value: Value,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Series {
+    /// The value of the field at the start of the range.
+    pub initial: Value,
+    pub points:  Vec<Point>,
+}
+
+/// The values of a field of an object in a time range.
+pub fn series(
+    conn: &Connection,
+    group: &str,
+    kind: &str,
+    object: &str,
+    field: &str,
+    from: i64,
+    to: i64,
+) -> rusqlite::Result<Series> {
+    // the last change that set the field before the range,
+    // skipping reconnections which leave the field unchanged
+    let mut stmt = conn.prepare_cached(
+        "SELECT * FROM changes
+         WHERE grp = ?1 AND kind = ?2 AND time < ?5
+             AND (object = ?3 AND (field IS NULL OR field = ?4) OR object IS NULL)
+             AND event NOT IN ('disconnected', 'resumed')
+         ORDER BY time DESC, rowid DESC
+         LIMIT 1",
+    )?;
+    let initial = stmt
+        .query_row(params![group, kind, object, field, from], Change::from_row)
+        .optional()?
+        .and_then(|change| change.field_value(field))
+        .unwrap_or(Value::Null);
+
+    let mut stmt = conn.prepare_cached(
+        "SELECT * FROM changes
+         WHERE grp = ?1 AND kind = ?2 AND time >= ?5 AND time <= ?6
+             AND (object = ?3 AND (field IS NULL OR field = ?4) OR object IS NULL)
+         ORDER BY time, rowid",
+    )?;
+    let points = stmt
+        .query_map(params![group, kind, object, field, from, to], Change::from_row)?
+        .filter_map(|change| match change {
+            Ok(change) => change
+                .field_value(field)
+                .map(|value| Ok(Point { time: change.time, event: change.event, value })),
+            Err(err) => Some(Err(err)),
+        })
+        .collect::<rusqlite::Result<_>>()?;
+
+    Ok(Series { initial, points })
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn change(time: i64, object: Option<&str>, event: EventType, value: Option<Value>) -> Change {
+        Change {
+            time,
+            group: "main".into(),
+            kind: "player".into(),
+            object: object.map(str::to_string),
+            event,
+            field: None,
+            value,
+        }
+    }
+
+    fn database(changes: &[Change]) -> Connection {
+        let mut conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(SCHEMA).unwrap();
+        insert(&mut conn, changes).unwrap();
+        conn
+    }
+
+    #[test]
+    fn resumed_watch_keeps_values() {
+        let conn = database(&[
+            change(1, Some("alice"), EventType::Added, Some(json!({ "world": "lobby" }))),
+            change(2, None, EventType::Disconnected, None),
+            change(3, None, EventType::Resumed, None),
+        ]);
+
+        let values = series(&conn, "main", "player", "alice", "world", 0, 10).unwrap();
+        assert_eq!(values.points.len(), 1);
+        assert_eq!(values.points[0].value, json!("lobby"));
+
+        let values = series(&conn, "main", "player", "alice", "world", 2, 10).unwrap();
+        assert_eq!(values.initial, json!("lobby"));
+        assert!(values.points.is_empty());
+    }
+
+    #[test]
+    fn cleared_watch_resets_values() {
+        let conn = database(&[
+            change(1, Some("alice"), EventType::Added, Some(json!({ "world": "lobby" }))),
+            change(2, None, EventType::Disconnected, None),
+            change(3, None, EventType::Clear, None),
+        ]);
+
+        let values = series(&conn, "main", "player", "alice", "world", 4, 10).unwrap();
+        assert_eq!(values.initial, Value::Null);
+    }
+}
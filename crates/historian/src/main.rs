@@ -0,0 +1,116 @@
+//! Records the changes of watched kinds into SQLite and serves queries over their history.
+
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::thread;
+
+use anyhow::Context as _;
+use clap::Parser;
+use futures::StreamExt;
+use tokio::sync::mpsc;
+use webconsole_client::protocol::ObjectDef;
+use webconsole_client::{select_kinds, Client, Retrying, Selection, WatchListEvent};
+
+use crate::db::{Change, EventType};
+
+mod api;
+mod db;
+
+#[derive(Parser)]
+#[command(version, about)]
+struct Args {
+    /// Base URL of the API server.
+    #[arg(long, env = "WEBCONSOLE_SERVER", default_value = "http://localhost:14875")]
+    server:   String,
+    /// Extra request header in the form `Name: value`, e.g. for an authenticating proxy.
+    #[arg(long = "header", short = 'H')]
+    headers:  Vec<String>,
+    /// Only record these kinds, in the form `group/kind`. All kinds are recorded if unspecified.
+    #[arg(long = "kind")]
+    kinds:    Vec<String>,
+    /// Path of the SQLite database, which is created if it does not exist.
+    #[arg(long, default_value = "history.sqlite")]
+    database: PathBuf,
+    /// Address to serve the query API on.
+    #[arg(long, default_value = "127.0.0.1:9776")]
+    listen:   SocketAddr,
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+
+    let mut client = Client::new(&args.server);
+    for header in &args.headers {
+        client = client.with_header_line(header)?;
+    }
+
+    let discovery = client.discovery().await.context("fetch discovery")?;
+    let defs = select_kinds(&discovery, &args.kinds)?;
+
+    let mut conn = db::open_writer(&args.database)
+        .with_context(|| format!("open {}", args.database.display()))?;
+    let (changes_tx, mut changes_rx) = mpsc::unbounded_channel::<Change>();
+    thread::spawn(move || {
+        while let Some(change) = changes_rx.blocking_recv() {
+            // write everything received in the meantime in the same transaction
+            let mut batch = vec![change];
+            while let Ok(change) = changes_rx.try_recv() {
+                batch.push(change);
+            }
+            if let Err(err) = db::insert(&mut conn, &batch) {
+                eprintln!("failed to write {} changes: {err}", batch.len());
+            }
+        }
+    });
+
+    for def in defs {
+        tokio::spawn(watch(client.clone(), def.clone(), changes_tx.clone()));
+    }
+
+    eprintln!("serving queries on http://{}", args.listen);
+    axum::Server::try_bind(&args.listen)
+        .with_context(|| format!("bind {}", args.listen))?
+        .serve(api::router(args.database).into_make_service())
+        .await?;
+
+    Ok(())
+}
+
+/// Records the changes of a kind, reopening the watch whenever it fails.
+async fn watch(client: Client, def: ObjectDef, changes: mpsc::UnboundedSender<Change>) {
+    let (group, kind) = (def.id.group, def.id.kind);
+    let change = |object: Option<String>, event, field: Option<String>, value| Change {
+        time: chrono::Utc::now().timestamp_micros(),
+        group: group.clone(),
+        kind: kind.clone(),
+        object,
+        event,
+        field,
+        value,
+    };
+    let mut events = client.watch_list_retrying(&group, &kind, Selection::default());
+
+    while let Some(item) = events.next().await {
+        let change = match item {
+            Retrying::Event(WatchListEvent::Clear) => change(None, EventType::Clear, None, None),
+            Retrying::Event(WatchListEvent::Resumed) => {
+                change(None, EventType::Resumed, None, None)
+            }
+            Retrying::Event(WatchListEvent::Added { item }) => {
+                change(Some(item.name), EventType::Added, None, Some(item.fields))
+            }
+            Retrying::Event(WatchListEvent::Removed { name }) => {
+                change(Some(name), EventType::Removed, None, None)
+            }
+            Retrying::Event(WatchListEvent::FieldUpdate { name, field, value }) => {
+                change(Some(name), EventType::Update, Some(field), Some(value))
+            }
+            Retrying::Failed(err) => {
+                eprintln!("watch {group}/{kind}: {err}");
+                change(None, EventType::Disconnected, None, None)
+            }
+        };
+        _ = changes.send(change);
+    }
+}
@@ -0,0 +1,135 @@
+//! HTTP endpoints with the same wire format as the plugin's handler.
+
+use std::convert::Infallible;
+use std::sync::Arc;
+
+use axum::extract::{Path, RawQuery, State as StateExt};
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::response::sse::{self, KeepAlive, Sse};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use futures::StreamExt;
+use tower_http::cors::CorsLayer;
+use webconsole_protocol::{parse_object_query, Selection};
+
+use crate::state::{select_fields, State};
+use crate::{mux, watch};
+
+pub fn router(state: Arc<State>) -> Router {
+    Router::new()
+        .route("/discovery", get(discovery))
+        .route("/locales", get(locales))
+        .route("/mux", get(mux::upgrade))
+        .route("/:file", get(locale_file))
+        .route("/:group/:kind", get(object_list))
+        .route("/:group/:kind/*name", get(single_object))
+        .fallback(|| async { not_found() })
+        .layer(CorsLayer::permissive())
+        .with_state(state)
+}
+
+fn not_found() -> Response { (StatusCode::NOT_FOUND, "404 Not Found").into_response() }
+
+async fn discovery(StateExt(state): StateExt<Arc<State>>) -> Response {
+    Json(&state.discovery).into_response()
+}
+
+async fn locales(StateExt(state): StateExt<Arc<State>>) -> Response {
+    Json(state.locales.keys().collect::<Vec<_>>()).into_response()
+}
+
+async fn locale_file(StateExt(state): StateExt<Arc<State>>, Path(file): Path<String>) -> Response {
+    let Some(locale) = file.strip_suffix(".ftl") else { return not_found() };
+    match state.locales.get(locale) {
+        Some(source) => ([(header::CONTENT_TYPE, "text/plain")], source.clone()).into_response(),
+        None => not_found(),
+    }
+}
+
+fn parse_query(query: Option<String>) -> Result<(bool, Selection), Response> {
+    parse_object_query(query.as_deref().unwrap_or_default())
+        .map_err(|err| (StatusCode::BAD_REQUEST, err.to_string()).into_response())
+}
+
+fn last_event_id(headers: &HeaderMap) -> Option<&str> {
+    headers.get("Last-Event-ID").and_then(|value| value.to_str().ok())
+}
+
+async fn object_list(
+    StateExt(state): StateExt<Arc<State>>,
+    Path((group, kind)): Path<(String, String)>,
+    RawQuery(query): RawQuery,
+    headers: HeaderMap,
+) -> Response {
+    let (is_watch, selection) = match parse_query(query) {
+        Ok(query) => query,
+        Err(resp) => return resp,
+    };
+
+    if is_watch {
+        let events = watch::watch_list(&state, &group, &kind, selection, last_event_id(&headers));
+        return match events {
+            Some(events) => event_stream(events),
+            None => not_found(),
+        };
+    }
+
+    let kinds = state.lock();
+    let Some(kind) = kinds.get(&group, &kind) else { return not_found() };
+    let paths = kind.field_paths(selection.fields.as_deref());
+    let limit = selection.limit.map_or(usize::MAX, |limit| limit as usize);
+
+    let mut body = String::new();
+    for (name, fields) in kind.objects.iter().take(limit) {
+        let object = select_fields(name, fields, &paths);
+        body.push_str(&serde_json::to_string(&object).expect("objects are serializable"));
+        body.push('\n');
+    }
+    ([(header::CACHE_CONTROL, "no-cache")], body).into_response()
+}
+
+async fn single_object(
+    StateExt(state): StateExt<Arc<State>>,
+    Path((group, kind, name)): Path<(String, String, String)>,
+    RawQuery(query): RawQuery,
+    headers: HeaderMap,
+) -> Response {
+    let (is_watch, selection) = match parse_query(query) {
+        Ok(query) => query,
+        Err(resp) => return resp,
+    };
+    let name = name.trim_start_matches('/');
+
+    if is_watch {
+        let events = watch::watch_single(
+            &state,
+            &group,
+            &kind,
+            name,
+            selection.fields,
+            last_event_id(&headers),
+        );
+        return match events {
+            Some(events) => event_stream(events),
+            None => not_found(),
+        };
+    }
+
+    let kinds = state.lock();
+    let Some(kind) = kinds.get(&group, &kind) else { return not_found() };
+    let Some(fields) = kind.objects.get(name) else { return not_found() };
+    let paths = kind.field_paths(selection.fields.as_deref());
+    Json(select_fields(name, fields, &paths)).into_response()
+}
+
+fn event_stream(events: futures::stream::BoxStream<'static, watch::Event>) -> Response {
+    let events = events.map(|event| {
+        let mut sse = sse::Event::default().data(event.data.to_string());
+        if let Some(id) = event.id {
+            sse = sse.id(id);
+        }
+        Ok::<_, Infallible>(sse)
+    });
+    Sse::new(events).keep_alive(KeepAlive::default()).into_response()
+}
@@ -0,0 +1,104 @@
+//! A mock WebConsole API server driven by a scenario file.
+//!
+//! It serves the same endpoints and wire format as the plugin,
+//! so that the site and other clients can be developed and tested without a PocketMine server.
+//! It can also replay a session recorded by a client to reproduce a bug.
+
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Context as _;
+use clap::Parser;
+
+mod http;
+mod mux;
+mod replay;
+mod scenario;
+mod state;
+mod watch;
+
+use scenario::{Action, Scenario};
+use state::{Change, State};
+
+#[derive(Parser)]
+#[command(version, about)]
+struct Args {
+    /// Path of the scenario file.
+    #[arg(required_unless_present = "replay")]
+    scenario:     Option<PathBuf>,
+    /// Replay a session file recorded by a client instead of playing a scenario.
+    #[arg(long, conflicts_with_all = ["scenario", "no_websocket"])]
+    replay:       Option<PathBuf>,
+    /// Address to listen on.
+    #[arg(long, default_value = "127.0.0.1:14875")]
+    listen:       SocketAddr,
+    /// Speed of the scenario steps or the replayed session,
+    /// e.g. 2 to wait half as long between steps.
+    #[arg(long, default_value_t = 1.0)]
+    speed:        f64,
+    /// Do not advertise the WebSocket transport, so that clients watch through SSE only.
+    #[arg(long)]
+    no_websocket: bool,
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+    anyhow::ensure!(args.speed > 0., "--speed must be positive");
+
+    let router = match (&args.replay, &args.scenario) {
+        (Some(session), _) => replay::router(Arc::new(replay::Session::load(session, args.speed)?)),
+        (None, Some(path)) => {
+            let scenario = Scenario::load(path)?;
+            let base = path.parent().unwrap_or(".".as_ref());
+            let locales = scenario.read_locales(base)?;
+            let state = Arc::new(State::new(&scenario, locales, !args.no_websocket)?);
+
+            tokio::spawn(play(state.clone(), scenario, args.speed));
+            http::router(state)
+        }
+        (None, None) => unreachable!("clap requires a scenario without --replay"),
+    };
+
+    let server = axum::Server::try_bind(&args.listen)
+        .with_context(|| format!("bind {}", args.listen))?
+        .serve(router.into_make_service());
+    // the actual port if 0 was requested
+    eprintln!("listening on http://{}", server.local_addr());
+    server.await?;
+
+    Ok(())
+}
+
+/// Applies the scenario steps in order.
+///
+/// Invalid steps, e.g. updating an object that was never added, are reported and skipped.
+async fn play(state: Arc<State>, scenario: Scenario, speed: f64) {
+    if scenario.steps.is_empty() {
+        return;
+    }
+
+    loop {
+        for (i, step) in scenario.steps.iter().enumerate() {
+            tokio::time::sleep(Duration::from_secs_f64(step.after.max(0.) / speed)).await;
+
+            let name = step.name.clone();
+            let change = match &step.action {
+                Action::Add { fields } => Change::Added { name, fields: fields.clone() },
+                Action::Remove => Change::Removed { name },
+                Action::Update { field, value } => {
+                    Change::FieldUpdate { name, field: field.clone(), value: value.clone() }
+                }
+            };
+            if let Err(err) = state.apply(&step.gk, change) {
+                eprintln!("step {}: {err:#}", i + 1);
+            }
+        }
+
+        if !scenario.repeat {
+            return;
+        }
+    }
+}
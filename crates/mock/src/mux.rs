@@ -0,0 +1,98 @@
+//! The WebSocket transport advertised in `Discovery.transports`.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::State as StateExt;
+use axum::response::Response;
+use futures::channel::mpsc;
+use futures::{SinkExt, StreamExt};
+use tokio::task::AbortHandle;
+use webconsole_protocol::mux::{ClientFrame, ServerFrame};
+use webconsole_protocol::Selection;
+
+use crate::state::State;
+use crate::watch;
+
+/// How many frames may wait for the socket before a subscription that produces more is ended,
+/// like the SSE watchers that fall behind.
+const FRAME_BUFFER: usize = 256;
+
+pub async fn upgrade(StateExt(state): StateExt<Arc<State>>, ws: WebSocketUpgrade) -> Response {
+    ws.on_upgrade(move |socket| serve(state, socket))
+}
+
+async fn serve(state: Arc<State>, socket: WebSocket) {
+    let (mut sink, mut stream) = socket.split();
+    let (tx, mut rx) = mpsc::channel(FRAME_BUFFER);
+    let mut subs = HashMap::<u64, AbortHandle>::new();
+
+    loop {
+        tokio::select! {
+            frame = rx.next() => {
+                let frame: ServerFrame = frame.expect("the sender is held by this loop");
+                let text = serde_json::to_string(&frame).expect("frames are serializable");
+                if sink.send(Message::Text(text)).await.is_err() {
+                    break;
+                }
+            }
+            message = stream.next() => match message {
+                Some(Ok(Message::Text(text))) => match serde_json::from_str(&text) {
+                    Ok(ClientFrame::Subscribe { id, group, kind, name, fields, limit, last_event_id }) => {
+                        let last_event_id = last_event_id.as_deref();
+                        let events = match name {
+                            Some(name) => watch::watch_single(&state, &group, &kind, &name, fields, last_event_id),
+                            None => {
+                                let selection = Selection { fields, limit };
+                                watch::watch_list(&state, &group, &kind, selection, last_event_id)
+                            }
+                        };
+                        let Some(mut events) = events else {
+                            let message = String::from("404 Not Found");
+                            let frame = ServerFrame::Error { id, status: Some(404), message };
+                            let text = serde_json::to_string(&frame).expect("frames are serializable");
+                            if sink.send(Message::Text(text)).await.is_err() {
+                                break;
+                            }
+                            continue;
+                        };
+
+                        let mut tx = tx.clone();
+                        let task = tokio::spawn(async move {
+                            while let Some(event) = events.next().await {
+                                let frame = ServerFrame::Event { id, event: event.data, event_id: event.id };
+                                match tx.try_send(frame) {
+                                    Ok(()) => {}
+                                    Err(err) if err.is_full() => {
+                                        eprintln!("ending a websocket subscription that fell behind");
+                                        let message = String::from("the subscription fell behind");
+                                        _ = tx.send(ServerFrame::Error { id, status: None, message }).await;
+                                        return;
+                                    }
+                                    Err(_) => return,
+                                }
+                            }
+                            _ = tx.send(ServerFrame::End { id }).await;
+                        });
+                        if let Some(replaced) = subs.insert(id, task.abort_handle()) {
+                            replaced.abort();
+                        }
+                    }
+                    Ok(ClientFrame::Unsubscribe { id }) => {
+                        if let Some(task) = subs.remove(&id) {
+                            task.abort();
+                        }
+                    }
+                    Err(err) => eprintln!("invalid websocket frame: {err}"),
+                },
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+
+    for task in subs.into_values() {
+        task.abort();
+    }
+}
@@ -0,0 +1,211 @@
+//! Serves a recorded session instead of a scenario.
+//!
+//! Each request is answered with the next recorded exchange for the same path,
+//! or the last one once they are exhausted,
+//! re-emitting its records with the recorded delays since the request.
+
+use std::collections::HashMap;
+use std::io;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::Context as _;
+use axum::body::{Bytes, StreamBody};
+use axum::extract::State as StateExt;
+use axum::http::{header, StatusCode, Uri};
+use axum::response::{IntoResponse, Response};
+use axum::Router;
+use futures::StreamExt;
+use tokio::time::Instant;
+use tower_http::cors::CorsLayer;
+use webconsole_protocol::parse_object_query;
+use webconsole_protocol::session::{Entry, Record};
+
+/// The records of the response to one request,
+/// with their delays in milliseconds since the request.
+struct Exchange {
+    records: Vec<(f64, Record)>,
+}
+
+struct Recorded {
+    exchanges: Vec<Arc<Exchange>>,
+    next:      usize,
+}
+
+pub struct Session {
+    paths: Mutex<HashMap<String, Recorded>>,
+    speed: f64,
+}
+
+impl Session {
+    pub fn load(path: &Path, speed: f64) -> anyhow::Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("read session file {}", path.display()))?;
+
+        // exchange IDs are only unique within a recording
+        let mut open: HashMap<u64, (String, f64, Exchange)> = HashMap::new();
+        let mut order = Vec::new();
+        for (i, line) in text.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let entry: Entry = serde_json::from_str(line)
+                .with_context(|| format!("{}:{}: invalid entry", path.display(), i + 1))?;
+
+            if let Record::Request { path, .. } = entry.record {
+                if let Some(done) = open.insert(entry.exchange, (path, entry.at, Exchange::new())) {
+                    order.push(done);
+                }
+                continue;
+            }
+
+            let Some((_, start, exchange)) = open.get_mut(&entry.exchange) else {
+                eprintln!("{}:{}: ignoring entry of unknown request", path.display(), i + 1);
+                continue;
+            };
+            exchange.records.push((entry.at - *start, entry.record));
+        }
+        order.extend(open.into_values());
+        order.sort_by(|(_, a, _), (_, b, _)| a.total_cmp(b));
+
+        let mut paths: HashMap<String, Recorded> = HashMap::new();
+        for (path, _, mut exchange) in order {
+            if path == "discovery" {
+                exchange.strip_websocket();
+            }
+            paths
+                .entry(path)
+                .or_insert_with(|| Recorded { exchanges: Vec::new(), next: 0 })
+                .exchanges
+                .push(Arc::new(exchange));
+        }
+
+        Ok(Self { paths: Mutex::new(paths), speed })
+    }
+
+    fn next(&self, path: &str) -> Option<Arc<Exchange>> {
+        let mut paths = self.paths.lock().expect("session lock is poisoned");
+        let recorded = paths.get_mut(path)?;
+        let exchange = recorded.exchanges.get(recorded.next).or(recorded.exchanges.last())?;
+        recorded.next += 1;
+        Some(exchange.clone())
+    }
+}
+
+impl Exchange {
+    fn new() -> Self { Self { records: Vec::new() } }
+
+    /// Watches are always replayed as event streams,
+    /// so the replayed discovery document must not advertise the WebSocket transport.
+    fn strip_websocket(&mut self) {
+        for (_, record) in &mut self.records {
+            let Record::Body { text } = record else { continue };
+            let Ok(mut discovery) = serde_json::from_str::<serde_json::Value>(text) else {
+                continue;
+            };
+            if let Some(transports) =
+                discovery.get_mut("transports").and_then(|t| t.as_object_mut())
+            {
+                transports.remove("websocket");
+            }
+            *text = discovery.to_string();
+        }
+    }
+}
+
+pub fn router(session: Arc<Session>) -> Router {
+    Router::new().fallback(replay).layer(CorsLayer::permissive()).with_state(session)
+}
+
+async fn replay(StateExt(session): StateExt<Arc<Session>>, uri: Uri) -> Response {
+    let path = uri.path_and_query().map_or("", |path| path.as_str()).trim_start_matches('/');
+    let Some(exchange) = session.next(path) else {
+        return (StatusCode::NOT_FOUND, "404 Not Found").into_response();
+    };
+
+    let start = Instant::now();
+    let speed = session.speed;
+    let delay = move |at: f64| start + Duration::from_secs_f64(at.max(0.) / 1000. / speed);
+
+    let mut records = exchange.records.iter().cloned().peekable();
+    let mut status = StatusCode::OK;
+    if let Some((at, record)) = records.peek() {
+        match record {
+            Record::Response { status: code, body } => {
+                tokio::time::sleep_until(delay(*at)).await;
+                status = StatusCode::from_u16(*code).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+                if let Some(body) = body {
+                    return (status, body.clone()).into_response();
+                }
+                records.next();
+            }
+            Record::Error { message } => {
+                tokio::time::sleep_until(delay(*at)).await;
+                return (StatusCode::BAD_GATEWAY, message.clone()).into_response();
+            }
+            // watches multiplexed over a WebSocket have no response record
+            _ => {}
+        }
+    }
+
+    let query = path.split_once('?').map_or("", |(_, query)| query);
+    let content_type = if parse_object_query(query).map_or(false, |(watch, _)| watch) {
+        "text/event-stream"
+    } else if path.ends_with(".ftl") {
+        "text/plain"
+    } else if exchange.records.iter().any(|(_, record)| matches!(record, Record::Line { .. })) {
+        "application/x-ndjson"
+    } else {
+        "application/json"
+    };
+
+    let records: Vec<_> = records.collect();
+    let closed = records.iter().any(|(_, record)| {
+        matches!(record, Record::Body { .. } | Record::Error { .. } | Record::End)
+    });
+    let chunks = futures::stream::iter(records)
+        .then(move |(at, record)| async move {
+            tokio::time::sleep_until(delay(at)).await;
+            record
+        })
+        .take_while(|record| futures::future::ready(!matches!(record, Record::End)))
+        .filter_map(|record| async move { encode(record) });
+    // a recording that stopped in the middle of a response keeps it open
+    let chunks =
+        if closed { chunks.boxed() } else { chunks.chain(futures::stream::pending()).boxed() };
+
+    (status, [(header::CONTENT_TYPE, content_type)], StreamBody::new(chunks)).into_response()
+}
+
+/// Encodes a record as a chunk of the response body.
+///
+/// An error aborts the response, so that the client sees it as a network error.
+fn encode(record: Record) -> Option<io::Result<Bytes>> {
+    let chunk = match record {
+        Record::Body { text } => text,
+        Record::Line { line } => format!("{line}\n"),
+        Record::Event { event, data, id, retry } => {
+            let mut chunk = String::new();
+            if event != "message" {
+                chunk.push_str(&format!("event: {event}\n"));
+            }
+            if let Some(id) = id {
+                chunk.push_str(&format!("id: {id}\n"));
+            }
+            if let Some(retry) = retry {
+                chunk.push_str(&format!("retry: {retry}\n"));
+            }
+            for line in data.split('\n') {
+                chunk.push_str(&format!("data: {line}\n"));
+            }
+            chunk.push('\n');
+            chunk
+        }
+        Record::Error { message } => {
+            return Some(Err(io::Error::new(io::ErrorKind::Other, message)))
+        }
+        Record::Request { .. } | Record::Response { .. } | Record::End => return None,
+    };
+    Some(Ok(Bytes::from(chunk)))
+}
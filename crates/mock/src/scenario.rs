@@ -0,0 +1,96 @@
+//! The scenario file that declares the API served by the mock server and scripts its changes.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context as _;
+use serde::Deserialize;
+use webconsole_protocol::{Group, GroupKind, ObjectDef};
+
+#[derive(Deserialize)]
+pub struct Scenario {
+    /// Fluent files served for each locale, relative to the scenario file.
+    ///
+    /// The files of a locale are concatenated in order,
+    /// like the files provided by different plugins.
+    #[serde(default)]
+    pub locales: BTreeMap<String, Vec<PathBuf>>,
+    #[serde(default)]
+    pub groups:  Vec<Group>,
+    /// Object kinds in the same format as the `apis` of the discovery document.
+    #[serde(default)]
+    pub kinds:   Vec<ObjectDef>,
+    /// Objects that exist when the server starts.
+    #[serde(default)]
+    pub objects: Vec<InitialObject>,
+    /// Changes applied one after another once the server starts.
+    #[serde(default)]
+    pub steps:   Vec<Step>,
+    /// Restart the steps from the beginning after the last one.
+    #[serde(default)]
+    pub repeat:  bool,
+}
+
+#[derive(Deserialize)]
+pub struct InitialObject {
+    #[serde(flatten)]
+    pub gk:     GroupKind,
+    pub name:   String,
+    /// Field values in the nested format of the API, e.g. `{ entity = { health = 20.0 } }`.
+    #[serde(default)]
+    pub fields: serde_json::Value,
+}
+
+#[derive(Deserialize)]
+pub struct Step {
+    /// Seconds to wait after the previous step.
+    #[serde(default)]
+    pub after:  f64,
+    #[serde(flatten)]
+    pub gk:     GroupKind,
+    pub name:   String,
+    #[serde(flatten)]
+    pub action: Action,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum Action {
+    Add {
+        #[serde(default)]
+        fields: serde_json::Value,
+    },
+    Remove,
+    Update {
+        field: String,
+        value: serde_json::Value,
+    },
+}
+
+impl Scenario {
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let source =
+            std::fs::read_to_string(path).with_context(|| format!("read {}", path.display()))?;
+        toml::from_str(&source).with_context(|| format!("parse {}", path.display()))
+    }
+
+    /// Reads the Fluent source of each locale.
+    pub fn read_locales(&self, base: &Path) -> anyhow::Result<BTreeMap<String, String>> {
+        self.locales
+            .iter()
+            .map(|(locale, files)| {
+                let mut source = String::new();
+                for file in files {
+                    let path = base.join(file);
+                    let file = std::fs::read_to_string(&path)
+                        .with_context(|| format!("read {}", path.display()))?;
+                    source.push_str(&file);
+                    if !source.ends_with('\n') {
+                        source.push('\n');
+                    }
+                }
+                Ok((locale.clone(), source))
+            })
+            .collect()
+    }
+}
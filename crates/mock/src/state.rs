@@ -0,0 +1,226 @@
+//! The objects served by the mock server and the history of their changes.
+
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::sync::{Mutex, MutexGuard};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::Context as _;
+use tokio::sync::broadcast;
+use webconsole_protocol::{
+    get_json_path, insert_json_path, Discovery, GroupKind, GroupKindDyn, GroupKindRef, Object,
+    ObjectDef, Transports,
+};
+
+use crate::scenario::Scenario;
+
+/// Number of changes per kind kept for resuming watches.
+const HISTORY_SIZE: usize = 1024;
+
+/// Number of changes per kind buffered for slow watchers before they are disconnected.
+const BROADCAST_SIZE: usize = 256;
+
+#[derive(Debug, Clone)]
+pub enum Change {
+    Added { name: String, fields: serde_json::Value },
+    Removed { name: String },
+    FieldUpdate { name: String, field: String, value: serde_json::Value },
+}
+
+pub struct State {
+    pub discovery: Discovery,
+    pub locales:   BTreeMap<String, String>,
+    pub ids:       EventIds,
+    kinds:         Mutex<HashMap<GroupKind, Kind>>,
+}
+
+pub struct Kind {
+    pub def:     ObjectDef,
+    /// The fields of each object in the nested format of the API.
+    pub objects: BTreeMap<String, serde_json::Value>,
+    /// Sequence number of the last change.
+    pub seq:     u64,
+    history:     VecDeque<(u64, Change)>,
+    tx:          broadcast::Sender<(u64, Change)>,
+}
+
+impl State {
+    pub fn new(
+        scenario: &Scenario,
+        locales: BTreeMap<String, String>,
+        websocket: bool,
+    ) -> anyhow::Result<Self> {
+        let discovery = Discovery {
+            groups:     scenario.groups.iter().cloned().collect(),
+            apis:       scenario.kinds.iter().cloned().collect(),
+            transports: Transports { websocket: websocket.then(|| String::from("mux")) },
+        };
+
+        for def in &scenario.kinds {
+            anyhow::ensure!(
+                discovery.groups.get(&def.id.group).is_some(),
+                "kind {}/{} belongs to an undeclared group",
+                def.id.group,
+                def.id.kind,
+            );
+        }
+
+        let kinds = scenario
+            .kinds
+            .iter()
+            .map(|def| {
+                let kind = Kind {
+                    def:     def.clone(),
+                    objects: BTreeMap::new(),
+                    seq:     0,
+                    history: VecDeque::new(),
+                    tx:      broadcast::channel(BROADCAST_SIZE).0,
+                };
+                (def.id.clone(), kind)
+            })
+            .collect();
+
+        let epoch = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis();
+        let ids = EventIds { epoch };
+        let state = Self { discovery, locales, ids, kinds: Mutex::new(kinds) };
+
+        for object in &scenario.objects {
+            let change =
+                Change::Added { name: object.name.clone(), fields: object.fields.clone() };
+            state.apply(&object.gk, change).with_context(|| {
+                format!("add {}/{}/{}", object.gk.group, object.gk.kind, object.name)
+            })?;
+        }
+
+        Ok(state)
+    }
+
+    /// Locks the kinds to inspect their objects or subscribe to their changes.
+    pub fn lock(&self) -> Kinds<'_> { Kinds { ids: self.ids, guard: self.kinds.lock().unwrap() } }
+
+    /// Validates and applies a change, notifying the watchers of its kind.
+    pub fn apply(&self, gk: &GroupKind, change: Change) -> anyhow::Result<()> {
+        let mut kinds = self.lock();
+        let kind = kinds
+            .get_mut(&gk.group, &gk.kind)
+            .with_context(|| format!("kind {}/{} is not declared", gk.group, gk.kind))?;
+
+        let change = match change {
+            Change::Added { name, fields } => {
+                anyhow::ensure!(!kind.objects.contains_key(&name), "{name} already exists");
+                let fields = match fields {
+                    serde_json::Value::Null => serde_json::Value::Object(Default::default()),
+                    serde_json::Value::Object(map) => serde_json::Value::Object(map),
+                    _ => anyhow::bail!("the fields of {name} must be a table"),
+                };
+                kind.objects.insert(name.clone(), fields.clone());
+                Change::Added { name, fields }
+            }
+            Change::Removed { name } => {
+                anyhow::ensure!(kind.objects.remove(&name).is_some(), "{name} does not exist");
+                Change::Removed { name }
+            }
+            Change::FieldUpdate { name, field, value } => {
+                anyhow::ensure!(kind.def.fields.get(&field).is_some(), "{field} is not declared");
+                let object =
+                    kind.objects.get_mut(&name).context(format!("{name} does not exist"))?;
+                insert_json_path(object, &field, value.clone())?;
+                Change::FieldUpdate { name, field, value }
+            }
+        };
+
+        kind.seq += 1;
+        if kind.history.len() == HISTORY_SIZE {
+            kind.history.pop_front();
+        }
+        kind.history.push_back((kind.seq, change.clone()));
+        // no receivers just means that nobody is watching
+        _ = kind.tx.send((kind.seq, change));
+
+        Ok(())
+    }
+}
+
+/// Converts between event IDs and the sequence numbers of changes.
+#[derive(Clone, Copy)]
+pub struct EventIds {
+    /// Distinguishes the event IDs of different runs of the server,
+    /// so that watches cannot resume from IDs issued before a restart.
+    epoch: u128,
+}
+
+impl EventIds {
+    pub fn format(self, seq: u64) -> String { format!("{}-{seq}", self.epoch) }
+
+    fn parse(self, id: &str) -> Option<u64> {
+        let (epoch, seq) = id.split_once('-')?;
+        if epoch.parse::<u128>().ok()? != self.epoch {
+            return None;
+        }
+        seq.parse().ok()
+    }
+}
+
+pub struct Kinds<'t> {
+    ids:   EventIds,
+    guard: MutexGuard<'t, HashMap<GroupKind, Kind>>,
+}
+
+impl<'t> Kinds<'t> {
+    pub fn get(&self, group: &str, kind: &str) -> Option<&Kind> {
+        self.guard.get(&GroupKindRef { group, kind } as &dyn GroupKindDyn)
+    }
+
+    fn get_mut(&mut self, group: &str, kind: &str) -> Option<&mut Kind> {
+        self.guard.get_mut(&GroupKindRef { group, kind } as &dyn GroupKindDyn)
+    }
+
+    /// The changes after the event ID sent by a client,
+    /// or `None` if they are no longer available and the client must start over.
+    pub fn changes_since(
+        &self,
+        kind: &Kind,
+        last_event_id: Option<&str>,
+    ) -> Option<Vec<(u64, Change)>> {
+        let last = self.ids.parse(last_event_id?)?;
+        if last > kind.seq {
+            return None;
+        }
+
+        let oldest = kind.history.front().map_or(kind.seq + 1, |&(seq, _)| seq);
+        if last + 1 < oldest {
+            return None;
+        }
+
+        Some(kind.history.iter().filter(|&&(seq, _)| seq > last).cloned().collect())
+    }
+}
+
+impl Kind {
+    /// Receives the changes after the current sequence number.
+    pub fn subscribe(&self) -> broadcast::Receiver<(u64, Change)> { self.tx.subscribe() }
+
+    /// The paths of the declared fields that are selected, or of all fields if unspecified.
+    pub fn field_paths(&self, selected: Option<&[String]>) -> Vec<String> {
+        self.def
+            .fields
+            .values()
+            .map(|field| &field.path)
+            .filter(|path| selected.map_or(true, |selected| selected.contains(path)))
+            .cloned()
+            .collect()
+    }
+}
+
+/// Builds the object returned to clients with only the selected fields.
+///
+/// Fields missing from the scenario are sent as `null`,
+/// since the plugin always provides a value for every declared field.
+pub fn select_fields(name: &str, fields: &serde_json::Value, paths: &[String]) -> Object {
+    let mut selected = serde_json::Value::Object(Default::default());
+    for path in paths {
+        let value = get_json_path(fields, path).cloned().unwrap_or_default();
+        // only fails if a declared path is nested under another declared path
+        _ = insert_json_path(&mut selected, path, value);
+    }
+    Object { name: name.to_string(), fields: selected }
+}
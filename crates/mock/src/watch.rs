@@ -0,0 +1,244 @@
+//! Watch streams shared by the SSE and WebSocket transports.
+
+use std::collections::BTreeSet;
+
+use futures::stream::{self, BoxStream};
+use futures::StreamExt;
+use tokio::sync::broadcast;
+use webconsole_protocol::{
+    get_json_path, insert_json_path, Selection, WatchListEvent, WatchSingleEvent,
+};
+
+use crate::state::{select_fields, Change, EventIds, State};
+
+/// An event sent to a watcher, with the ID to resume the watch after it.
+pub struct Event {
+    pub id:   Option<String>,
+    pub data: serde_json::Value,
+}
+
+fn untagged<T: serde::Serialize>(event: T) -> Event {
+    Event { id: None, data: serde_json::to_value(event).expect("events are serializable") }
+}
+
+/// Attaches the event ID of a change to the last of the events it caused.
+fn tag<T: serde::Serialize>(events: Vec<T>, id: String) -> Vec<Event> {
+    let mut events: Vec<_> = events.into_iter().map(untagged).collect();
+    if let Some(last) = events.last_mut() {
+        last.id = Some(id);
+    }
+    events
+}
+
+/// Watches the objects of a kind, or returns `None` if the kind is not declared.
+///
+/// Watches with a limit cannot be resumed,
+/// since the server does not know which objects the client had been shown.
+pub fn watch_list(
+    state: &State,
+    group: &str,
+    kind: &str,
+    selection: Selection,
+    last_event_id: Option<&str>,
+) -> Option<BoxStream<'static, Event>> {
+    let kinds = state.lock();
+    let kind = kinds.get(group, kind)?;
+
+    let mut view = ListView {
+        paths:   kind.field_paths(selection.fields.as_deref()),
+        limit:   selection.limit.map(|limit| limit as usize),
+        shown:   BTreeSet::new(),
+        pending: Vec::new(),
+    };
+
+    let resumed =
+        if view.limit.is_none() { kinds.changes_since(kind, last_event_id) } else { None };
+    let initial = match resumed {
+        Some(changes) => {
+            // rewind the current objects to those that the client had been shown
+            view.shown.extend(kind.objects.keys().cloned());
+            for (_, change) in changes.iter().rev() {
+                match change {
+                    Change::Added { name, .. } => view.shown.remove(name),
+                    Change::Removed { name } => view.shown.insert(name.clone()),
+                    Change::FieldUpdate { .. } => continue,
+                };
+            }
+
+            let mut initial = vec![untagged(WatchListEvent::Resumed)];
+            for (seq, change) in changes {
+                initial.extend(tag(view.apply(&change), state.ids.format(seq)));
+            }
+            initial
+        }
+        None => {
+            let mut events = vec![WatchListEvent::Clear];
+            for (name, fields) in &kind.objects {
+                events.extend(view.add(name, fields));
+            }
+            tag(events, state.ids.format(kind.seq))
+        }
+    };
+
+    let live =
+        live(kind.subscribe(), view, kind.seq, state.ids, |view, change| Some(view.apply(change)));
+    Some(stream::iter(initial).chain(live).boxed())
+}
+
+/// Watches the fields of an object, or returns `None` if it does not exist.
+///
+/// The stream ends when the object is removed.
+pub fn watch_single(
+    state: &State,
+    group: &str,
+    kind: &str,
+    name: &str,
+    fields: Option<Vec<String>>,
+    last_event_id: Option<&str>,
+) -> Option<BoxStream<'static, Event>> {
+    let kinds = state.lock();
+    let kind = kinds.get(group, kind)?;
+    let object = kind.objects.get(name)?;
+
+    let mut view =
+        SingleView { name: name.to_string(), paths: kind.field_paths(fields.as_deref()) };
+
+    let resumed = kinds.changes_since(kind, last_event_id).and_then(|changes| {
+        let mut events = vec![untagged(WatchSingleEvent::Resumed)];
+        for (seq, change) in changes {
+            // an object removed since then is sent in full like a new watch
+            events.extend(tag(view.apply(&change)?, state.ids.format(seq)));
+        }
+        Some(events)
+    });
+    let initial = match resumed {
+        Some(events) => events,
+        None => {
+            let current = select_fields(name, object, &view.paths);
+            let events = view
+                .paths
+                .iter()
+                .map(|path| WatchSingleEvent::Update {
+                    field: path.clone(),
+                    value: get_json_path(&current.fields, path).cloned().unwrap_or_default(),
+                })
+                .collect();
+            tag(events, state.ids.format(kind.seq))
+        }
+    };
+
+    let live = live(kind.subscribe(), view, kind.seq, state.ids, SingleView::apply);
+    Some(stream::iter(initial).chain(live).boxed())
+}
+
+/// Converts the changes after `after` into events until `apply` returns `None`.
+///
+/// The stream also ends if the watcher falls too far behind,
+/// in which case the client is expected to reconnect and resume.
+fn live<V: Send + 'static, T: serde::Serialize + 'static>(
+    rx: broadcast::Receiver<(u64, Change)>,
+    view: V,
+    after: u64,
+    ids: EventIds,
+    apply: fn(&mut V, &Change) -> Option<Vec<T>>,
+) -> impl futures::Stream<Item = Event> + Send + 'static {
+    stream::unfold((rx, view), move |(mut rx, mut view)| async move {
+        loop {
+            match rx.recv().await {
+                Ok((seq, _)) if seq <= after => continue,
+                Ok((seq, change)) => {
+                    let events = apply(&mut view, &change)?;
+                    if !events.is_empty() {
+                        return Some((tag(events, ids.format(seq)), (rx, view)));
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(_)) => {
+                    eprintln!("disconnecting a watcher that fell behind");
+                    return None;
+                }
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    })
+    .flat_map(stream::iter)
+}
+
+/// The objects of a kind shown to one watcher.
+struct ListView {
+    paths:   Vec<String>,
+    limit:   Option<usize>,
+    shown:   BTreeSet<String>,
+    /// Objects added beyond the limit, in the order they were added, with their latest fields.
+    ///
+    /// Like the plugin, the server shows them when a shown object is removed.
+    pending: Vec<(String, serde_json::Value)>,
+}
+
+impl ListView {
+    fn add(&mut self, name: &str, fields: &serde_json::Value) -> Option<WatchListEvent> {
+        if self.limit.map_or(false, |limit| self.shown.len() >= limit) {
+            self.pending.push((name.to_string(), fields.clone()));
+            return None;
+        }
+
+        self.shown.insert(name.to_string());
+        Some(WatchListEvent::Added { item: select_fields(name, fields, &self.paths) })
+    }
+
+    fn apply(&mut self, change: &Change) -> Vec<WatchListEvent> {
+        match change {
+            Change::Added { name, fields } => self.add(name, fields).into_iter().collect(),
+            Change::Removed { name } => {
+                if !self.shown.remove(name) {
+                    self.pending.retain(|(pending, _)| pending != name);
+                    return Vec::new();
+                }
+
+                let mut events = vec![WatchListEvent::Removed { name: name.clone() }];
+                if !self.pending.is_empty() {
+                    let (name, fields) = self.pending.remove(0);
+                    events.extend(self.add(&name, &fields));
+                }
+                events
+            }
+            Change::FieldUpdate { name, field, value } => {
+                if self.shown.contains(name) {
+                    if self.paths.contains(field) {
+                        return vec![WatchListEvent::FieldUpdate {
+                            name:  name.clone(),
+                            field: field.clone(),
+                            value: value.clone(),
+                        }];
+                    }
+                } else if let Some((_, fields)) =
+                    self.pending.iter_mut().find(|(pending, _)| pending == name)
+                {
+                    // the change was validated against the same object in the state
+                    _ = insert_json_path(fields, field, value.clone());
+                }
+                Vec::new()
+            }
+        }
+    }
+}
+
+/// The fields of an object shown to one watcher.
+struct SingleView {
+    name:  String,
+    paths: Vec<String>,
+}
+
+impl SingleView {
+    /// Returns `None` if the object was removed.
+    fn apply(&mut self, change: &Change) -> Option<Vec<WatchSingleEvent>> {
+        match change {
+            Change::Removed { name } if *name == self.name => None,
+            Change::FieldUpdate { name, field, value }
+                if *name == self.name && self.paths.contains(field) =>
+            {
+                Some(vec![WatchSingleEvent::Update { field: field.clone(), value: value.clone() }])
+            }
+            _ => Some(Vec::new()),
+        }
+    }
+}
@@ -0,0 +1,203 @@
+//! Runs the mock server on a free port and uses it through `webconsole-client`.
+
+use std::ffi::OsStr;
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::Path;
+use std::process::{Child, Command, Stdio};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use futures::StreamExt;
+use serde_json::json;
+use webconsole_client::{
+    Client, Object, Recorder, ResumeToken, Selection, WatchListEvent, WatchSingleEvent,
+};
+
+struct Mock {
+    child: Child,
+    url:   String,
+}
+
+impl Mock {
+    fn start() -> Self {
+        Self::spawn(&[concat!(env!("CARGO_MANIFEST_DIR"), "/tests/scenario.toml").as_ref()])
+    }
+
+    fn replay(session: &Path) -> Self { Self::spawn(&["--replay".as_ref(), session.as_os_str()]) }
+
+    fn spawn(args: &[&OsStr]) -> Self {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_webconsole-mock"))
+            .args(args)
+            .args(["--listen", "127.0.0.1:0"])
+            .stderr(Stdio::piped())
+            .spawn()
+            .expect("start the mock server");
+
+        let stderr = child.stderr.take().expect("stderr is piped");
+        let mut lines = BufReader::new(stderr).lines();
+        let url = lines
+            .by_ref()
+            .map(|line| line.expect("read stderr"))
+            .find_map(|line| line.strip_prefix("listening on ").map(str::to_string))
+            .expect("the mock server prints its address");
+        // keep draining stderr so that the server never blocks on it
+        std::thread::spawn(move || lines.for_each(drop));
+
+        Self { child, url }
+    }
+}
+
+impl Drop for Mock {
+    fn drop(&mut self) {
+        _ = self.child.kill();
+        _ = self.child.wait();
+    }
+}
+
+#[tokio::test]
+async fn list_and_get() {
+    let mock = Mock::start();
+    let client = Client::new(&mock.url);
+
+    let discovery = client.discovery().await.unwrap();
+    assert!(discovery.apis.values().any(|def| def.id.kind == "player"));
+
+    let objects: Vec<_> =
+        client.list("main", "player", &Selection::default()).await.unwrap().collect().await;
+    let alice = objects.into_iter().next().expect("alice is listed").unwrap();
+    assert_eq!(alice.name, "alice");
+    assert_eq!(alice.fields, json!({ "health": 20.0, "world": "world" }));
+
+    let fields = [String::from("world")];
+    let alice = client.get("main", "player", "alice", Some(&fields)).await.unwrap();
+    assert_eq!(alice.fields, json!({ "world": "world" }));
+
+    assert!(client.get("main", "player", "nobody", None).await.is_err());
+}
+
+#[tokio::test]
+async fn watch() {
+    let mock = Mock::start();
+    let client = Client::new(&mock.url);
+
+    let resume = ResumeToken::default();
+    let mut list =
+        client.watch_list("main", "player", &Selection::default(), &resume).await.unwrap();
+    assert!(matches!(list.next().await, Some(Ok(WatchListEvent::Clear))));
+    assert!(matches!(
+        list.next().await,
+        Some(Ok(WatchListEvent::Added { item })) if item.name == "alice",
+    ));
+    assert!(matches!(
+        list.next().await,
+        Some(Ok(WatchListEvent::Added { item })) if item.name == "bob",
+    ));
+
+    let mut single = client
+        .watch_single("main", "player", "alice", None, &ResumeToken::default())
+        .await
+        .unwrap();
+    // the current health is sent first, then the update of the scenario if it is still pending
+    loop {
+        match single.next().await {
+            Some(Ok(WatchSingleEvent::Update { field, value }))
+                if field == "health" && value == json!(14.5) =>
+            {
+                break;
+            }
+            Some(Ok(_)) => {}
+            other => panic!("unexpected {other:?}"),
+        }
+    }
+}
+
+#[tokio::test]
+async fn resume_single_after_removal() {
+    let mock = Mock::start();
+    let client = Client::new(&mock.url);
+
+    let resume = ResumeToken::default();
+    let bob = wait_for(&client, "bob", "nether").await;
+    let mut single = client.watch_single("main", "player", "bob", None, &resume).await.unwrap();
+    // the event ID comes with the last field
+    for _ in 0..2 {
+        assert!(matches!(single.next().await, Some(Ok(WatchSingleEvent::Update { .. }))));
+    }
+    drop(single);
+    assert!(resume.get().is_some());
+
+    // bob leaves and joins again, so the missed changes cannot be replayed
+    wait_for(&client, "bob", "end").await;
+    let mut single = client.watch_single("main", "player", "bob", None, &resume).await.unwrap();
+    let mut fields = bob.fields;
+    for _ in 0..2 {
+        match single.next().await {
+            Some(Ok(WatchSingleEvent::Update { field, value })) => fields[field] = value,
+            other => panic!("unexpected {other:?}"),
+        }
+    }
+    assert_eq!(fields, json!({ "health": 10.0, "world": "end" }));
+}
+
+#[tokio::test]
+async fn record_and_replay() {
+    let session = SharedBuf::default();
+    let recorded = {
+        let mock = Mock::start();
+        let client = Client::new(&mock.url).with_recorder(Recorder::new(session.clone()));
+        list_and_watch(&client).await
+    };
+
+    let path = Path::new(env!("CARGO_TARGET_TMPDIR"))
+        .join(format!("record-and-replay-{}.jsonl", std::process::id()));
+    std::fs::write(&path, &*session.0.lock().unwrap()).unwrap();
+    let mock = Mock::replay(&path);
+    let replayed = list_and_watch(&Client::new(&mock.url)).await;
+    _ = std::fs::remove_file(&path);
+
+    assert_eq!(recorded.0.len(), 1);
+    assert!(matches!(recorded.1[..], [WatchListEvent::Clear, _, _]));
+    assert_eq!(replayed, recorded);
+}
+
+/// Lists the players, then watches them until bob joins.
+async fn list_and_watch(client: &Client) -> (Vec<Object>, Vec<WatchListEvent>) {
+    let objects = client.list("main", "player", &Selection::default()).await.unwrap();
+    let objects = objects.map(Result::unwrap).collect().await;
+
+    let mut events = Vec::new();
+    let resume = ResumeToken::default();
+    let mut list =
+        client.watch_list("main", "player", &Selection::default(), &resume).await.unwrap();
+    while let Some(event) = list.next().await {
+        let event = event.unwrap();
+        let done = matches!(&event, WatchListEvent::Added { item } if item.name == "bob");
+        events.push(event);
+        if done {
+            break;
+        }
+    }
+    (objects, events)
+}
+
+/// A session file kept in memory, which the test reads while the recorder still owns it.
+#[derive(Clone, Default)]
+struct SharedBuf(Arc<Mutex<Vec<u8>>>);
+
+impl Write for SharedBuf {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> { self.0.lock().unwrap().write(buf) }
+
+    fn flush(&mut self) -> io::Result<()> { Ok(()) }
+}
+
+/// Waits until the scenario has moved `name` to `world`.
+async fn wait_for(client: &Client, name: &str, world: &str) -> Object {
+    loop {
+        if let Ok(object) = client.get("main", "player", name, None).await {
+            if object.fields["world"] == world {
+                return object;
+            }
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+}
@@ -0,0 +1,123 @@
+use std::fmt;
+
+use serde::de::DeserializeOwned;
+
+/// An error from an API request, classified so that pages can explain it to the user.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ApiError {
+    /// The server rejected the credentials with 401 or 403.
+    Unauthorized { path: String, status: u16 },
+    /// The server does not know the path, e.g. an unknown kind or object name.
+    NotFound { path: String },
+    /// The server does not support the request method on the path.
+    MethodNotAllowed { path: String },
+    /// The server responded with another unsuccessful status.
+    ServerError { status: u16, body: String },
+    /// The request could not be sent or the response could not be received.
+    Network { message: String },
+    /// A header configured for the requests is not a valid HTTP header.
+    InvalidHeader { message: String },
+    /// The response was received but could not be understood.
+    Decode {
+        message:     String,
+        line:        Option<String>,
+        /// The 1-based line number of `line` in a multi-document response.
+        line_number: Option<usize>,
+    },
+}
+
+impl ApiError {
+    /// A short explanation of the error for the user.
+    pub fn summary(&self) -> &'static str {
+        match self {
+            Self::Unauthorized { .. } => "The server requires you to log in.",
+            Self::NotFound { .. } => "The server does not provide this resource.",
+            Self::MethodNotAllowed { .. } => "The server does not support this operation.",
+            Self::ServerError { .. } => "The server failed to handle the request.",
+            Self::Network { .. } => "Cannot connect to the server.",
+            Self::InvalidHeader { .. } => "A configured request header is invalid.",
+            Self::Decode { .. } => "The server sent a response that cannot be understood.",
+        }
+    }
+
+    /// The translation key of [`ApiError::summary`].
+    pub fn i18n_key(&self) -> &'static str {
+        match self {
+            Self::Unauthorized { .. } => "base-error-unauthorized",
+            Self::NotFound { .. } => "base-error-not-found",
+            Self::MethodNotAllowed { .. } => "base-error-method-not-allowed",
+            Self::ServerError { .. } => "base-error-server",
+            Self::Network { .. } => "base-error-network",
+            Self::InvalidHeader { .. } => "base-error-invalid-header",
+            Self::Decode { .. } => "base-error-decode",
+        }
+    }
+
+    /// Whether retrying the same request may succeed.
+    pub fn is_transient(&self) -> bool {
+        matches!(self, Self::Network { .. } | Self::ServerError { .. } | Self::Decode { .. })
+    }
+
+    pub fn network(err: impl fmt::Display) -> Self { Self::Network { message: err.to_string() } }
+
+    pub fn invalid_header(err: impl fmt::Display) -> Self {
+        Self::InvalidHeader { message: err.to_string() }
+    }
+
+    pub fn decode(message: impl fmt::Display, line: Option<String>) -> Self {
+        Self::Decode { message: message.to_string(), line, line_number: None }
+    }
+
+    /// Records the line of the response body that a decode error occurred on.
+    pub fn on_line(mut self, number: usize) -> Self {
+        if let Self::Decode { line_number, .. } = &mut self {
+            *line_number = Some(number);
+        }
+        self
+    }
+
+    /// Classifies an unsuccessful status code.
+    pub fn from_status(path: &str, status: u16, body: String) -> Self {
+        match status {
+            401 | 403 => Self::Unauthorized { path: path.to_string(), status },
+            404 => Self::NotFound { path: path.to_string() },
+            405 => Self::MethodNotAllowed { path: path.to_string() },
+            status => Self::ServerError { status, body },
+        }
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unauthorized { path, status } => write!(f, "{path} responded with {status}"),
+            Self::NotFound { path } => write!(f, "{path} not found"),
+            Self::MethodNotAllowed { path } => write!(f, "method not allowed on {path}"),
+            Self::ServerError { status, body } => {
+                write!(f, "server responded with {status}: {body}")
+            }
+            Self::Network { message } => write!(f, "network error: {message}"),
+            Self::InvalidHeader { message } => write!(f, "invalid header: {message}"),
+            Self::Decode { message, line, line_number } => {
+                write!(f, "{message}")?;
+                if let Some(number) = line_number {
+                    write!(f, " on line {number}")?;
+                }
+                if let Some(line) = line {
+                    write!(f, " in {line:?}")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for ApiError {}
+
+/// Deserializes a JSON document, reporting the line that failed to parse.
+pub fn decode_json<T: DeserializeOwned>(text: &str) -> Result<T, ApiError> {
+    serde_json::from_str(text).map_err(|err| {
+        let line = text.lines().nth(err.line().saturating_sub(1)).unwrap_or(text);
+        ApiError::decode(err, Some(line.to_string()))
+    })
+}
@@ -0,0 +1,107 @@
+use std::borrow::Borrow;
+use std::collections::{btree_map, BTreeMap};
+use std::fmt;
+use std::marker::PhantomData;
+
+use serde::de::{SeqAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// A map that is serialized as a list, indexed with one of its fields.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IdMap<K: Eq + Ord, V> {
+    map: BTreeMap<K, V>,
+}
+
+impl<K: Eq + Ord, V> IdMap<K, V> {
+    pub fn values(&self) -> impl Iterator<Item = &V> { self.map.values() }
+
+    pub fn get<Q: Eq + Ord + ?Sized>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+    {
+        self.map.get(key)
+    }
+
+    pub fn len(&self) -> usize { self.map.len() }
+
+    pub fn is_empty(&self) -> bool { self.map.is_empty() }
+}
+
+impl<K: Eq + Ord, V: HasId<K>> IdMap<K, V> {
+    /// Inserts a value, replacing any value with the same ID.
+    pub fn insert(&mut self, value: V) -> Option<V> { self.map.insert(value.id(), value) }
+}
+
+impl<K: Eq + Ord, V> Default for IdMap<K, V> {
+    fn default() -> Self { Self { map: BTreeMap::new() } }
+}
+
+impl<'t, K: Eq + Ord, V> IntoIterator for &'t IdMap<K, V> {
+    type Item = &'t V;
+    type IntoIter = btree_map::Values<'t, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter { self.map.values() }
+}
+
+impl<K: Eq + Ord, V: HasId<K>> FromIterator<V> for IdMap<K, V> {
+    fn from_iter<I: IntoIterator<Item = V>>(iter: I) -> Self {
+        Self { map: iter.into_iter().map(|value| (value.id(), value)).collect() }
+    }
+}
+
+impl<K: Eq + Ord, V: Serialize> Serialize for IdMap<K, V> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_seq(self.map.values())
+    }
+}
+
+impl<'de, K, V> Deserialize<'de> for IdMap<K, V>
+where
+    K: Eq + Ord,
+    V: HasId<K> + Deserialize<'de>,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct ListVisitor<K, V> {
+            marker: PhantomData<(K, V)>,
+        }
+
+        impl<'de, K, V> Visitor<'de> for ListVisitor<K, V>
+        where
+            K: Eq + Ord,
+            V: HasId<K> + Deserialize<'de>,
+        {
+            type Value = BTreeMap<K, V>;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a sequence")
+            }
+
+            fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+            where
+                A: SeqAccess<'de>,
+            {
+                let mut map = BTreeMap::new();
+
+                while let Some(value) = seq.next_element::<V>()? {
+                    map.insert(value.id(), value);
+                }
+
+                Ok(map)
+            }
+        }
+
+        let visitor = ListVisitor { marker: PhantomData };
+        let map = deserializer.deserialize_seq(visitor)?;
+        Ok(Self { map })
+    }
+}
+
+pub trait HasId<Id> {
+    fn id(&self) -> Id;
+}
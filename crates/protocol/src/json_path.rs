@@ -0,0 +1,66 @@
+use std::fmt;
+
+/// Looks up a dot-separated path, e.g. `position.x`, in a JSON value.
+pub fn get_json_path<'t>(
+    mut value: &'t serde_json::Value,
+    path: &str,
+) -> Option<&'t serde_json::Value> {
+    for part in path.split('.') {
+        if !part.is_empty() {
+            let serde_json::Value::Object(map) = value else { return None };
+            value = map.get(part)?;
+        }
+    }
+
+    Some(value)
+}
+
+/// Replaces the value at a dot-separated path, which must already exist.
+pub fn set_json_path(
+    mut object: &mut serde_json::Value,
+    path: &str,
+    value: serde_json::Value,
+) -> Result<(), JsonPathError> {
+    for part in path.split('.') {
+        let serde_json::Value::Object(map) = object else {
+            return Err(JsonPathError(format!("{path} is not under an object")));
+        };
+        object = match map.get_mut(part) {
+            Some(object) => object,
+            None => return Err(JsonPathError(format!("{part:?} does not exist"))),
+        };
+    }
+
+    *object = value;
+
+    Ok(())
+}
+
+/// Writes the value at a dot-separated path, creating the intermediate objects as needed.
+///
+/// This is how servers assemble an object from the values of nested field paths.
+pub fn insert_json_path(
+    mut object: &mut serde_json::Value,
+    path: &str,
+    value: serde_json::Value,
+) -> Result<(), JsonPathError> {
+    for part in path.split('.') {
+        let serde_json::Value::Object(map) = object else {
+            return Err(JsonPathError(format!("{path} is not under an object")));
+        };
+        object = map.entry(part).or_insert_with(|| serde_json::Value::Object(Default::default()));
+    }
+
+    *object = value;
+
+    Ok(())
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct JsonPathError(String);
+
+impl fmt::Display for JsonPathError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result { f.write_str(&self.0) }
+}
+
+impl std::error::Error for JsonPathError {}
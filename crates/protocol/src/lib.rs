@@ -0,0 +1,263 @@
+//! The JSON schema of the WebConsole API.
+//!
+//! This crate has no platform-specific dependencies
+//! so that the site, servers, proxies and command line tools share the same types.
+
+use std::borrow::Borrow;
+use std::cmp;
+use std::hash::Hash;
+
+use serde::{Deserialize, Serialize};
+
+mod error;
+pub use error::{decode_json, ApiError};
+mod id_map;
+pub use id_map::{HasId, IdMap};
+mod json_path;
+pub use json_path::{get_json_path, insert_json_path, set_json_path, JsonPathError};
+mod query;
+pub use query::{object_path, parse_object_query, Selection};
+pub mod mux;
+pub mod ndjson;
+pub mod session;
+pub mod sse;
+pub mod store;
+
+/// A translation key resolved by the locale files served by the API server.
+pub type I18nKey = String;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Discovery {
+    pub groups:     IdMap<String, Group>,
+    pub apis:       IdMap<GroupKind, ObjectDef>,
+    #[serde(default)]
+    pub transports: Transports,
+}
+
+/// Optional transports supported by the server in addition to SSE.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct Transports {
+    /// Path of the endpoint that multiplexes watches over a WebSocket.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub websocket: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ObjectDef {
+    #[serde(flatten)]
+    pub id:           GroupKind,
+    pub display_name: I18nKey,
+    #[serde(default)]
+    pub metadata:     KnownObjectMetadata,
+    pub fields:       IdMap<String, FieldDef>,
+}
+impl HasId<GroupKind> for ObjectDef {
+    fn id(&self) -> GroupKind { self.id.clone() }
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct KnownObjectMetadata {
+    #[serde(rename = "webconsole/site/hide-name")]
+    #[serde(default)]
+    pub hide_name: bool,
+
+    #[serde(rename = "webconsole/site/desc-name")]
+    #[serde(default)]
+    pub desc_name: bool,
+
+    #[serde(rename = "webconsole/site/default-display-mode")]
+    #[serde(default)]
+    pub default_display_mode: DisplayMode,
+}
+
+/// How the site displays a list of objects.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DisplayMode {
+    #[default]
+    Cards,
+    Table,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FieldDef {
+    pub path:         String,
+    pub display_name: I18nKey,
+    #[serde(default)]
+    pub metadata:     KnownFieldMetadata,
+    #[serde(rename = "type")]
+    pub ty:           FieldType,
+}
+impl HasId<String> for FieldDef {
+    fn id(&self) -> String { self.path.clone() }
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct KnownFieldMetadata {
+    #[serde(rename = "webconsole/site/display-priority")]
+    #[serde(default)]
+    pub display_priority: i32,
+    #[serde(rename = "webconsole/site/hide-by-default")]
+    #[serde(default)]
+    pub hide_by_default:  bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum FieldType {
+    String {},
+    Int64 {
+        #[serde(default)]
+        is_timestamp: bool,
+        #[serde(default)]
+        min:          Option<i64>,
+        #[serde(default)]
+        max:          Option<i64>,
+    },
+    Float64 {
+        #[serde(default)]
+        is_timestamp: bool,
+        #[serde(default)]
+        min:          Option<f64>,
+        #[serde(default)]
+        max:          Option<f64>,
+    },
+    Bool {},
+    Enum {
+        options: IdMap<String, EnumOption>,
+    },
+    Object {
+        #[serde(flatten)]
+        gk: GroupKind,
+    },
+    Nullable {
+        item: Box<FieldType>,
+    },
+    List {
+        item: Box<FieldType>,
+    },
+    Compound {
+        fields: IdMap<String, CompoundSubfield>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CompoundSubfield {
+    pub key:  String,
+    pub name: I18nKey,
+    #[serde(rename = "type")]
+    pub ty:   FieldType,
+}
+
+impl HasId<String> for CompoundSubfield {
+    fn id(&self) -> String { self.key.clone() }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EnumOption {
+    pub id:   String,
+    pub i18n: I18nKey,
+}
+
+impl HasId<String> for EnumOption {
+    fn id(&self) -> String { self.id.clone() }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Group {
+    pub id:               String,
+    pub display_name:     I18nKey,
+    pub display_priority: u32,
+}
+impl HasId<String> for Group {
+    fn id(&self) -> String { self.id.clone() }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct GroupKind {
+    pub group: String,
+    pub kind:  String,
+}
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct GroupKindRef<'t> {
+    pub group: &'t str,
+    pub kind:  &'t str,
+}
+
+pub trait GroupKindDyn {
+    fn group(&self) -> &str;
+    fn kind(&self) -> &str;
+}
+impl GroupKindDyn for GroupKind {
+    fn group(&self) -> &str { &self.group }
+    fn kind(&self) -> &str { &self.kind }
+}
+impl<'t> GroupKindDyn for GroupKindRef<'t> {
+    fn group(&self) -> &str { self.group }
+    fn kind(&self) -> &str { self.kind }
+}
+
+impl Hash for dyn GroupKindDyn + '_ {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.group().hash(state);
+        self.kind().hash(state);
+    }
+}
+impl PartialEq for dyn GroupKindDyn + '_ {
+    fn eq(&self, other: &Self) -> bool {
+        self.group() == other.group() && self.kind() == other.kind()
+    }
+}
+impl Eq for dyn GroupKindDyn + '_ {}
+impl PartialOrd for dyn GroupKindDyn + '_ {
+    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> { Some(self.cmp(other)) }
+}
+impl Ord for dyn GroupKindDyn + '_ {
+    fn cmp(&self, other: &Self) -> cmp::Ordering {
+        self.group().cmp(other.group()).then_with(|| self.kind().cmp(other.kind()))
+    }
+}
+
+impl<'t> Borrow<dyn GroupKindDyn + 't> for GroupKind {
+    fn borrow(&self) -> &(dyn GroupKindDyn + 't) { self }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Object {
+    #[serde(rename = "_name")]
+    pub name:   String,
+    #[serde(flatten)]
+    pub fields: serde_json::Value,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "event")]
+pub enum WatchListEvent {
+    /// The server could not resume the watch; all previously received objects are invalid.
+    Clear,
+    /// The server is replaying the events missed since the `Last-Event-ID` sent by the client.
+    Resumed,
+    Added {
+        item: Object,
+    },
+    Removed {
+        name: String,
+    },
+    FieldUpdate {
+        name:  String,
+        field: String,
+        value: serde_json::Value,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "event")]
+pub enum WatchSingleEvent {
+    /// The server is replaying the events missed since the `Last-Event-ID` sent by the client.
+    Resumed,
+    Update {
+        field: String,
+        value: serde_json::Value,
+    },
+}
@@ -0,0 +1,54 @@
+//! Frames of the WebSocket transport that multiplexes many watches over one connection.
+//!
+//! All frames are JSON text messages tagged with `type`.
+//! The client sends `subscribe` frames with a client-chosen `id`,
+//! optionally restricted with the same `fields` and `limit` as the HTTP query parameters,
+//! and `unsubscribe` frames with the same `id` when the subscription is dropped.
+//! The server replies with `event` frames carrying the same JSON objects as the SSE `data:` lines,
+//! and an `error` or `end` frame when it stops serving a subscription.
+//! The optional `event_id` of an `event` frame plays the role of the SSE `id:` line,
+//! and is sent back as `last_event_id` when the subscription is reopened.
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ClientFrame {
+    Subscribe {
+        id:            u64,
+        group:         String,
+        kind:          String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        name:          Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        fields:        Option<Vec<String>>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        limit:         Option<u32>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        last_event_id: Option<String>,
+    },
+    Unsubscribe {
+        id: u64,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServerFrame {
+    Event {
+        id:       u64,
+        event:    serde_json::Value,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        event_id: Option<String>,
+    },
+    Error {
+        id:      u64,
+        /// The HTTP status that the equivalent SSE request would have responded with.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        status:  Option<u16>,
+        message: String,
+    },
+    End {
+        id: u64,
+    },
+}
@@ -0,0 +1,93 @@
+/// A complete line of a newline-delimited JSON stream.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Line {
+    /// The 1-based line number in the response body.
+    pub number: usize,
+    pub bytes:  Vec<u8>,
+}
+
+/// An incremental splitter for `application/x-ndjson` bodies.
+///
+/// Input may be split at arbitrary byte boundaries.
+/// Blank lines are skipped but still counted in line numbers.
+#[derive(Default)]
+pub struct Decoder {
+    line:   Vec<u8>,
+    number: usize,
+}
+
+impl Decoder {
+    /// Feeds a chunk of the body and returns the lines completed by it.
+    pub fn feed(&mut self, chunk: &[u8]) -> Vec<Line> {
+        let mut lines = Vec::new();
+
+        let mut rest = chunk;
+        while let Some(pos) = rest.iter().position(|&byte| byte == b'\n') {
+            self.line.extend_from_slice(&rest[..pos]);
+            rest = &rest[pos + 1..];
+            lines.extend(self.take_line());
+        }
+        self.line.extend_from_slice(rest);
+
+        lines
+    }
+
+    /// Returns the last line if the body does not end with a newline.
+    pub fn finish(&mut self) -> Option<Line> {
+        if self.line.is_empty() {
+            return None;
+        }
+        self.take_line()
+    }
+
+    fn take_line(&mut self) -> Option<Line> {
+        self.number += 1;
+
+        let mut bytes = std::mem::take(&mut self.line);
+        if bytes.last() == Some(&b'\r') {
+            bytes.pop();
+        }
+        if bytes.iter().all(u8::is_ascii_whitespace) {
+            return None;
+        }
+
+        Some(Line { number: self.number, bytes })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode(chunks: &[&[u8]]) -> Vec<(usize, String)> {
+        let mut decoder = Decoder::default();
+        let mut lines: Vec<_> = chunks.iter().flat_map(|chunk| decoder.feed(chunk)).collect();
+        lines.extend(decoder.finish());
+        lines
+            .into_iter()
+            .map(|line| (line.number, String::from_utf8(line.bytes).unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn lines_split_across_chunks() {
+        let lines = decode(&[b"{\"a\"", b":1}\n{\"b\":2}\r", b"\n\n", b"{\"c\":3}"]);
+        assert_eq!(
+            lines,
+            [(1, String::from("{\"a\":1}")), (2, "{\"b\":2}".into()), (4, "{\"c\":3}".into())],
+        );
+    }
+
+    #[test]
+    fn line_numbers_count_blank_lines() {
+        let lines = decode(&[b"\n", b" \r\n", b"1\n", b"", b"\n2\n"]);
+        assert_eq!(lines, [(3, String::from("1")), (5, "2".into())]);
+    }
+
+    #[test]
+    fn finish_without_trailing_line() {
+        let mut decoder = Decoder::default();
+        assert_eq!(decoder.feed(b"1\n").len(), 1);
+        assert_eq!(decoder.finish(), None);
+    }
+}
@@ -0,0 +1,186 @@
+use std::fmt;
+
+use serde::de::{self, MapAccess, SeqAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize};
+
+/// Restricts the fields and the number of objects returned by an object request.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Selection {
+    /// Only return the fields with these paths. All fields are returned if unspecified.
+    pub fields: Option<Vec<String>>,
+    /// Return at most this many objects. Ignored for single-object requests.
+    pub limit:  Option<u32>,
+}
+
+/// Query parameters accepted by object requests.
+#[derive(Serialize)]
+struct ObjectQuery<'t> {
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    watch:  bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    fields: Option<FieldList<'t>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    limit:  Option<u32>,
+}
+
+impl<'t> ObjectQuery<'t> {
+    fn new(watch: bool, selection: &'t Selection) -> Self {
+        Self { watch, fields: selection.fields.as_deref().map(FieldList), limit: selection.limit }
+    }
+}
+
+/// Serializes as `fields[0]=a&fields[1]=b`.
+///
+/// An empty sequence would be omitted from the query string and select all fields,
+/// so an empty list is serialized as `fields=` instead.
+struct FieldList<'t>(&'t [String]);
+
+impl<'t> Serialize for FieldList<'t> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if self.0.is_empty() {
+            serializer.serialize_str("")
+        } else {
+            self.0.serialize(serializer)
+        }
+    }
+}
+
+/// Query parameters parsed by servers, accepting everything that [`ObjectQuery`] produces.
+#[derive(Deserialize)]
+struct OwnedObjectQuery {
+    /// Not an `Option`, which would parse `fields=` as unspecified.
+    #[serde(default)]
+    fields: OwnedFieldList,
+    #[serde(default)]
+    limit:  Option<u32>,
+}
+
+/// Deserializes `fields[0]=a&fields[1]=b`, `fields[]=a` or `fields=`.
+#[derive(Default)]
+struct OwnedFieldList(Option<Vec<String>>);
+
+impl<'de> Deserialize<'de> for OwnedFieldList {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct FieldListVisitor;
+
+        impl<'de> Visitor<'de> for FieldListVisitor {
+            type Value = OwnedFieldList;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "a list of field paths or an empty string")
+            }
+
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<Self::Value, E> {
+                if value.is_empty() {
+                    Ok(OwnedFieldList(Some(Vec::new())))
+                } else {
+                    Ok(OwnedFieldList(Some(vec![value.to_string()])))
+                }
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+                let mut fields = Vec::new();
+                while let Some(field) = seq.next_element()? {
+                    fields.push(field);
+                }
+                Ok(OwnedFieldList(Some(fields)))
+            }
+
+            /// Indexed lists are parsed as maps from the index to the value.
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+                let mut fields = Vec::new();
+                while let Some((index, field)) = map.next_entry::<String, String>()? {
+                    fields.push((index.parse::<usize>().unwrap_or(usize::MAX), field));
+                }
+                fields.sort_by_key(|&(index, _)| index);
+                Ok(OwnedFieldList(Some(fields.into_iter().map(|(_, field)| field).collect())))
+            }
+        }
+
+        deserializer.deserialize_any(FieldListVisitor)
+    }
+}
+
+/// Parses the query string of an object request into the `watch` flag and the selection.
+///
+/// This is the inverse of [`object_path`] for servers.
+pub fn parse_object_query(query: &str) -> Result<(bool, Selection), serde_qs::Error> {
+    // like `isset($_GET["watch"])`, any value enables watching, including `?watch` without `=`
+    let watch = query.split('&').any(|pair| pair.split('=').next() == Some("watch"));
+
+    let query: OwnedObjectQuery = serde_qs::Config::new(5, false).deserialize_str(query)?;
+    let selection = Selection { fields: query.fields.0, limit: query.limit };
+    Ok((watch, selection))
+}
+
+/// Appends the query parameters of an object request to `path`, e.g. `group/kind/name`.
+pub fn object_path(path: &str, watch: bool, selection: &Selection) -> String {
+    with_query(path, &ObjectQuery::new(watch, selection))
+}
+
+fn with_query(path: &str, query: &impl Serialize) -> String {
+    let query = serde_qs::to_string(query).expect("query parameters are serializable");
+    if query.is_empty() {
+        path.to_string()
+    } else {
+        format!("{path}?{query}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn query(path: &str) -> &str { path.split_once('?').map_or("", |(_, query)| query) }
+
+    fn fields(fields: &[&str]) -> Option<Vec<String>> {
+        Some(fields.iter().map(|field| field.to_string()).collect())
+    }
+
+    #[test]
+    fn round_trip() {
+        let selections = [
+            Selection::default(),
+            Selection { fields: fields(&[]), limit: None },
+            Selection { fields: fields(&["a"]), limit: None },
+            Selection { fields: fields(&["a.b", "c"]), limit: Some(2) },
+            Selection { fields: None, limit: Some(10) },
+        ];
+        for watch in [false, true] {
+            for selection in &selections {
+                let path = object_path("main/player", watch, selection);
+                let parsed = parse_object_query(query(&path)).unwrap();
+                assert_eq!(parsed, (watch, selection.clone()), "{path}");
+            }
+        }
+    }
+
+    #[test]
+    fn empty_fields_select_nothing() {
+        let path =
+            object_path("main/player", false, &Selection { fields: fields(&[]), limit: None });
+        assert_eq!(path, "main/player?fields=");
+        assert_eq!(parse_object_query("fields=").unwrap().1.fields, fields(&[]));
+        assert_eq!(parse_object_query("").unwrap().1.fields, None);
+    }
+
+    #[test]
+    fn parse_php_forms() {
+        assert_eq!(parse_object_query("fields[]=a").unwrap().1.fields, fields(&["a"]));
+        assert_eq!(parse_object_query("fields[0]=a").unwrap().1.fields, fields(&["a"]));
+        assert_eq!(parse_object_query("fields=a").unwrap().1.fields, fields(&["a"]));
+        assert_eq!(
+            parse_object_query("fields[1]=b&fields[0]=a").unwrap().1.fields,
+            fields(&["a", "b"]),
+        );
+    }
+
+    #[test]
+    fn parse_watch() {
+        assert!(parse_object_query("watch").unwrap().0);
+        assert!(parse_object_query("watch=").unwrap().0);
+        assert!(parse_object_query("limit=1&watch=0").unwrap().0);
+        assert!(!parse_object_query("limit=1").unwrap().0);
+        assert!(!parse_object_query("watcher=1").unwrap().0);
+    }
+}
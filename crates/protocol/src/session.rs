@@ -0,0 +1,57 @@
+//! Recordings of the traffic between a client and the API server.
+//!
+//! A session file is newline-delimited JSON with one [`Entry`] per line.
+//! Each request opens an exchange identified by [`Entry::exchange`],
+//! followed by the records of its response in the order they were received.
+//! Exchanges of concurrent requests may interleave.
+//!
+//! Watches multiplexed over a WebSocket are recorded as if they were event streams,
+//! with the same path as the SSE request and no `response` record.
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Entry {
+    /// Milliseconds since the recording started.
+    pub at:       f64,
+    /// Identifies the request that this entry belongs to.
+    pub exchange: u64,
+    #[serde(flatten)]
+    pub record:   Record,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Record {
+    /// A request to `path`, relative to the server URL and including the query string.
+    Request {
+        path:          String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        last_event_id: Option<String>,
+    },
+    /// The status of the response, with its body if the request was rejected.
+    Response {
+        status: u16,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        body:   Option<String>,
+    },
+    /// The whole body of a JSON or text response.
+    Body { text: String },
+    /// A line of a newline-delimited JSON list.
+    Line { line: String },
+    /// An event of a watch.
+    Event {
+        /// The SSE event type, `message` for the events of the protocol.
+        event: String,
+        data:  String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        id:    Option<String>,
+        /// The reconnection time requested with `retry:`, in milliseconds.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        retry: Option<u64>,
+    },
+    /// The request or the response body failed with a network error.
+    Error { message: String },
+    /// The server closed the response body.
+    End,
+}
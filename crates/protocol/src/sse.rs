@@ -0,0 +1,222 @@
+use std::time::Duration;
+
+/// A dispatched server-sent event.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Event {
+    /// The `event:` type, `message` if the server did not specify one.
+    pub event: String,
+    pub data:  String,
+    /// The last event ID seen on the stream when this event was dispatched.
+    pub id:    Option<String>,
+    /// The reconnection time most recently requested with `retry:`.
+    pub retry: Option<Duration>,
+}
+
+impl Event {
+    /// The ID to resume the stream from, `None` if an empty `id:` line reset it.
+    pub fn resume_id(&self) -> Option<&str> { self.id.as_deref().filter(|id| !id.is_empty()) }
+}
+
+/// An incremental `text/event-stream` parser.
+///
+/// Input may be split at arbitrary byte boundaries,
+/// including in the middle of a UTF-8 sequence or a CRLF pair.
+#[derive(Default)]
+pub struct Parser {
+    line:       Vec<u8>,
+    skip_lf:    bool,
+    started:    bool,
+    event_type: String,
+    data:       String,
+    has_data:   bool,
+    id:         Option<String>,
+    retry:      Option<Duration>,
+}
+
+impl Parser {
+    /// Feeds a chunk of the stream and returns the events completed by it.
+    pub fn feed(&mut self, mut chunk: &[u8]) -> Vec<Event> {
+        let mut events = Vec::new();
+
+        if !self.started && !chunk.is_empty() {
+            let bom = b"\xEF\xBB\xBF";
+            let prefix_len = cmp_prefix(&self.line, chunk, bom);
+            if self.line.len() + prefix_len < bom.len() && prefix_len == chunk.len() {
+                // the chunk is still a prefix of the BOM, wait for more input
+                self.line.extend_from_slice(chunk);
+                return events;
+            }
+            self.started = true;
+            if self.line.len() + prefix_len == bom.len() {
+                self.line.clear();
+                chunk = &chunk[prefix_len..];
+            }
+        }
+
+        for &byte in chunk {
+            if self.skip_lf {
+                self.skip_lf = false;
+                if byte == b'\n' {
+                    continue;
+                }
+            }
+
+            match byte {
+                b'\r' | b'\n' => {
+                    self.skip_lf = byte == b'\r';
+                    let line = std::mem::take(&mut self.line);
+                    if let Some(event) = self.process_line(&String::from_utf8_lossy(&line)) {
+                        events.push(event);
+                    }
+                }
+                _ => self.line.push(byte),
+            }
+        }
+
+        events
+    }
+
+    /// The value of the last `id:` line, used to resume the stream.
+    pub fn last_event_id(&self) -> Option<&str> { self.id.as_deref() }
+
+    fn process_line(&mut self, line: &str) -> Option<Event> {
+        if line.is_empty() {
+            return self.dispatch();
+        }
+
+        if line.starts_with(':') {
+            return None;
+        }
+
+        let (field, value) = match line.split_once(':') {
+            Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+            None => (line, ""),
+        };
+
+        match field {
+            "event" => self.event_type = value.to_string(),
+            "data" => {
+                if self.has_data {
+                    self.data.push('\n');
+                }
+                self.data.push_str(value);
+                self.has_data = true;
+            }
+            "id" => {
+                if !value.contains('\0') {
+                    self.id = Some(value.to_string());
+                }
+            }
+            "retry" => {
+                if !value.is_empty() && value.bytes().all(|byte| byte.is_ascii_digit()) {
+                    if let Ok(millis) = value.parse() {
+                        self.retry = Some(Duration::from_millis(millis));
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        None
+    }
+
+    fn dispatch(&mut self) -> Option<Event> {
+        let event_type = std::mem::take(&mut self.event_type);
+        let data = std::mem::take(&mut self.data);
+
+        if !std::mem::replace(&mut self.has_data, false) {
+            return None;
+        }
+
+        Some(Event {
+            event: if event_type.is_empty() { String::from("message") } else { event_type },
+            data,
+            id: self.id.clone(),
+            retry: self.retry,
+        })
+    }
+}
+
+/// Returns the length of the prefix of `chunk` that continues `buffered` as a prefix of `expect`.
+fn cmp_prefix(buffered: &[u8], chunk: &[u8], expect: &[u8]) -> usize {
+    let Some(rest) = expect.get(buffered.len()..) else { return 0 };
+    if !expect.starts_with(buffered) {
+        return 0;
+    }
+    rest.iter().zip(chunk).take_while(|(a, b)| a == b).count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(chunks: &[&[u8]]) -> Vec<Event> {
+        let mut parser = Parser::default();
+        chunks.iter().flat_map(|chunk| parser.feed(chunk)).collect()
+    }
+
+    fn event(event: &str, data: &str, id: Option<&str>, retry: Option<u64>) -> Event {
+        Event {
+            event: event.to_string(),
+            data:  data.to_string(),
+            id:    id.map(str::to_string),
+            retry: retry.map(Duration::from_millis),
+        }
+    }
+
+    #[test]
+    fn fields() {
+        let events = parse(&[b": comment\nevent: update\ndata: a\ndata:b\nid: 1\nretry: 500\n\n"]);
+        assert_eq!(events, [event("update", "a\nb", Some("1"), Some(500))]);
+    }
+
+    #[test]
+    fn id_and_retry_persist() {
+        let events = parse(&[b"id: 1\nretry: 10\ndata: a\n\ndata: b\n\nretry: x\nid\ndata: c\n\n"]);
+        assert_eq!(
+            events,
+            [
+                event("message", "a", Some("1"), Some(10)),
+                event("message", "b", Some("1"), Some(10)),
+                event("message", "c", Some(""), Some(10)),
+            ],
+        );
+    }
+
+    #[test]
+    fn empty_id_resets_resume_id() {
+        let events = parse(&[b"id: 1\ndata: a\n\nid\ndata: b\n\nid:\ndata: c\n\n"]);
+        let resume_ids: Vec<_> = events.iter().map(Event::resume_id).collect();
+        assert_eq!(resume_ids, [Some("1"), None, None]);
+        assert_eq!(events[1].id.as_deref(), Some(""));
+        assert_eq!(parse(&[b"data: a\n\n"])[0].resume_id(), None);
+    }
+
+    #[test]
+    fn events_without_data_are_not_dispatched() {
+        let mut parser = Parser::default();
+        assert_eq!(parser.feed(b"event: ping\nid: 2\n\n"), []);
+        assert_eq!(parser.last_event_id(), Some("2"));
+        assert_eq!(parser.feed(b"data\n\n"), [event("message", "", Some("2"), None)]);
+    }
+
+    #[test]
+    fn line_endings() {
+        let expected = [event("message", "a", None, None), event("message", "b", None, None)];
+        assert_eq!(parse(&[b"data: a\r\n\r\ndata: b\r\n\r\n"]), expected);
+        assert_eq!(parse(&[b"data: a\r\rdata: b\n\n"]), expected);
+        // a CRLF split across chunks is one line break
+        assert_eq!(parse(&[b"data: a\r", b"\n\r", b"\ndata: b\r\n\r\n"]), expected);
+    }
+
+    #[test]
+    fn split_across_chunks() {
+        let stream = "\u{feff}data: é\nid: 7\n\ndata: b\n\n".as_bytes();
+        let expected =
+            [event("message", "é", Some("7"), None), event("message", "b", Some("7"), None)];
+        for size in 1..stream.len() {
+            let chunks: Vec<_> = stream.chunks(size).collect();
+            assert_eq!(parse(&chunks), expected, "chunks of {size} bytes");
+        }
+    }
+}
@@ -0,0 +1,108 @@
+use std::collections::BTreeMap;
+
+use crate::{set_json_path, Object, WatchListEvent};
+
+/// The objects of a list watch, materialised from its events.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ObjectStore {
+    objects: BTreeMap<String, Object>,
+}
+
+impl ObjectStore {
+    /// The objects keyed by name.
+    pub fn objects(&self) -> &BTreeMap<String, Object> { &self.objects }
+
+    pub fn get(&self, name: &str) -> Option<&Object> { self.objects.get(name) }
+
+    pub fn len(&self) -> usize { self.objects.len() }
+
+    pub fn is_empty(&self) -> bool { self.objects.is_empty() }
+
+    pub fn clear(&mut self) { self.objects.clear(); }
+
+    /// Applies an event and returns whether the objects changed.
+    ///
+    /// Updates of unknown objects or fields are logged and ignored.
+    pub fn apply(&mut self, event: &WatchListEvent) -> bool {
+        match event {
+            WatchListEvent::Clear => {
+                self.objects.clear();
+                true
+            }
+            WatchListEvent::Resumed => false,
+            WatchListEvent::Added { item } => {
+                self.objects.insert(item.name.clone(), item.clone());
+                true
+            }
+            WatchListEvent::Removed { name } => self.objects.remove(name).is_some(),
+            WatchListEvent::FieldUpdate { name, field, value } => {
+                let Some(object) = self.objects.get_mut(name) else { return false };
+                if let Err(err) = set_json_path(&mut object.fields, field, value.clone()) {
+                    log::warn!("invalid json path: {err}");
+                    return false;
+                }
+                true
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn added(name: &str, fields: serde_json::Value) -> WatchListEvent {
+        WatchListEvent::Added { item: Object { name: name.to_string(), fields } }
+    }
+
+    #[test]
+    fn add_update_remove() {
+        let mut store = ObjectStore::default();
+        assert!(store.apply(&added("a", json!({ "pos": { "x": 1 } }))));
+        assert!(store.apply(&added("b", json!({}))));
+
+        let update = WatchListEvent::FieldUpdate {
+            name:  "a".into(),
+            field: "pos.x".into(),
+            value: json!(2),
+        };
+        assert!(store.apply(&update));
+        assert_eq!(store.get("a").unwrap().fields, json!({ "pos": { "x": 2 } }));
+
+        assert!(store.apply(&WatchListEvent::Removed { name: "b".into() }));
+        assert!(!store.apply(&WatchListEvent::Removed { name: "b".into() }));
+        assert_eq!(store.objects().keys().collect::<Vec<_>>(), ["a"]);
+    }
+
+    #[test]
+    fn added_replaces_object() {
+        let mut store = ObjectStore::default();
+        store.apply(&added("a", json!({ "x": 1 })));
+        store.apply(&added("a", json!({ "y": 2 })));
+        assert_eq!(store.len(), 1);
+        assert_eq!(store.get("a").unwrap().fields, json!({ "y": 2 }));
+    }
+
+    #[test]
+    fn update_of_unknown_object_is_ignored() {
+        let mut store = ObjectStore::default();
+        let update =
+            WatchListEvent::FieldUpdate { name: "a".into(), field: "x".into(), value: json!(1) };
+        assert!(!store.apply(&update));
+        assert!(store.is_empty());
+    }
+
+    #[test]
+    fn clear_and_resumed() {
+        let mut store = ObjectStore::default();
+        store.apply(&added("a", json!({})));
+
+        assert!(!store.apply(&WatchListEvent::Resumed));
+        assert_eq!(store.len(), 1);
+
+        assert!(store.apply(&WatchListEvent::Clear));
+        assert!(store.is_empty());
+    }
+}
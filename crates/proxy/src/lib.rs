@@ -0,0 +1,102 @@
+//! Helpers shared by the servers that proxy requests to API servers,
+//! i.e. the gateway and the aggregator.
+
+use axum::body::StreamBody;
+use axum::http::{header, HeaderMap, HeaderName, StatusCode};
+use axum::response::{IntoResponse, Response};
+use futures::TryStreamExt;
+
+/// A plain text response with the status and its reason, e.g. `404 Not Found`.
+pub fn error(status: StatusCode) -> Response {
+    let reason = status.canonical_reason().unwrap_or_default();
+    (status, format!("{} {reason}", status.as_u16())).into_response()
+}
+
+/// Decodes like PHP's `urldecode`, which the plugin applies to the path before routing it,
+/// so that a proxy routes and authorizes encoded paths the same way as the API server.
+pub fn url_decode(str: &str) -> String {
+    let bytes = str.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes.get(i + 1..i + 3).and_then(|hex| std::str::from_utf8(hex).ok());
+        match (bytes[i], hex.and_then(|hex| u8::from_str_radix(hex, 16).ok())) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (b'+', _) => {
+                decoded.push(b' ');
+                i += 1;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Headers that only apply to a single connection, or that proxies set themselves.
+pub const SKIPPED_HEADERS: &[HeaderName] = &[
+    header::AUTHORIZATION,
+    header::CONNECTION,
+    header::HOST,
+    header::PROXY_AUTHENTICATE,
+    header::PROXY_AUTHORIZATION,
+    header::TE,
+    header::TRAILER,
+    header::TRANSFER_ENCODING,
+    header::UPGRADE,
+];
+
+/// Copies the end-to-end headers, except CORS headers which the proxy sets itself.
+pub fn copy_headers(from: &HeaderMap, to: &mut HeaderMap) {
+    for (name, value) in from {
+        let cors = name.as_str().starts_with("access-control-");
+        if !cors && !SKIPPED_HEADERS.contains(name) {
+            to.append(name.clone(), value.clone());
+        }
+    }
+}
+
+/// Streams the response of an API server, which is necessary for watches.
+pub fn stream_response(upstream: reqwest::Response) -> Response {
+    let status = upstream.status();
+    let mut headers = HeaderMap::new();
+    copy_headers(upstream.headers(), &mut headers);
+    let body = StreamBody::new(upstream.bytes_stream().map_err(axum::Error::new));
+    (status, headers, body).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::HeaderValue;
+
+    use super::*;
+
+    #[test]
+    fn url_decode_like_php() {
+        assert_eq!(url_decode("main%2Fplayer"), "main/player");
+        assert_eq!(url_decode("a+b%20c"), "a b c");
+        assert_eq!(url_decode("100%"), "100%");
+        assert_eq!(url_decode("%zz%4"), "%zz%4");
+    }
+
+    #[test]
+    fn copy_end_to_end_headers() {
+        let mut from = HeaderMap::new();
+        from.insert(header::CONTENT_TYPE, HeaderValue::from_static("text/event-stream"));
+        from.insert(header::AUTHORIZATION, HeaderValue::from_static("Bearer secret"));
+        from.insert(header::CONNECTION, HeaderValue::from_static("keep-alive"));
+        from.insert(header::ACCESS_CONTROL_ALLOW_ORIGIN, HeaderValue::from_static("*"));
+        from.insert("last-event-id", HeaderValue::from_static("1"));
+
+        let mut to = HeaderMap::new();
+        copy_headers(&from, &mut to);
+        let mut names: Vec<_> = to.keys().map(HeaderName::as_str).collect();
+        names.sort_unstable();
+        assert_eq!(names, ["content-type", "last-event-id"]);
+    }
+}
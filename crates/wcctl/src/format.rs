@@ -0,0 +1,170 @@
+//! Formats field values the same way as the site's `InlineDisplay`.
+
+use chrono::TimeZone;
+use fluent::FluentArgs;
+use webconsole_client::protocol::FieldType;
+
+use crate::i18n::I18n;
+
+const LIST_DISPLAY_LIMIT: usize = 3;
+
+fn round(number: f64) -> f64 { (number * 10.).round() / 10. }
+
+/// Formats a value of type `ty`.
+///
+/// Nested values are abbreviated like in the table cells of the site.
+pub fn value(i18n: &I18n, ty: &FieldType, value: &serde_json::Value, nested: bool) -> String {
+    use serde_json::Value;
+
+    match ty {
+        FieldType::String {} => match value {
+            Value::String(string) => string.clone(),
+            v => invalid_type("String", v),
+        },
+        FieldType::Int64 { is_timestamp, .. } | FieldType::Float64 { is_timestamp, .. } => {
+            match value {
+                Value::Number(number) => {
+                    if *is_timestamp {
+                        match number.as_f64() {
+                            Some(number) => timestamp(number),
+                            None => String::from("invalid timestamp"),
+                        }
+                    } else {
+                        number
+                            .as_f64()
+                            .map(|v| round(v).to_string())
+                            .or_else(|| number.as_u64().map(|v| format!("{v:.1}")))
+                            .or_else(|| number.as_i64().map(|v| format!("{v:.1}")))
+                            .unwrap_or_else(|| number.to_string())
+                    }
+                }
+                v => invalid_type("Number", v),
+            }
+        }
+        FieldType::Bool {} => match value {
+            Value::Bool(bool) => bool.to_string(),
+            v => invalid_type("Bool", v),
+        },
+        FieldType::Enum { options } => match value {
+            Value::String(string) => match options.get(string.as_str()) {
+                Some(option) => i18n.disp(&option.i18n),
+                None => String::from("invalid option"),
+            },
+            v => invalid_type("String", v),
+        },
+        FieldType::Nullable { item } => match value {
+            Value::Null => String::from("null"),
+            // this is not visually nested, no need to compact the output.
+            _ => self::value(i18n, item, value, false),
+        },
+        FieldType::List { item } => match value {
+            Value::Null => i18n.disp("base-list-empty"),
+            Value::Array(array) if array.is_empty() => i18n.disp("base-list-empty"),
+            Value::Array(array) => {
+                if nested {
+                    let mut args = FluentArgs::new();
+                    args.set("count", array.len());
+                    i18n.disp_with("base-list-item-count-nested", &args)
+                } else {
+                    let mut parts: Vec<_> = array
+                        .iter()
+                        .take(LIST_DISPLAY_LIMIT)
+                        .map(|element| self::value(i18n, item, element, true))
+                        .collect();
+                    if array.len() > LIST_DISPLAY_LIMIT {
+                        let mut args = FluentArgs::new();
+                        args.set("count", array.len() - LIST_DISPLAY_LIMIT);
+                        parts.push(i18n.disp_with("base-list-item-count-remainder", &args));
+                    }
+                    parts.join(", ")
+                }
+            }
+            v => invalid_type("List", v),
+        },
+        FieldType::Compound { fields } => {
+            let map = match value {
+                Value::Null => serde_json::Map::default(),
+                Value::Array(array) if array.is_empty() => serde_json::Map::default(),
+                Value::Object(map) => map.clone(),
+                v => return invalid_type("Compound", v),
+            };
+
+            if map.is_empty() {
+                i18n.disp("base-compound-empty")
+            } else if nested {
+                String::from("{\u{2026}}")
+            } else {
+                let parts: Vec<_> = fields
+                    .values()
+                    .map(|field| {
+                        let value = map.get(field.key.as_str()).unwrap_or(&Value::Null);
+                        let value = self::value(i18n, &field.ty, value, true);
+                        format!("{}={value}", i18n.disp(&field.name))
+                    })
+                    .collect();
+                parts.join(" ")
+            }
+        }
+        FieldType::Object { .. } => match value {
+            Value::String(name) => name.clone(),
+            v => invalid_type("String", v),
+        },
+    }
+}
+
+/// Formats a timestamp in the same unit as the site, i.e. microseconds since the epoch.
+fn timestamp(micros: f64) -> String {
+    match chrono::Local.timestamp_millis_opt((micros / 1000.) as i64).single() {
+        Some(time) => time.format("%Y-%m-%d %H:%M:%S").to_string(),
+        None => String::from("invalid timestamp"),
+    }
+}
+
+/// Describes a field type for the discovery output.
+pub fn field_type(ty: &FieldType) -> String {
+    match ty {
+        FieldType::String {} => String::from("string"),
+        FieldType::Int64 { is_timestamp: true, .. } => String::from("int64 (timestamp)"),
+        FieldType::Int64 { min, max, .. } => format!("int64{}", range(min, max)),
+        FieldType::Float64 { is_timestamp: true, .. } => String::from("float64 (timestamp)"),
+        FieldType::Float64 { min, max, .. } => format!("float64{}", range(min, max)),
+        FieldType::Bool {} => String::from("bool"),
+        FieldType::Enum { options } => {
+            let ids: Vec<_> = options.values().map(|option| option.id.as_str()).collect();
+            format!("enum {{{}}}", ids.join(", "))
+        }
+        FieldType::Object { gk } => format!("object {}/{}", gk.group, gk.kind),
+        FieldType::Nullable { item } => format!("{}?", field_type(item)),
+        FieldType::List { item } => format!("[{}]", field_type(item)),
+        FieldType::Compound { fields } => {
+            let fields: Vec<_> = fields
+                .values()
+                .map(|field| format!("{}: {}", field.key, field_type(&field.ty)))
+                .collect();
+            format!("{{{}}}", fields.join(", "))
+        }
+    }
+}
+
+fn range<T: std::fmt::Display>(min: &Option<T>, max: &Option<T>) -> String {
+    match (min, max) {
+        (None, None) => String::new(),
+        (min, max) => format!(
+            " [{}, {}]",
+            min.as_ref().map_or_else(String::new, ToString::to_string),
+            max.as_ref().map_or_else(String::new, ToString::to_string),
+        ),
+    }
+}
+
+fn invalid_type(expect: &str, got: &serde_json::Value) -> String {
+    let got_ty = match got {
+        serde_json::Value::Null => "Null",
+        serde_json::Value::Bool(..) => "Bool",
+        serde_json::Value::Number(..) => "Number",
+        serde_json::Value::String(..) => "String",
+        serde_json::Value::Array(..) => "Array",
+        serde_json::Value::Object(..) => "Object",
+    };
+    format!("expected {expect}, got {got_ty}")
+}
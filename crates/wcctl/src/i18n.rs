@@ -0,0 +1,67 @@
+use fluent::{FluentArgs, FluentBundle, FluentResource};
+use webconsole_client::Client;
+
+/// Translations loaded from the server.
+///
+/// Keys without a translation are displayed as is,
+/// so that the output stays readable when no locale could be loaded.
+pub struct I18n {
+    bundle: Option<FluentBundle<FluentResource>>,
+}
+
+impl I18n {
+    /// Displays the raw translation keys.
+    pub fn keys() -> Self { Self { bundle: None } }
+
+    /// Loads the requested locale, or the one matching `$LANG` if unspecified.
+    pub async fn load(client: &Client, requested: Option<&str>) -> anyhow::Result<Self> {
+        let locales = client.locales().await?;
+
+        let env_lang = std::env::var("LANG").ok();
+        let env_lang = env_lang.as_deref().and_then(|lang| lang.split(['.', '_']).next());
+        let locale = match requested {
+            Some(requested) => requested.to_string(),
+            None => {
+                let preferred = [env_lang, Some("en")];
+                let found = preferred
+                    .into_iter()
+                    .flatten()
+                    .find_map(|prefer| locales.iter().find(|locale| locale.starts_with(prefer)));
+                match found.or_else(|| locales.first()) {
+                    Some(locale) => locale.clone(),
+                    None => return Ok(Self::keys()),
+                }
+            }
+        };
+
+        let source = client.locale_file(&locale).await?;
+        let resource = FluentResource::try_new(source)
+            .map_err(|(_, errs)| anyhow::anyhow!("parse {locale}.ftl: {:?}", errs.first()))?;
+
+        let mut bundle = FluentBundle::new(vec![locale.parse()?]);
+        // terminals do not need bidi isolation marks around arguments
+        bundle.set_use_isolating(false);
+        if let Err(errs) = bundle.add_resource(resource) {
+            anyhow::bail!("add {locale}.ftl to fluent bundle: {:?}", errs.first());
+        }
+
+        Ok(Self { bundle: Some(bundle) })
+    }
+
+    pub fn disp(&self, id: &str) -> String { self.disp_with(id, &FluentArgs::new()) }
+
+    pub fn disp_with(&self, id: &str, args: &FluentArgs) -> String {
+        let Some(bundle) = &self.bundle else { return id.to_string() };
+        let Some(pat) = bundle.get_message(id).and_then(|message| message.value()) else {
+            return id.to_string();
+        };
+
+        let mut errors = Vec::new();
+        let ret = bundle.format_pattern(pat, Some(args), &mut errors);
+        if let Some(err) = errors.into_iter().next() {
+            return format!("Formatting error: {err}");
+        }
+
+        ret.to_string()
+    }
+}
@@ -0,0 +1,317 @@
+//! Command line client for the WebConsole API.
+
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+use anyhow::Context as _;
+use clap::{Parser, Subcommand, ValueEnum};
+use futures::{StreamExt, TryStreamExt};
+use webconsole_client::protocol::{get_json_path, FieldDef, GroupKindDyn, GroupKindRef, ObjectDef};
+use webconsole_client::{
+    Client, Discovery, Object, Recorder, ResumeToken, Selection, WatchListEvent, WatchSingleEvent,
+};
+
+mod format;
+mod i18n;
+mod table;
+mod tui;
+
+use i18n::I18n;
+
+#[derive(Parser)]
+#[command(version, about)]
+struct Cli {
+    /// Base URL of the API server.
+    #[arg(long, env = "WEBCONSOLE_SERVER", default_value = "http://localhost:14875")]
+    server:   String,
+    /// Extra request header in the form `Name: value`, e.g. for an authenticating proxy.
+    #[arg(long = "header", short = 'H')]
+    headers:  Vec<String>,
+    /// Locale used to translate display names and enum options.
+    #[arg(long)]
+    locale:   Option<String>,
+    /// Print translation keys instead of loading translations from the server.
+    #[arg(long)]
+    raw_keys: bool,
+    /// Record every request, response and event into this session file,
+    /// which can be replayed with `webconsole-mock --replay`.
+    #[arg(long)]
+    record:   Option<PathBuf>,
+    #[command(subcommand)]
+    command:  Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Print the groups, kinds and field schemas provided by the server.
+    Discovery,
+    /// List the objects of a kind.
+    List {
+        group:  String,
+        kind:   String,
+        #[arg(long, short, value_enum, default_value_t = Output::Table)]
+        output: Output,
+        #[command(flatten)]
+        select: SelectArgs,
+        /// Return at most this many objects.
+        #[arg(long)]
+        limit:  Option<u32>,
+    },
+    /// Print a single object.
+    Get {
+        group:  String,
+        kind:   String,
+        name:   String,
+        #[arg(long, short, value_enum, default_value_t = Output::Table)]
+        output: Output,
+        #[command(flatten)]
+        select: SelectArgs,
+    },
+    /// Follow the changes of a kind, or of a single object if a name is given.
+    Watch {
+        group:  String,
+        kind:   String,
+        name:   Option<String>,
+        #[arg(long, short, value_enum, default_value_t = Output::Table)]
+        output: Output,
+        #[command(flatten)]
+        select: SelectArgs,
+    },
+    /// Browse the groups and kinds of the server in an interactive dashboard.
+    Tui,
+}
+
+#[derive(clap::Args)]
+struct SelectArgs {
+    /// Comma-separated paths of the fields to request. All fields are requested if unspecified.
+    #[arg(long, value_delimiter = ',')]
+    fields: Option<Vec<String>>,
+}
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum Output {
+    /// Formatted for humans, with field values displayed like on the site.
+    Table,
+    /// One raw JSON document per line.
+    Ndjson,
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() -> anyhow::Result<()> {
+    let cli = Cli::parse();
+
+    let mut client = Client::new(&cli.server);
+    for header in &cli.headers {
+        client = client.with_header_line(header)?;
+    }
+    if let Some(path) = &cli.record {
+        let recorder = Recorder::create(path)
+            .with_context(|| format!("create session file {}", path.display()))?;
+        client = client.with_recorder(recorder);
+    }
+
+    let i18n = if cli.raw_keys {
+        I18n::keys()
+    } else {
+        I18n::load(&client, cli.locale.as_deref()).await.context("load translations")?
+    };
+
+    let mut out = io::stdout().lock();
+    match cli.command {
+        Command::Tui => {
+            let discovery = client.discovery().await?;
+            drop(out);
+            return tui::run(client, i18n, discovery).await;
+        }
+        Command::Discovery => {
+            let discovery = client.discovery().await?;
+            print_discovery(&mut out, &i18n, &discovery)?;
+        }
+        Command::List { group, kind, output, select, limit } => {
+            let def = object_def(&client, &group, &kind).await?;
+            let selection = Selection { fields: select.fields, limit };
+            let mut objects = client.list(&group, &kind, &selection).await?;
+            match output {
+                Output::Ndjson => {
+                    while let Some(object) = objects.next().await {
+                        writeln!(out, "{}", serde_json::to_string(&object?)?)?;
+                    }
+                }
+                Output::Table => {
+                    let objects: Vec<Object> = objects.try_collect().await?;
+                    let fields = visible_fields(&def, selection.fields.as_deref());
+                    print_objects(&mut out, &i18n, &def, &fields, &objects)?;
+                }
+            }
+        }
+        Command::Get { group, kind, name, output, select } => {
+            let def = object_def(&client, &group, &kind).await?;
+            let object = client.get(&group, &kind, &name, select.fields.as_deref()).await?;
+            match output {
+                Output::Ndjson => writeln!(out, "{}", serde_json::to_string(&object)?)?,
+                Output::Table => {
+                    let fields = visible_fields(&def, select.fields.as_deref());
+                    let rows: Vec<_> = fields
+                        .iter()
+                        .map(|field| {
+                            let value = get_json_path(&object.fields, &field.path)
+                                .unwrap_or(&serde_json::Value::Null);
+                            vec![
+                                i18n.disp(&field.display_name),
+                                format::value(&i18n, &field.ty, value, false),
+                            ]
+                        })
+                        .collect();
+                    table::write(&mut out, &[String::from("FIELD"), String::from("VALUE")], &rows)?;
+                }
+            }
+        }
+        Command::Watch { group, kind, name: None, output, select } => {
+            let def = object_def(&client, &group, &kind).await?;
+            let selection = Selection { fields: select.fields, limit: None };
+            let mut events =
+                client.watch_list(&group, &kind, &selection, &ResumeToken::default()).await?;
+            while let Some(event) = events.next().await {
+                let event = event?;
+                match output {
+                    Output::Ndjson => writeln!(out, "{}", serde_json::to_string(&event)?)?,
+                    Output::Table => print_list_event(&mut out, &i18n, &def, &event)?,
+                }
+                out.flush()?;
+            }
+            anyhow::bail!("watch closed by server");
+        }
+        Command::Watch { group, kind, name: Some(name), output, select } => {
+            let def = object_def(&client, &group, &kind).await?;
+            let mut events = client
+                .watch_single(&group, &kind, &name, select.fields, &ResumeToken::default())
+                .await?;
+            while let Some(event) = events.next().await {
+                let event = event?;
+                match output {
+                    Output::Ndjson => writeln!(out, "{}", serde_json::to_string(&event)?)?,
+                    Output::Table => match &event {
+                        WatchSingleEvent::Resumed => writeln!(out, "# resumed")?,
+                        WatchSingleEvent::Update { field, value } => {
+                            writeln!(out, "~ {}", field_value(&i18n, &def, field, value))?;
+                        }
+                    },
+                }
+                out.flush()?;
+            }
+            anyhow::bail!("watch closed by server");
+        }
+    }
+
+    Ok(())
+}
+
+async fn object_def(client: &Client, group: &str, kind: &str) -> anyhow::Result<ObjectDef> {
+    let discovery = client.discovery().await?;
+    let def = discovery.apis.get(&GroupKindRef { group, kind } as &dyn GroupKindDyn);
+    def.cloned().with_context(|| format!("the server does not provide {group}/{kind}"))
+}
+
+/// The selected fields, or all fields, in the order displayed by the site.
+fn visible_fields<'t>(def: &'t ObjectDef, selected: Option<&[String]>) -> Vec<&'t FieldDef> {
+    let mut fields: Vec<_> = def
+        .fields
+        .values()
+        .filter(|field| selected.map_or(true, |selected| selected.contains(&field.path)))
+        .collect();
+    fields.sort_by_key(|field| (std::cmp::Reverse(field.metadata.display_priority), &field.path));
+    fields
+}
+
+fn print_discovery(mut out: impl Write, i18n: &I18n, discovery: &Discovery) -> io::Result<()> {
+    let mut groups: Vec<_> = discovery.groups.values().collect();
+    groups.sort_by_key(|group| (group.display_priority, &group.id));
+
+    for group in groups {
+        writeln!(out, "{} ({})", group.id, i18n.disp(&group.display_name))?;
+        for def in discovery.apis.values().filter(|def| def.id.group == group.id) {
+            writeln!(out, "  {}/{} ({})", def.id.group, def.id.kind, i18n.disp(&def.display_name))?;
+            for field in visible_fields(def, None) {
+                writeln!(
+                    out,
+                    "    {}: {} ({})",
+                    field.path,
+                    format::field_type(&field.ty),
+                    i18n.disp(&field.display_name),
+                )?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn print_objects(
+    out: impl Write,
+    i18n: &I18n,
+    def: &ObjectDef,
+    fields: &[&FieldDef],
+    objects: &[Object],
+) -> io::Result<()> {
+    let mut header = vec![i18n.disp("base-name")];
+    header.extend(fields.iter().map(|field| i18n.disp(&field.display_name)));
+
+    let mut objects: Vec<_> = objects.iter().collect();
+    objects.sort_by(|a, b| a.name.cmp(&b.name));
+    if def.metadata.desc_name {
+        objects.reverse();
+    }
+
+    let rows: Vec<_> = objects
+        .into_iter()
+        .map(|object| {
+            let mut row = vec![object.name.clone()];
+            row.extend(fields.iter().map(|field| {
+                let value =
+                    get_json_path(&object.fields, &field.path).unwrap_or(&serde_json::Value::Null);
+                format::value(i18n, &field.ty, value, true)
+            }));
+            row
+        })
+        .collect();
+
+    table::write(out, &header, &rows)
+}
+
+fn print_list_event(
+    mut out: impl Write,
+    i18n: &I18n,
+    def: &ObjectDef,
+    event: &WatchListEvent,
+) -> io::Result<()> {
+    match event {
+        WatchListEvent::Clear => writeln!(out, "# cleared"),
+        WatchListEvent::Resumed => writeln!(out, "# resumed"),
+        WatchListEvent::Added { item } => {
+            let mut line = format!("+ {}", item.name);
+            for field in visible_fields(def, None) {
+                if let Some(value) = get_json_path(&item.fields, &field.path) {
+                    line.push(' ');
+                    line.push_str(&field_value(i18n, def, &field.path, value));
+                }
+            }
+            writeln!(out, "{line}")
+        }
+        WatchListEvent::Removed { name } => writeln!(out, "- {name}"),
+        WatchListEvent::FieldUpdate { name, field, value } => {
+            writeln!(out, "~ {name} {}", field_value(i18n, def, field, value))
+        }
+    }
+}
+
+/// Formats `path=value`, falling back to raw JSON if the field is not in the schema.
+fn field_value(i18n: &I18n, def: &ObjectDef, path: &str, value: &serde_json::Value) -> String {
+    match def.fields.get(path) {
+        Some(field) => format!(
+            "{}={}",
+            i18n.disp(&field.display_name),
+            format::value(i18n, &field.ty, value, true)
+        ),
+        None => format!("{path}={value}"),
+    }
+}
@@ -0,0 +1,137 @@
+//! An interactive dashboard that mirrors the site in the terminal.
+
+use std::io::{self, Stdout};
+use std::time::Duration;
+
+use crossterm::event::{Event, EventStream, KeyEventKind};
+use crossterm::terminal::{self, EnterAlternateScreen, LeaveAlternateScreen};
+use futures::stream::BoxStream;
+use futures::{StreamExt, TryStreamExt};
+use ratatui::backend::CrosstermBackend;
+use ratatui::Terminal;
+use tokio::time::Instant;
+use webconsole_client::{ApiError, Client, Discovery, ResumeToken, Selection, WatchListEvent};
+
+use crate::i18n::I18n;
+
+mod app;
+mod view;
+
+use app::App;
+
+/// How long to wait before reopening a failed watch.
+const RETRY_DELAY: Duration = Duration::from_secs(2);
+
+type Watch = BoxStream<'static, Result<WatchListEvent, ApiError>>;
+
+pub async fn run(client: Client, i18n: I18n, discovery: Discovery) -> anyhow::Result<()> {
+    let mut terminal = TerminalGuard::enter()?;
+    let mut app = App::new(client.host.clone(), i18n, discovery);
+    let mut input = EventStream::new();
+
+    let mut watch: Option<Watch> = None;
+    let mut resume = ResumeToken::default();
+    let mut retry_at = Some(Instant::now());
+    let mut watched = None;
+
+    loop {
+        if app.current_kind() != watched.as_ref() {
+            watched = app.current_kind().cloned();
+            watch = None;
+            resume = ResumeToken::default();
+            retry_at = watched.is_some().then(Instant::now);
+        }
+
+        terminal.0.draw(|frame| view::draw(frame, &app))?;
+
+        tokio::select! {
+            event = input.next() => match event {
+                Some(Ok(Event::Key(key))) if key.kind == KeyEventKind::Press => {
+                    if !app.on_key(key) {
+                        return Ok(());
+                    }
+                }
+                Some(Ok(_)) => {}
+                Some(Err(err)) => return Err(err.into()),
+                None => return Ok(()),
+            },
+            event = next_event(&mut watch) => match event {
+                Some(Ok(event)) => {
+                    app.status = None;
+                    app.apply(&event);
+                }
+                Some(Err(err)) => {
+                    app.status = Some(err.to_string());
+                    watch = None;
+                    retry_at = Some(Instant::now() + RETRY_DELAY);
+                }
+                None => {
+                    app.status = Some(String::from("watch closed by server"));
+                    watch = None;
+                    retry_at = Some(Instant::now() + RETRY_DELAY);
+                }
+            },
+            () = sleep_until(retry_at) => {
+                retry_at = None;
+                if let Some(gk) = &watched {
+                    watch = Some(open_watch(&client, &gk.group, &gk.kind, &resume));
+                }
+            }
+        }
+    }
+}
+
+/// Opens a watch without waiting for the response, so that the interface stays responsive.
+// the async block owns the arguments borrowed by `watch_list`
+#[allow(clippy::redundant_async_block)]
+fn open_watch(client: &Client, group: &str, kind: &str, resume: &ResumeToken) -> Watch {
+    let (client, group, kind, resume) =
+        (client.clone(), group.to_string(), kind.to_string(), resume.clone());
+    let stream =
+        async move { client.watch_list(&group, &kind, &Selection::default(), &resume).await };
+    futures::stream::once(stream).try_flatten().boxed()
+}
+
+async fn next_event(watch: &mut Option<Watch>) -> Option<Result<WatchListEvent, ApiError>> {
+    match watch {
+        Some(watch) => watch.next().await,
+        None => futures::future::pending().await,
+    }
+}
+
+async fn sleep_until(deadline: Option<Instant>) {
+    match deadline {
+        Some(deadline) => tokio::time::sleep_until(deadline).await,
+        None => futures::future::pending().await,
+    }
+}
+
+/// Restores the terminal when the dashboard exits, including on errors.
+struct TerminalGuard(Terminal<CrosstermBackend<Stdout>>);
+
+impl TerminalGuard {
+    fn enter() -> io::Result<Self> {
+        terminal::enable_raw_mode()?;
+        crossterm::execute!(io::stdout(), EnterAlternateScreen)?;
+        let terminal = Terminal::new(CrosstermBackend::new(io::stdout()));
+        match terminal {
+            Ok(terminal) => Ok(Self(terminal)),
+            Err(err) => {
+                Self::leave();
+                Err(err)
+            }
+        }
+    }
+
+    fn leave() {
+        _ = terminal::disable_raw_mode();
+        _ = crossterm::execute!(io::stdout(), LeaveAlternateScreen);
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        Self::leave();
+        _ = self.0.show_cursor();
+    }
+}
@@ -0,0 +1,258 @@
+//! State of the dashboard and its key bindings.
+
+use std::cmp;
+use std::collections::HashSet;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use webconsole_client::protocol::{DisplayMode, FieldDef, Group, GroupKind, ObjectDef};
+use webconsole_client::{Discovery, Object, ObjectStore, WatchListEvent};
+
+use crate::i18n::I18n;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Focus {
+    Sidebar,
+    Objects,
+}
+
+pub struct App {
+    pub host:      String,
+    pub i18n:      I18n,
+    pub discovery: Discovery,
+    /// The groups in the order of the site's navigation, with the kinds of each.
+    pub nav:       Vec<(Group, Vec<GroupKind>)>,
+    /// Index of the selected kind among all kinds in `nav`.
+    pub current:   usize,
+    pub focus:     Focus,
+    pub view:      Option<KindView>,
+    /// The error of the current watch, if it is being reopened.
+    pub status:    Option<String>,
+}
+
+/// The objects of the selected kind and how they are displayed.
+pub struct KindView {
+    pub def:            ObjectDef,
+    pub store:          ObjectStore,
+    pub mode:           DisplayMode,
+    pub hidden:         HashSet<String>,
+    /// Name of the selected object, kept across updates of the list.
+    pub selected:       Option<String>,
+    pub detail:         bool,
+    /// Cursor of the field selector, if it is open.
+    pub field_selector: Option<usize>,
+}
+
+impl App {
+    pub fn new(host: String, i18n: I18n, discovery: Discovery) -> Self {
+        let mut groups: Vec<_> = discovery.groups.values().cloned().collect();
+        groups.sort_by_key(|group| group.display_priority);
+
+        let nav = groups
+            .into_iter()
+            .map(|group| {
+                let kinds = discovery
+                    .apis
+                    .values()
+                    .filter(|def| def.id.group == group.id)
+                    .map(|def| def.id.clone())
+                    .collect();
+                (group, kinds)
+            })
+            .collect();
+
+        let mut app = Self {
+            host,
+            i18n,
+            discovery,
+            nav,
+            current: 0,
+            focus: Focus::Sidebar,
+            view: None,
+            status: None,
+        };
+        app.select_kind(0);
+        app
+    }
+
+    pub fn kinds(&self) -> impl Iterator<Item = &GroupKind> {
+        self.nav.iter().flat_map(|(_, kinds)| kinds)
+    }
+
+    pub fn current_kind(&self) -> Option<&GroupKind> { self.view.as_ref().map(|view| &view.def.id) }
+
+    fn select_kind(&mut self, index: usize) {
+        let Some(gk) = self.kinds().nth(index).cloned() else { return };
+        let Some(def) = self.discovery.apis.get(&gk) else { return };
+
+        self.current = index;
+        self.status = None;
+        self.view = Some(KindView::new(def.clone()));
+    }
+
+    pub fn apply(&mut self, event: &WatchListEvent) {
+        if let Some(view) = &mut self.view {
+            view.store.apply(event);
+        }
+    }
+
+    /// Handles a key press and returns whether the dashboard should keep running.
+    pub fn on_key(&mut self, key: KeyEvent) -> bool {
+        if key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL) {
+            return false;
+        }
+
+        if let Some(view) = &mut self.view {
+            if view.field_selector.is_some() {
+                view.on_field_selector_key(key.code);
+                return true;
+            }
+        }
+
+        match key.code {
+            KeyCode::Char('q') => return false,
+            KeyCode::Tab | KeyCode::BackTab => {
+                self.focus = match self.focus {
+                    Focus::Sidebar => Focus::Objects,
+                    Focus::Objects => Focus::Sidebar,
+                };
+            }
+            KeyCode::Left | KeyCode::Char('h') => self.focus = Focus::Sidebar,
+            KeyCode::Right | KeyCode::Char('l') => self.focus = Focus::Objects,
+            KeyCode::Char('m') => {
+                if let Some(view) = &mut self.view {
+                    view.mode = match view.mode {
+                        DisplayMode::Cards => DisplayMode::Table,
+                        DisplayMode::Table => DisplayMode::Cards,
+                    };
+                }
+            }
+            KeyCode::Char('f') => {
+                if let Some(view) = &mut self.view {
+                    view.field_selector = Some(0);
+                }
+            }
+            code => match self.focus {
+                Focus::Sidebar => self.on_sidebar_key(code),
+                Focus::Objects => {
+                    if let Some(view) = &mut self.view {
+                        view.on_objects_key(code);
+                    }
+                }
+            },
+        }
+
+        true
+    }
+
+    fn on_sidebar_key(&mut self, code: KeyCode) {
+        let count = self.kinds().count();
+        match code {
+            KeyCode::Up | KeyCode::Char('k') if self.current > 0 => {
+                self.select_kind(self.current - 1);
+            }
+            KeyCode::Down | KeyCode::Char('j') if self.current + 1 < count => {
+                self.select_kind(self.current + 1);
+            }
+            KeyCode::Enter => self.focus = Focus::Objects,
+            _ => {}
+        }
+    }
+}
+
+impl KindView {
+    /// Displays the kind like the site does when its list page is opened.
+    fn new(def: ObjectDef) -> Self {
+        let hidden = def
+            .fields
+            .values()
+            .filter(|field| field.metadata.hide_by_default)
+            .map(|field| field.path.clone())
+            .collect();
+
+        Self {
+            mode: def.metadata.default_display_mode,
+            def,
+            store: ObjectStore::default(),
+            hidden,
+            selected: None,
+            detail: false,
+            field_selector: None,
+        }
+    }
+
+    /// All fields in the order displayed by the site.
+    pub fn all_fields(&self) -> Vec<&FieldDef> {
+        let mut fields: Vec<_> = self.def.fields.values().collect();
+        fields.sort_by_key(|field| (cmp::Reverse(field.metadata.display_priority), &field.path));
+        fields
+    }
+
+    /// The fields that are not hidden by the field selector.
+    pub fn visible_fields(&self) -> Vec<&FieldDef> {
+        let mut fields = self.all_fields();
+        fields.retain(|field| !self.hidden.contains(&field.path));
+        fields
+    }
+
+    /// The objects in display order.
+    pub fn objects(&self) -> Vec<&Object> {
+        let mut objects: Vec<_> = self.store.objects().values().collect();
+        if self.def.metadata.desc_name {
+            objects.reverse();
+        }
+        objects
+    }
+
+    /// Index of the selected object in [`KindView::objects`].
+    pub fn selected_index(&self) -> Option<usize> {
+        let selected = self.selected.as_ref()?;
+        self.objects().iter().position(|object| &object.name == selected)
+    }
+
+    pub fn selected_object(&self) -> Option<&Object> {
+        self.selected.as_ref().and_then(|name| self.store.get(name))
+    }
+
+    fn on_objects_key(&mut self, code: KeyCode) {
+        let names: Vec<String> = self.objects().iter().map(|object| object.name.clone()).collect();
+        let index = self.selected_index();
+        let new = match code {
+            KeyCode::Up | KeyCode::Char('k') => index.map_or(0, |index| index.saturating_sub(1)),
+            KeyCode::Down | KeyCode::Char('j') => index.map_or(0, |index| index + 1),
+            KeyCode::Home | KeyCode::Char('g') => 0,
+            KeyCode::End | KeyCode::Char('G') => names.len().saturating_sub(1),
+            KeyCode::Enter => {
+                self.detail = self.selected_object().is_some();
+                return;
+            }
+            KeyCode::Esc => {
+                self.detail = false;
+                return;
+            }
+            _ => return,
+        };
+        if let Some(name) = names.get(new.min(names.len().saturating_sub(1))) {
+            self.selected = Some(name.clone());
+        }
+    }
+
+    fn on_field_selector_key(&mut self, code: KeyCode) {
+        let paths: Vec<String> = self.all_fields().iter().map(|field| field.path.clone()).collect();
+        let Some(cursor) = &mut self.field_selector else { return };
+        match code {
+            KeyCode::Up | KeyCode::Char('k') => *cursor = cursor.saturating_sub(1),
+            KeyCode::Down | KeyCode::Char('j') => {
+                *cursor = (*cursor + 1).min(paths.len().saturating_sub(1));
+            }
+            KeyCode::Char(' ') | KeyCode::Enter => {
+                if let Some(path) = paths.get(*cursor) {
+                    if !self.hidden.remove(path) {
+                        self.hidden.insert(path.clone());
+                    }
+                }
+            }
+            KeyCode::Esc | KeyCode::Char('f') | KeyCode::Char('q') => self.field_selector = None,
+            _ => {}
+        }
+    }
+}
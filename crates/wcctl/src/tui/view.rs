@@ -0,0 +1,288 @@
+//! Draws the dashboard.
+
+use ratatui::backend::Backend;
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{
+    Block, Borders, Cell, Clear, List, ListItem, ListState, Paragraph, Row, Table, TableState, Wrap,
+};
+use ratatui::Frame;
+use webconsole_client::protocol::{get_json_path, DisplayMode, FieldDef};
+use webconsole_client::Object;
+
+use super::app::{App, Focus, KindView};
+use crate::format;
+use crate::i18n::I18n;
+
+const SIDEBAR_WIDTH: u16 = 28;
+/// Table columns are truncated to this width, like the abbreviated cells of the site.
+const MAX_COLUMN_WIDTH: usize = 32;
+
+pub fn draw<B: Backend>(frame: &mut Frame<B>, app: &App) {
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(0), Constraint::Length(1)])
+        .split(frame.size());
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Length(SIDEBAR_WIDTH), Constraint::Min(0)])
+        .split(rows[0]);
+
+    draw_sidebar(frame, app, columns[0]);
+    match &app.view {
+        Some(view) => draw_kind(frame, app, view, columns[1]),
+        None => frame.render_widget(Paragraph::new("The server provides no kinds."), columns[1]),
+    }
+    draw_status(frame, app, rows[1]);
+
+    if let Some(view) = &app.view {
+        if let Some(cursor) = view.field_selector {
+            draw_field_selector(frame, &app.i18n, view, cursor);
+        }
+    }
+}
+
+fn block(title: String, focused: bool) -> Block<'static> {
+    let style = if focused { Style::default().fg(Color::Cyan) } else { Style::default() };
+    Block::default().borders(Borders::ALL).border_style(style).title(title)
+}
+
+fn highlight() -> Style { Style::default().add_modifier(Modifier::REVERSED) }
+
+fn draw_sidebar<B: Backend>(frame: &mut Frame<B>, app: &App, area: Rect) {
+    let mut items = Vec::new();
+    let mut selected = None;
+    let mut index = 0;
+    for (group, kinds) in &app.nav {
+        items.push(
+            ListItem::new(app.i18n.disp(&group.display_name))
+                .style(Style::default().add_modifier(Modifier::BOLD)),
+        );
+        for gk in kinds {
+            if index == app.current {
+                selected = Some(items.len());
+            }
+            index += 1;
+
+            let name = match app.discovery.apis.get(gk) {
+                Some(def) => app.i18n.disp(&def.display_name),
+                None => format!("{}/{}", gk.group, gk.kind),
+            };
+            items.push(ListItem::new(format!("  {name}")));
+        }
+    }
+
+    let list = List::new(items)
+        .block(block(app.host.clone(), app.focus == Focus::Sidebar))
+        .highlight_style(highlight());
+    frame.render_stateful_widget(list, area, &mut ListState::default().with_selected(selected));
+}
+
+fn draw_kind<B: Backend>(frame: &mut Frame<B>, app: &App, view: &KindView, area: Rect) {
+    let (list_area, detail_area) = if view.detail {
+        let split = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(60), Constraint::Percentage(40)])
+            .split(area);
+        (split[0], Some(split[1]))
+    } else {
+        (area, None)
+    };
+
+    let mode = match view.mode {
+        DisplayMode::Cards => "base-display-card",
+        DisplayMode::Table => "base-display-table",
+    };
+    let title = format!(
+        "{} ({}) [{}]",
+        app.i18n.disp(&view.def.display_name),
+        view.store.len(),
+        app.i18n.disp(mode),
+    );
+    let block = block(title, app.focus == Focus::Objects);
+
+    let fields = view.visible_fields();
+    let objects = view.objects();
+    match view.mode {
+        DisplayMode::Table => {
+            draw_table(frame, &app.i18n, view, &fields, &objects, block, list_area);
+        }
+        DisplayMode::Cards => {
+            draw_cards(frame, &app.i18n, view, &fields, &objects, block, list_area);
+        }
+    }
+
+    if let Some(area) = detail_area {
+        draw_detail(frame, &app.i18n, view, area);
+    }
+}
+
+fn draw_table<B: Backend>(
+    frame: &mut Frame<B>,
+    i18n: &I18n,
+    view: &KindView,
+    fields: &[&FieldDef],
+    objects: &[&Object],
+    block: Block<'_>,
+    area: Rect,
+) {
+    let hide_name = view.def.metadata.hide_name;
+
+    let mut header: Vec<String> = Vec::new();
+    if !hide_name {
+        header.push(i18n.disp("base-name"));
+    }
+    header.extend(fields.iter().map(|field| i18n.disp(&field.display_name)));
+
+    let rows: Vec<Vec<String>> = objects
+        .iter()
+        .map(|object| {
+            let mut row = Vec::new();
+            if !hide_name {
+                row.push(object.name.clone());
+            }
+            row.extend(fields.iter().map(|field| {
+                match get_json_path(&object.fields, &field.path) {
+                    Some(value) => format::value(i18n, &field.ty, value, true),
+                    None => String::new(),
+                }
+            }));
+            row
+        })
+        .collect();
+
+    let widths: Vec<Constraint> = (0..header.len())
+        .map(|column| {
+            let width = std::iter::once(&header)
+                .chain(&rows)
+                .map(|row| row[column].chars().count())
+                .max()
+                .unwrap_or_default();
+            Constraint::Length(width.min(MAX_COLUMN_WIDTH) as u16)
+        })
+        .collect();
+
+    let header = Row::new(header).style(Style::default().add_modifier(Modifier::BOLD));
+    let rows = rows.into_iter().map(|row| Row::new(row.into_iter().map(Cell::from)));
+    let table =
+        Table::new(rows).header(header).widths(&widths).block(block).highlight_style(highlight());
+    frame.render_stateful_widget(
+        table,
+        area,
+        &mut TableState::default().with_selected(view.selected_index()),
+    );
+}
+
+fn draw_cards<B: Backend>(
+    frame: &mut Frame<B>,
+    i18n: &I18n,
+    view: &KindView,
+    fields: &[&FieldDef],
+    objects: &[&Object],
+    block: Block<'_>,
+    area: Rect,
+) {
+    let items: Vec<ListItem> = objects
+        .iter()
+        .map(|object| {
+            let mut lines = Vec::new();
+            if !view.def.metadata.hide_name {
+                lines.push(Line::styled(
+                    object.name.clone(),
+                    Style::default().add_modifier(Modifier::BOLD),
+                ));
+            }
+            for field in fields {
+                let value =
+                    get_json_path(&object.fields, &field.path).unwrap_or(&serde_json::Value::Null);
+                lines.push(Line::from(vec![
+                    Span::styled(
+                        format!("  {} ", i18n.disp(&field.display_name)),
+                        Style::default().fg(Color::Blue),
+                    ),
+                    Span::raw(format::value(i18n, &field.ty, value, false)),
+                ]));
+            }
+            lines.push(Line::default());
+            ListItem::new(lines)
+        })
+        .collect();
+
+    let list = List::new(items).block(block).highlight_style(highlight());
+    frame.render_stateful_widget(
+        list,
+        area,
+        &mut ListState::default().with_selected(view.selected_index()),
+    );
+}
+
+/// Shows every field of the selected object, like the site's object page.
+fn draw_detail<B: Backend>(frame: &mut Frame<B>, i18n: &I18n, view: &KindView, area: Rect) {
+    let Some(object) = view.selected_object() else {
+        let name = view.selected.clone().unwrap_or_default();
+        frame.render_widget(Paragraph::new("").block(block(name, false)), area);
+        return;
+    };
+
+    let mut lines = Vec::new();
+    for field in view.all_fields() {
+        let value = get_json_path(&object.fields, &field.path).unwrap_or(&serde_json::Value::Null);
+        lines.push(Line::styled(
+            i18n.disp(&field.display_name),
+            Style::default().fg(Color::Blue).add_modifier(Modifier::BOLD),
+        ));
+        lines.push(Line::from(format!("  {}", format::value(i18n, &field.ty, value, false))));
+    }
+
+    let paragraph =
+        Paragraph::new(lines).block(block(object.name.clone(), false)).wrap(Wrap { trim: false });
+    frame.render_widget(paragraph, area);
+}
+
+fn draw_field_selector<B: Backend>(
+    frame: &mut Frame<B>,
+    i18n: &I18n,
+    view: &KindView,
+    cursor: usize,
+) {
+    let fields = view.all_fields();
+    let items: Vec<ListItem> = fields
+        .iter()
+        .map(|field| {
+            let check = if view.hidden.contains(&field.path) { ' ' } else { 'x' };
+            ListItem::new(format!("[{check}] {}", i18n.disp(&field.display_name)))
+        })
+        .collect();
+
+    let area = frame.size();
+    let width = area.width.min(40);
+    let height = area.height.min(fields.len() as u16 + 2);
+    let popup = Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    };
+
+    let list = List::new(items)
+        .block(block(i18n.disp("base-properties-title"), true))
+        .highlight_style(highlight());
+    frame.render_widget(Clear, popup);
+    frame.render_stateful_widget(
+        list,
+        popup,
+        &mut ListState::default().with_selected(Some(cursor)),
+    );
+}
+
+fn draw_status<B: Backend>(frame: &mut Frame<B>, app: &App, area: Rect) {
+    let line = match &app.status {
+        Some(status) => Line::styled(status.clone(), Style::default().fg(Color::Red)),
+        None => Line::styled(
+            "q quit  tab focus  ↑↓ select  enter details  m cards/table  f fields",
+            Style::default().add_modifier(Modifier::DIM),
+        ),
+    };
+    frame.render_widget(Paragraph::new(line), area);
+}
@@ -0,0 +1,164 @@
+//! The configuration file of the bridge.
+//!
+//! ```toml
+//! # Directory of the batches that are not delivered yet.
+//! queue = "webhook-queue"
+//! # Whether the objects that exist when the bridge starts are sent as `added` events.
+//! # By default they only serve as the baseline of later events.
+//! report_initial = false
+//!
+//! [[webhooks]]
+//! # Identifies the queue of the webhook, so it must not change while batches are pending.
+//! name = "chat"
+//! url = "http://127.0.0.1:8080/hook"
+//! headers = { Authorization = "Bearer secret" }
+//! # Events are collected for this long before they are sent together.
+//! batch_window_ms = 1000
+//! max_batch = 100
+//!
+//! # An event is sent if it matches any filter.
+//! [[webhooks.filters]]
+//! group = "main"
+//! kind = "player"
+//! events = ["added", "removed"]
+//!
+//! [[webhooks.filters]]
+//! group = "main"
+//! kind = "log-message"
+//! events = ["added"]
+//! # The object must have these field values.
+//! equals = { verbosity = "error" }
+//! ```
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::Context as _;
+use serde::{Deserialize, Serialize};
+use webconsole_client::protocol::get_json_path;
+
+use crate::Event;
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    #[serde(default = "default_queue")]
+    pub queue:          PathBuf,
+    #[serde(default)]
+    pub report_initial: bool,
+    pub webhooks:       Vec<Webhook>,
+}
+
+fn default_queue() -> PathBuf { PathBuf::from("webhook-queue") }
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Webhook {
+    pub name:            String,
+    pub url:             String,
+    #[serde(default)]
+    pub headers:         BTreeMap<String, String>,
+    #[serde(default = "default_batch_window_ms")]
+    pub batch_window_ms: u64,
+    #[serde(default = "default_max_batch")]
+    pub max_batch:       usize,
+    pub filters:         Vec<Filter>,
+}
+
+fn default_batch_window_ms() -> u64 { 1000 }
+
+fn default_max_batch() -> usize { 100 }
+
+impl Webhook {
+    pub fn batch_window(&self) -> Duration { Duration::from_millis(self.batch_window_ms) }
+
+    pub fn matches(&self, event: &Event) -> bool {
+        self.filters.iter().any(|filter| filter.matches(event))
+    }
+}
+
+/// Selects events by the kind, the type of event and the fields of the object.
+///
+/// Unspecified conditions match all events.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Filter {
+    pub group:  Option<String>,
+    pub kind:   Option<String>,
+    pub events: Option<Vec<EventType>>,
+    /// Only updates of these field paths are sent. Does not affect other events.
+    pub fields: Option<Vec<String>>,
+    /// Field paths and the values they must have in the object.
+    ///
+    /// Removed objects are matched with their last known fields.
+    #[serde(default)]
+    pub equals: BTreeMap<String, serde_json::Value>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventType {
+    Added,
+    Removed,
+    FieldUpdate,
+}
+
+impl Filter {
+    /// Whether the filter may match events of a kind.
+    pub fn selects(&self, group: &str, kind: &str) -> bool {
+        self.group.as_deref().map_or(true, |filter| filter == group)
+            && self.kind.as_deref().map_or(true, |filter| filter == kind)
+    }
+
+    fn matches(&self, event: &Event) -> bool {
+        if !self.selects(&event.group, &event.kind) {
+            return false;
+        }
+        if let Some(events) = &self.events {
+            if !events.contains(&event.event) {
+                return false;
+            }
+        }
+        if let (Some(fields), Some(field)) = (&self.fields, &event.field) {
+            if !fields.contains(field) {
+                return false;
+            }
+        }
+        self.equals.iter().all(|(path, expected)| {
+            event.object.as_ref().and_then(|object| get_json_path(object, path)) == Some(expected)
+        })
+    }
+}
+
+impl Config {
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let source =
+            std::fs::read_to_string(path).with_context(|| format!("read {}", path.display()))?;
+        let config: Self =
+            toml::from_str(&source).with_context(|| format!("parse {}", path.display()))?;
+
+        for (index, webhook) in config.webhooks.iter().enumerate() {
+            anyhow::ensure!(
+                !webhook.name.is_empty()
+                    && webhook
+                        .name
+                        .chars()
+                        .all(|char| char.is_ascii_alphanumeric() || "-_".contains(char)),
+                "the name of webhook {index} must only contain letters, digits, `-` and `_`",
+            );
+            anyhow::ensure!(
+                config.webhooks[..index].iter().all(|other| other.name != webhook.name),
+                "duplicate webhook name {:?}",
+                webhook.name,
+            );
+            anyhow::ensure!(
+                webhook.max_batch > 0,
+                "max_batch of {:?} must be positive",
+                webhook.name
+            );
+        }
+
+        Ok(config)
+    }
+}
@@ -0,0 +1,335 @@
+//! Forwards the events of watched kinds to HTTP webhooks.
+
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Context as _;
+use clap::Parser;
+use futures::StreamExt;
+use serde::Serialize;
+use tokio::sync::mpsc;
+use webconsole_client::protocol::ObjectDef;
+use webconsole_client::{Client, ObjectStore, Retrying, Selection, WatchListEvent};
+
+use crate::config::{Config, EventType, Webhook};
+
+mod config;
+mod queue;
+
+#[derive(Parser)]
+#[command(version, about)]
+struct Args {
+    /// Path of the configuration file.
+    config:  PathBuf,
+    /// Base URL of the API server.
+    #[arg(long, env = "WEBCONSOLE_SERVER", default_value = "http://localhost:14875")]
+    server:  String,
+    /// Extra request header in the form `Name: value`, e.g. for an authenticating proxy.
+    #[arg(long = "header", short = 'H')]
+    headers: Vec<String>,
+}
+
+/// How long the server may pause while re-adding objects after a clear
+/// before the objects that were not re-added are reported as removed.
+const RESYNC_SETTLE: Duration = Duration::from_millis(500);
+
+/// An event as sent to webhooks.
+#[derive(Debug, Clone, Serialize)]
+pub struct Event {
+    /// Microseconds since the Unix epoch when the bridge received the event.
+    pub time:   i64,
+    pub group:  String,
+    pub kind:   String,
+    pub event:  EventType,
+    pub name:   String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub field:  Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value:  Option<serde_json::Value>,
+    /// The fields of the object after the event, or before it was removed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub object: Option<serde_json::Value>,
+}
+
+type Route = (Arc<Webhook>, mpsc::UnboundedSender<Event>);
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+    let config = Config::load(&args.config)?;
+
+    let mut client = Client::new(&args.server);
+    for header in &args.headers {
+        client = client.with_header_line(header)?;
+    }
+    let discovery = client.discovery().await.context("fetch discovery")?;
+
+    let http = reqwest::Client::new();
+    let mut routes: Vec<Route> = Vec::new();
+    let mut queues = Vec::new();
+    for webhook in config.webhooks {
+        let webhook = Arc::new(webhook);
+        let (tx, rx) = mpsc::unbounded_channel();
+        routes.push((webhook.clone(), tx));
+        queues.push(tokio::spawn(queue::run(webhook, config.queue.clone(), http.clone(), rx)));
+    }
+
+    for def in discovery.apis.values() {
+        let routes: Vec<Route> = routes
+            .iter()
+            .filter(|(webhook, _)| {
+                webhook.filters.iter().any(|filter| filter.selects(&def.id.group, &def.id.kind))
+            })
+            .cloned()
+            .collect();
+        if !routes.is_empty() {
+            let forwarder = Forwarder::new(&def.id.group, &def.id.kind, config.report_initial);
+            tokio::spawn(watch(client.clone(), def.clone(), forwarder, routes));
+        }
+    }
+    drop(routes);
+
+    for queue in queues {
+        queue.await??;
+    }
+    Ok(())
+}
+
+/// Forwards the events of a kind, reopening the watch whenever it fails.
+async fn watch(client: Client, def: ObjectDef, mut forwarder: Forwarder, routes: Vec<Route>) {
+    let (group, kind) = (&def.id.group, &def.id.kind);
+    let send = |events: Vec<Event>| {
+        for event in events {
+            for (webhook, tx) in &routes {
+                if webhook.matches(&event) {
+                    _ = tx.send(event.clone());
+                }
+            }
+        }
+    };
+
+    let mut events = client.watch_list_retrying(group, kind, Selection::default());
+    let mut connected = false;
+
+    loop {
+        // a failed watch is resynced from the objects before it once it reopens
+        let next = if connected && forwarder.is_resyncing() {
+            match tokio::time::timeout(RESYNC_SETTLE, events.next()).await {
+                Ok(next) => next,
+                Err(_) => {
+                    send(forwarder.end_resync());
+                    continue;
+                }
+            }
+        } else {
+            events.next().await
+        };
+        match next {
+            Some(Retrying::Event(event)) => {
+                connected = true;
+                send(forwarder.apply(event));
+            }
+            Some(Retrying::Failed(err)) => {
+                connected = false;
+                eprintln!("watch {group}/{kind}: {err}");
+            }
+            None => return,
+        }
+    }
+}
+
+/// Tracks the objects of a kind to attach them to events.
+struct Forwarder {
+    group:          String,
+    kind:           String,
+    store:          ObjectStore,
+    /// The objects before the server cleared the watch,
+    /// as long as it is re-adding objects after the clear.
+    previous:       Option<ObjectStore>,
+    /// Whether the first list of objects has been received.
+    synced:         bool,
+    report_initial: bool,
+}
+
+impl Forwarder {
+    fn new(group: &str, kind: &str, report_initial: bool) -> Self {
+        Self {
+            group: group.to_string(),
+            kind: kind.to_string(),
+            store: ObjectStore::default(),
+            previous: None,
+            synced: false,
+            report_initial,
+        }
+    }
+
+    /// Whether the server is re-adding the objects after a clear.
+    fn is_resyncing(&self) -> bool { self.previous.is_some() }
+
+    /// Reports the objects that were not re-added after a clear as removed.
+    fn end_resync(&mut self) -> Vec<Event> {
+        let Some(previous) = self.previous.take() else { return Vec::new() };
+        let report = self.synced;
+        self.synced = true;
+        if !report {
+            return Vec::new();
+        }
+        previous
+            .objects()
+            .values()
+            .filter(|object| self.store.get(&object.name).is_none())
+            .map(|object| {
+                let (name, fields) = (object.name.clone(), object.fields.clone());
+                self.event(EventType::Removed, name, None, None, Some(fields))
+            })
+            .collect()
+    }
+
+    /// Converts a watch event to the events that are forwarded.
+    fn apply(&mut self, event: WatchListEvent) -> Vec<Event> {
+        let mut events = Vec::new();
+        if let WatchListEvent::Clear = event {
+            // a watch that failed while re-adding is compared with the objects before both clears
+            let mut previous = self.previous.take().unwrap_or_default();
+            for item in self.store.objects().values() {
+                previous.apply(&WatchListEvent::Added { item: item.clone() });
+            }
+            self.previous = Some(previous);
+        } else if !matches!(event, WatchListEvent::Added { .. }) {
+            events = self.end_resync();
+        }
+
+        let before = match &event {
+            WatchListEvent::Removed { name } => {
+                self.store.get(name).map(|object| object.fields.clone())
+            }
+            _ => None,
+        };
+        self.store.apply(&event);
+
+        let (ty, name, field, value) = match event {
+            WatchListEvent::Clear | WatchListEvent::Resumed => return events,
+            WatchListEvent::Added { item } => {
+                if let Some(previous) = &self.previous {
+                    // the objects present when the bridge starts are the baseline
+                    if !self.synced && !self.report_initial {
+                        return events;
+                    }
+                    // objects that did not change while the watch was restarting are not reported again
+                    if previous.get(&item.name) == Some(&item) {
+                        return events;
+                    }
+                }
+                (EventType::Added, item.name, None, None)
+            }
+            WatchListEvent::Removed { name } => (EventType::Removed, name, None, None),
+            WatchListEvent::FieldUpdate { name, field, value } => {
+                (EventType::FieldUpdate, name, Some(field), Some(value))
+            }
+        };
+
+        let object = before.or_else(|| self.store.get(&name).map(|object| object.fields.clone()));
+        events.push(self.event(ty, name, field, value, object));
+        events
+    }
+
+    fn event(
+        &self,
+        event: EventType,
+        name: String,
+        field: Option<String>,
+        value: Option<serde_json::Value>,
+        object: Option<serde_json::Value>,
+    ) -> Event {
+        Event {
+            time: chrono::Utc::now().timestamp_micros(),
+            group: self.group.clone(),
+            kind: self.kind.clone(),
+            event,
+            name,
+            field,
+            value,
+            object,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use webconsole_client::protocol::Object;
+
+    use super::*;
+
+    fn added(name: &str, hp: i64) -> WatchListEvent {
+        let item = Object { name: name.to_string(), fields: serde_json::json!({ "hp": hp }) };
+        WatchListEvent::Added { item }
+    }
+
+    fn summary(events: Vec<Event>) -> Vec<(EventType, String)> {
+        events.into_iter().map(|event| (event.event, event.name)).collect()
+    }
+
+    fn sync(forwarder: &mut Forwarder, events: Vec<WatchListEvent>) -> Vec<(EventType, String)> {
+        let mut out = Vec::new();
+        for event in events {
+            out.extend(summary(forwarder.apply(event)));
+        }
+        out.extend(summary(forwarder.end_resync()));
+        out
+    }
+
+    #[test]
+    fn initial_sync_is_baseline() {
+        let mut forwarder = Forwarder::new("main", "player", false);
+        let initial = sync(&mut forwarder, vec![WatchListEvent::Clear, added("a", 1)]);
+        assert_eq!(initial, []);
+        assert_eq!(summary(forwarder.apply(added("b", 1))), [(EventType::Added, "b".into())]);
+    }
+
+    #[test]
+    fn initial_sync_can_be_reported() {
+        let mut forwarder = Forwarder::new("main", "player", true);
+        let initial = sync(&mut forwarder, vec![WatchListEvent::Clear, added("a", 1)]);
+        assert_eq!(initial, [(EventType::Added, "a".into())]);
+    }
+
+    #[test]
+    fn resync_reports_differences() {
+        let mut forwarder = Forwarder::new("main", "player", false);
+        sync(&mut forwarder, vec![WatchListEvent::Clear, added("a", 1), added("b", 1)]);
+
+        let resync =
+            sync(&mut forwarder, vec![WatchListEvent::Clear, added("a", 1), added("c", 1)]);
+        assert_eq!(resync, [(EventType::Added, "c".into()), (EventType::Removed, "b".into())]);
+
+        let resync = sync(&mut forwarder, vec![WatchListEvent::Clear, added("a", 2)]);
+        assert_eq!(resync, [(EventType::Added, "a".into()), (EventType::Removed, "c".into())]);
+    }
+
+    #[test]
+    fn resync_ends_at_next_event() {
+        let mut forwarder = Forwarder::new("main", "player", false);
+        sync(&mut forwarder, vec![WatchListEvent::Clear, added("a", 1), added("b", 1)]);
+
+        forwarder.apply(WatchListEvent::Clear);
+        forwarder.apply(added("a", 1));
+        let events = forwarder.apply(WatchListEvent::Removed { name: "a".into() });
+        assert_eq!(
+            summary(events),
+            [(EventType::Removed, "b".into()), (EventType::Removed, "a".into())],
+        );
+        assert!(!forwarder.is_resyncing());
+    }
+
+    #[test]
+    fn failed_resync_keeps_previous_objects() {
+        let mut forwarder = Forwarder::new("main", "player", false);
+        sync(&mut forwarder, vec![WatchListEvent::Clear, added("a", 1), added("b", 1)]);
+
+        forwarder.apply(WatchListEvent::Clear);
+        forwarder.apply(added("a", 1));
+        let resync = sync(&mut forwarder, vec![WatchListEvent::Clear, added("b", 1)]);
+        assert_eq!(resync, [(EventType::Removed, "a".into())]);
+    }
+}
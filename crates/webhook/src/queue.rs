@@ -0,0 +1,127 @@
+//! Batches the events of a webhook and delivers them from a persistent queue.
+//!
+//! Each batch is written to a file in the queue directory of the webhook
+//! and only deleted once the webhook accepted it,
+//! so batches are retried across restarts until they are delivered, in order.
+//! Events are lost if the bridge stops before their batching window ends.
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Context as _;
+use serde::Serialize;
+use tokio::sync::{mpsc, Notify};
+use tokio::time::Instant;
+
+use crate::config::Webhook;
+use crate::Event;
+
+const MIN_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// The body of the requests to webhooks.
+#[derive(Serialize)]
+struct Payload<'a> {
+    webhook: &'a str,
+    events:  &'a [Event],
+}
+
+/// Runs the queue of a webhook until the event channel is closed.
+pub async fn run(
+    webhook: Arc<Webhook>,
+    dir: PathBuf,
+    http: reqwest::Client,
+    mut events: mpsc::UnboundedReceiver<Event>,
+) -> anyhow::Result<()> {
+    let dir = dir.join(&webhook.name);
+    tokio::fs::create_dir_all(&dir).await.with_context(|| format!("create {}", dir.display()))?;
+    let mut next = match pending(&dir).await?.last() {
+        Some((seq, _)) => seq + 1,
+        None => 0,
+    };
+
+    let notify = Arc::new(Notify::new());
+    tokio::spawn(deliver(webhook.clone(), dir.clone(), http, notify.clone()));
+
+    while let Some(event) = events.recv().await {
+        let deadline = Instant::now() + webhook.batch_window();
+        let mut batch = vec![event];
+        while batch.len() < webhook.max_batch {
+            match tokio::time::timeout_at(deadline, events.recv()).await {
+                Ok(Some(event)) => batch.push(event),
+                Ok(None) | Err(_) => break,
+            }
+        }
+
+        let payload = Payload { webhook: &webhook.name, events: &batch };
+        let body = serde_json::to_vec(&payload).context("serialize batch")?;
+        let path = dir.join(format!("{next:016}.json"));
+        let tmp = path.with_extension("tmp");
+        // rename so that a partially written batch is never sent
+        tokio::fs::write(&tmp, body).await.with_context(|| format!("write {}", tmp.display()))?;
+        tokio::fs::rename(&tmp, &path)
+            .await
+            .with_context(|| format!("write {}", path.display()))?;
+        next += 1;
+        notify.notify_one();
+    }
+
+    Ok(())
+}
+
+/// The batches in the queue directory, oldest first.
+async fn pending(dir: &Path) -> anyhow::Result<Vec<(u64, PathBuf)>> {
+    let mut batches = Vec::new();
+    let mut entries =
+        tokio::fs::read_dir(dir).await.with_context(|| format!("read {}", dir.display()))?;
+    while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+        if path.extension().map_or(true, |ext| ext != "json") {
+            continue;
+        }
+        if let Some(seq) = path.file_stem().and_then(|stem| stem.to_str()?.parse().ok()) {
+            batches.push((seq, path));
+        }
+    }
+    batches.sort();
+    Ok(batches)
+}
+
+/// Sends the pending batches one at a time, backing off while the webhook fails.
+async fn deliver(webhook: Arc<Webhook>, dir: PathBuf, http: reqwest::Client, notify: Arc<Notify>) {
+    let mut backoff = MIN_BACKOFF;
+    loop {
+        match send_oldest(&webhook, &dir, &http).await {
+            Ok(true) => backoff = MIN_BACKOFF,
+            Ok(false) => notify.notified().await,
+            Err(err) => {
+                eprintln!("webhook {}: {err:#}, retrying in {}s", webhook.name, backoff.as_secs());
+                tokio::time::sleep(backoff).await;
+                backoff = (backoff * 2).min(MAX_BACKOFF);
+            }
+        }
+    }
+}
+
+/// Sends the oldest pending batch and returns whether there was one.
+async fn send_oldest(
+    webhook: &Webhook,
+    dir: &Path,
+    http: &reqwest::Client,
+) -> anyhow::Result<bool> {
+    let Some((_, path)) = pending(dir).await?.into_iter().next() else { return Ok(false) };
+    let body = tokio::fs::read(&path).await.with_context(|| format!("read {}", path.display()))?;
+
+    let mut req = http.post(&webhook.url).header("Content-Type", "application/json");
+    for (name, value) in &webhook.headers {
+        req = req.header(name, value);
+    }
+    let resp = req.body(body).send().await.context("send batch")?;
+    if !resp.status().is_success() {
+        anyhow::bail!("server responded with {}", resp.status());
+    }
+
+    tokio::fs::remove_file(&path).await.with_context(|| format!("remove {}", path.display()))?;
+    Ok(true)
+}
@@ -114,12 +114,12 @@ final class Handler {
                 }
             }
 
-            if ($isWatch) {
-                $identity = yield from $objectDef->desc->get($name);
-                if ($identity === null) {
-                    return $this->notFound();
-                }
+            $identity = yield from $objectDef->desc->get($name);
+            if ($identity === null) {
+                return $this->notFound();
+            }
 
+            if ($isWatch) {
                 return new HttpResponse(
                     "HTTP/1.0",
                     "200 OK",
@@ -132,6 +132,9 @@ final class Handler {
                     }),
                 );
             }
+
+            $item = yield from self::populateObjectFields($objectDef, $identity, $fieldFilter, fn($field) => $field->desc->get($identity));
+            return $this->okJson($item);
         }
 
         return new HttpResponse("HTTP/1.0", "405 Method Not Allowed", new HttpHeaders, Traverser::fromClosure(function() {
@@ -1,10 +1,21 @@
 base-home = Home
 base-error = Error
+base-error-unauthorized = The server requires you to log in.
+base-error-not-found = The server does not provide this resource.
+base-error-method-not-allowed = The server does not support this operation.
+base-error-server = The server failed to handle the request.
+base-error-network = Cannot connect to the server.
+base-error-decode = The server sent a response that cannot be understood.
+base-error-invalid-header = A configured request header is invalid.
 base-nav-switch-server = Switch server
+base-nav-download-recording = Download recording
 base-properties-title = Properties
 base-properties-search = Search
 base-display-card = Cards
 base-display-table = Table
+base-info-live = Live
+base-info-snapshot = Snapshot
+base-info-refresh = Refresh
 base-name = Name
 base-list-empty = nil
 base-list-item-count-nested = {$count ->
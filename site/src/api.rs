@@ -1,25 +1,39 @@
 #![allow(dead_code)]
 
-use std::borrow::Borrow;
-use std::cmp;
-use std::collections::HashSet;
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, HashSet};
 use std::future::Future;
-use std::hash::Hash;
-use std::rc::Rc;
+use std::rc::{Rc, Weak};
+use std::time::Duration;
 
-use anyhow::Context;
+use base64::Engine as _;
 use fluent::{FluentBundle, FluentResource};
+use futures::stream::LocalBoxStream;
 use futures::{Stream, StreamExt};
-use gloo::net::eventsource::futures::EventSource;
 use gloo::net::http;
 use gloo::storage::Storage as _;
 use serde::de::DeserializeOwned;
-use serde::Deserialize;
-use yew::hook;
-
-use crate::i18n::{self, I18n};
-use crate::pages::list as list_page;
-use crate::util::{Grc, HasId, IdMap, RcStr, StreamWith};
+use serde::{Deserialize, Serialize};
+use webconsole_protocol::session::Record;
+use webconsole_protocol::{decode_json, ndjson, object_path, sse};
+pub use webconsole_protocol::{
+    ApiError, CompoundSubfield, Discovery, DisplayMode, EnumOption, FieldDef, FieldType, Group,
+    GroupKind, GroupKindDyn, GroupKindRef, KnownFieldMetadata, KnownObjectMetadata, Object,
+    ObjectDef, Selection, Transports, WatchListEvent, WatchSingleEvent,
+};
+use yew::{hook, use_memo, Callback};
+
+use crate::i18n::I18n;
+use crate::util::{Grc, RcStr, StreamWith};
+
+mod fetch;
+mod mux;
+mod record;
+mod registry;
+
+use record::Exchange;
+pub use record::Recorder;
+pub use registry::{ListKey, ListSubscription};
 
 #[derive(Deserialize)]
 struct UrlQuery {
@@ -42,21 +56,219 @@ pub fn infer_host() -> RcStr {
     RcStr::new("http://localhost:14875")
 }
 
+/// Whether the page was opened with `?record`, in which case the client records a session.
+pub fn recording_requested() -> bool {
+    let Ok(search) = gloo::utils::window().location().search() else { return false };
+    search
+        .trim_start_matches('?')
+        .split('&')
+        .any(|pair| pair.split_once('=').map_or(pair, |(key, _)| key) == "record")
+}
+
+pub const CREDENTIALS_STORAGE_KEY: &str = "webconsole:apiserver-credentials";
+
+/// Credentials sent to an authenticating proxy in front of the API server.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Credentials {
+    Token { token: String },
+    Basic { username: String, password: String },
+}
+
+impl Credentials {
+    /// The value of the `Authorization` header.
+    pub fn authorization(&self) -> String {
+        match self {
+            Self::Token { token } => format!("Bearer {token}"),
+            Self::Basic { username, password } => {
+                let encoded = base64::engine::general_purpose::STANDARD
+                    .encode(format!("{username}:{password}"));
+                format!("Basic {encoded}")
+            }
+        }
+    }
+}
+
+/// Loads the credentials stored for `host`.
+pub fn load_credentials(host: &str) -> Option<Credentials> {
+    let mut all: HashMap<String, Credentials> =
+        gloo::storage::LocalStorage::get(CREDENTIALS_STORAGE_KEY).ok()?;
+    all.remove(host)
+}
+
+/// Stores the credentials for `host`, or forgets them if `credentials` is `None`.
+pub fn store_credentials(host: &str, credentials: Option<&Credentials>) {
+    let mut all: HashMap<String, Credentials> =
+        gloo::storage::LocalStorage::get(CREDENTIALS_STORAGE_KEY).unwrap_or_default();
+    match credentials {
+        Some(credentials) => all.insert(host.to_string(), credentials.clone()),
+        None => all.remove(host),
+    };
+    if let Err(err) = gloo::storage::LocalStorage::set(CREDENTIALS_STORAGE_KEY, all) {
+        log::error!("store credentials: {err:?}");
+    }
+}
+
 #[hook]
-pub fn use_client(host: RcStr) -> Grc<Client> { Grc::new(Client { host }) }
+pub fn use_client(
+    host: RcStr,
+    credentials: Option<Credentials>,
+    on_unauthorized: Callback<ApiError>,
+) -> Grc<Client> {
+    let client = use_memo(
+        |(host, credentials)| {
+            let mut client = Client::new(host.clone()).on_unauthorized(on_unauthorized);
+            if let Some(credentials) = credentials {
+                client = client.with_credentials(credentials);
+            }
+            if recording_requested() {
+                client = client.with_recorder(Recorder::new());
+            }
+            client
+        },
+        (host, credentials),
+    );
+    Grc::from_rc(client)
+}
 
 pub struct Client {
-    pub host: RcStr,
+    pub host:      RcStr,
+    /// Extra headers sent with every request, e.g. for an authenticating proxy.
+    pub headers:   Vec<(RcStr, RcStr)>,
+    pub transport: Transport,
+    /// Path of the multiplexed WebSocket endpoint, if advertised in discovery.
+    mux_path:      RefCell<Option<RcStr>>,
+    mux:           RefCell<Option<Rc<mux::Mux>>>,
+    /// Set when a WebSocket closed before opening, after which watches use SSE.
+    mux_failed:    Cell<bool>,
+    /// List watches shared among components, see [`Client::subscribe_list`].
+    lists:         RefCell<HashMap<registry::ListKey, Weak<registry::ListSubscription>>>,
+    /// Called when the server rejects the credentials.
+    unauthorized:  Option<Callback<ApiError>>,
+    recorder:      Option<Rc<Recorder>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Transport {
+    /// Always open one event stream per watch.
+    Sse,
+    /// Share one WebSocket among all watches if the server supports it, otherwise use SSE.
+    Auto,
 }
 
 impl Client {
-    pub fn locales(self: &Rc<Self>) -> impl Future<Output = anyhow::Result<I18n>> {
+    pub fn new(host: RcStr) -> Self {
+        Self {
+            host,
+            headers: Vec::new(),
+            transport: Transport::Auto,
+            mux_path: RefCell::new(None),
+            mux: RefCell::new(None),
+            mux_failed: Cell::new(false),
+            lists: RefCell::new(HashMap::new()),
+            unauthorized: None,
+            recorder: None,
+        }
+    }
+
+    pub fn with_header(mut self, name: impl Into<RcStr>, value: impl Into<RcStr>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    pub fn with_transport(mut self, transport: Transport) -> Self {
+        self.transport = transport;
+        self
+    }
+
+    /// Sends the credentials in the `Authorization` header of every request.
+    pub fn with_credentials(self, credentials: &Credentials) -> Self {
+        self.with_header(String::from("Authorization"), credentials.authorization())
+    }
+
+    /// Calls `callback` whenever a request fails with [`ApiError::Unauthorized`].
+    pub fn on_unauthorized(mut self, callback: Callback<ApiError>) -> Self {
+        self.unauthorized = Some(callback);
+        self
+    }
+
+    /// Records every request, response and event of this client.
+    pub fn with_recorder(mut self, recorder: Recorder) -> Self {
+        self.recorder = Some(Rc::new(recorder));
+        self
+    }
+
+    pub fn recorder(&self) -> Option<&Rc<Recorder>> { self.recorder.as_ref() }
+
+    fn exchange(&self, path: &str, last_event_id: Option<&str>) -> Exchange {
+        Exchange::start(self.recorder.as_ref(), path, last_event_id)
+    }
+
+    fn report(&self, err: ApiError) -> ApiError {
+        if let (ApiError::Unauthorized { .. }, Some(callback)) = (&err, &self.unauthorized) {
+            callback.emit(err.clone());
+        }
+        err
+    }
+
+    fn http_request(&self, path: &str) -> http::Request {
+        let mut req = http::Request::new(&format!("{}/{path}", &self.host));
+        for (name, value) in &self.headers {
+            req = req.header(name, value);
+        }
+        req
+    }
+
+    /// Sends a request and rejects unsuccessful statuses.
+    async fn send(
+        &self,
+        path: &str,
+        req: http::Request,
+        exchange: &Exchange,
+    ) -> Result<http::Response, ApiError> {
+        let resp = match req.send().await {
+            Ok(resp) => resp,
+            Err(err) => {
+                exchange.record(Record::Error { message: err.to_string() });
+                return Err(ApiError::network(err));
+            }
+        };
+
+        let status = resp.status();
+        if resp.ok() {
+            exchange.record(Record::Response { status, body: None });
+            return Ok(resp);
+        }
+
+        let body = resp.text().await.unwrap_or_default();
+        exchange.record(Record::Response { status, body: Some(body.clone()) });
+        Err(self.report(ApiError::from_status(path, status, body)))
+    }
+
+    /// Sends a request and reads the whole body of the response.
+    async fn get_text(&self, path: &str) -> Result<String, ApiError> {
+        let exchange = self.exchange(path, None);
+        let resp = self.send(path, self.http_request(path), &exchange).await?;
+        match resp.text().await {
+            Ok(text) => {
+                exchange.record(Record::Body { text: text.clone() });
+                Ok(text)
+            }
+            Err(err) => {
+                exchange.record(Record::Error { message: err.to_string() });
+                Err(ApiError::network(err))
+            }
+        }
+    }
+
+    // the async blocks own the clone of the client borrowed by the `_impl` methods
+    #[allow(clippy::redundant_async_block)]
+    pub fn locales(self: &Rc<Self>) -> impl Future<Output = Result<I18n, ApiError>> {
         let this = self.clone();
         async move { this.locales_impl().await }
     }
-    async fn locales_impl(&self) -> anyhow::Result<I18n> {
-        let locales: HashSet<Box<str>> =
-            self.request("locales").await.context("request locales")?;
+    async fn locales_impl(&self) -> Result<I18n, ApiError> {
+        let locales: HashSet<Box<str>> = self.request("locales").await?;
         let prefers = gloo::utils::window().navigator().languages();
         let prefer = prefers.find(&mut |value, _i, _array| {
             let value = value.as_string().expect("locales should be list of strings");
@@ -65,335 +277,352 @@ impl Client {
         let prefer = match prefer.as_string() {
             Some(prefer) => prefer,
             None => {
-                let locale = locales.iter().next().context("server locale list is mepty")?;
+                let Some(locale) = locales.iter().next() else {
+                    return Err(ApiError::decode("server locale list is empty", None));
+                };
                 locale.to_string()
             }
         };
 
-        let resp = http::Request::new(&format!("{}/{prefer}.ftl", &self.host))
-            .send()
-            .await
-            .context("request ftl file")?;
-        if resp.status() != 200 {}
-        let ftl_str = resp.text().await.context("receive ftl file")?;
+        let ftl_str = self.get_text(&format!("{prefer}.ftl")).await?;
         let res = match FluentResource::try_new(ftl_str) {
             Ok(res) => res,
-            Err((_res, errs)) => {
+            Err((res, errs)) => {
                 let err = errs.into_iter().next().expect("at least one error");
-                return Err(err).context("parse fluent resource");
+                let line = line_at(res.source(), err.pos.start);
+                return Err(ApiError::decode(
+                    format!("parse fluent resource: {err}"),
+                    Some(line.to_string()),
+                ));
             }
         };
 
-        let mut bundle =
-            FluentBundle::new(vec![prefer.parse().context("server provided invalid locale")?]);
+        let locale = prefer.parse().map_err(|err| {
+            ApiError::decode(format!("server provided invalid locale: {err}"), Some(prefer))
+        })?;
+        let mut bundle = FluentBundle::new(vec![locale]);
         if let Err(errs) = bundle.add_resource(res) {
             let err = errs.into_iter().next().expect("at least one error");
-            return Err(err).context("add resource to fluent bundle");
+            return Err(ApiError::decode(format!("add resource to fluent bundle: {err}"), None));
         }
 
         Ok(I18n { bundle: Grc::new(bundle) })
     }
 
-    pub fn discovery(self: &Rc<Self>) -> impl Future<Output = anyhow::Result<Grc<Discovery>>> {
+    #[allow(clippy::redundant_async_block)]
+    pub fn discovery(self: &Rc<Self>) -> impl Future<Output = Result<Grc<Discovery>, ApiError>> {
         let this = self.clone();
         async move { this.discovery_impl().await }
     }
-    async fn discovery_impl(&self) -> anyhow::Result<Grc<Discovery>> {
-        Ok(Grc::new(self.request("discovery").await?))
+    async fn discovery_impl(&self) -> Result<Grc<Discovery>, ApiError> {
+        let discovery: Discovery = self.request("discovery").await?;
+        *self.mux_path.borrow_mut() = discovery.transports.websocket.clone().map(RcStr::from);
+        Ok(Grc::new(discovery))
     }
 
+    #[allow(clippy::redundant_async_block)]
     pub fn list(
         self: &Rc<Self>,
         group: String,
         kind: String,
-    ) -> impl Future<Output = anyhow::Result<Vec<Object>>> {
-        let this = self.clone();
-        async move { this.list_impl(&group, &kind).await }
-    }
-    async fn list_impl(&self, group: &str, kind: &str) -> anyhow::Result<Vec<Object>> {
-        let resp = http::Request::new(&format!("{}/{group}/{kind}", &self.host))
-            .send()
-            .await
-            .context("sending list request")?;
-        let lines = resp.text().await.context("response is not valid UTF-8")?;
-
-        let mut objects = Vec::new();
-        for line in lines.split("\n") {
-            if !line.is_empty() {
-                let object = serde_json::from_str(line).context("deserialize list json")?;
-                objects.push(object);
-            }
-        }
-
-        Ok(objects)
-    }
-
-    pub fn watch_list(
-        self: &Rc<Self>,
-        group: String,
-        kind: String,
-    ) -> impl Future<Output = anyhow::Result<impl Stream<Item = anyhow::Result<WatchListEvent>>>>
+        selection: Selection,
+    ) -> impl Future<Output = Result<LocalBoxStream<'static, Result<Object, ApiError>>, ApiError>>
     {
         let this = self.clone();
-        async move { this.watch_list_impl(&group, &kind).await }
+        async move { this.list_impl(&group, &kind, &selection).await }
     }
-    async fn watch_list_impl(
+    /// Yields the objects as their lines arrive instead of waiting for the whole body.
+    async fn list_impl(
         &self,
         group: &str,
         kind: &str,
-    ) -> anyhow::Result<impl Stream<Item = anyhow::Result<WatchListEvent>>> {
-        let mut es = EventSource::new(&format!("{}/{group}/{kind}?watch=true", &self.host))
-            .context("instantiate EventSource to watch events")?;
-        let sub = es.subscribe("message").context("subscribe to message events")?;
-
-        let mapped = sub.map(|event| {
-            let (_, event) =
-                event.map_err(|err| anyhow::anyhow!("{err}")).context("read event error")?;
-            let de = serde_json::from_str(
-                &event.data().as_string().context("event data should be string")?,
-            )
-            .context("deserialize event json")?;
-            Ok(de)
+        selection: &Selection,
+    ) -> Result<LocalBoxStream<'static, Result<Object, ApiError>>, ApiError> {
+        let path = object_path(&format!("{group}/{kind}"), false, selection);
+        let abort = fetch::AbortGuard::new().map_err(ApiError::network)?;
+        let req = self.http_request(&path).abort_signal(Some(&abort.signal()));
+        let exchange = self.exchange(&path, None);
+        let resp = self.send(&path, req, &exchange).await?;
+        let Some(body) = resp.body() else {
+            return Err(ApiError::network("list response has no body"));
+        };
+
+        let mut decoder = ndjson::Decoder::default();
+        let errors = exchange.clone();
+        let lines = fetch::read_chunks(body)
+            .map(Some)
+            .chain(futures::stream::once(async { None }))
+            .map(move |chunk| match chunk {
+                Some(Ok(chunk)) => decoder.feed(&chunk).into_iter().map(Ok).collect(),
+                Some(Err(err)) => {
+                    let message = format!("{err:?}");
+                    errors.record(Record::Error { message: message.clone() });
+                    vec![Err(ApiError::network(message))]
+                }
+                None => decoder.finish().into_iter().map(Ok).collect(),
+            })
+            .flat_map(futures::stream::iter);
+        let lines_exchange = exchange.clone();
+        let objects = lines.map(move |line| {
+            let line = line?;
+            let text = String::from_utf8_lossy(&line.bytes);
+            lines_exchange.record(Record::Line { line: text.to_string() });
+            decode_json(&text).map_err(|err| err.on_line(line.number))
         });
 
-        Ok(StreamWith { stream: mapped, attach: es })
+        let stream = Box::pin(exchange.until_end(objects));
+        Ok(StreamWith { stream, attach: abort }.boxed_local())
     }
 
-    pub fn watch_single(
+    #[allow(clippy::redundant_async_block)]
+    pub fn get(
         self: &Rc<Self>,
         group: String,
         kind: String,
         name: String,
-    ) -> impl Future<Output = anyhow::Result<impl Stream<Item = anyhow::Result<WatchSingleEvent>>>>
-    {
+        fields: Option<Vec<String>>,
+    ) -> impl Future<Output = Result<Object, ApiError>> {
         let this = self.clone();
-        async move { this.watch_single_impl(&group, &kind, &name).await }
+        async move { this.get_impl(&group, &kind, &name, fields.as_deref()).await }
     }
-    async fn watch_single_impl(
+    async fn get_impl(
         &self,
         group: &str,
         kind: &str,
         name: &str,
-    ) -> anyhow::Result<impl Stream<Item = anyhow::Result<WatchSingleEvent>>> {
-        let mut es = EventSource::new(&format!("{}/{group}/{kind}/{name}?watch=true", &self.host,))
-            .context("instantiate EventSource to watch events")?;
-        let sub = es.subscribe("message").context("subscribe to message events")?;
-
-        let mapped = sub.map(|event| {
-            let (_, event) =
-                event.map_err(|err| anyhow::anyhow!("{err}")).context("read event error")?;
-            let de = serde_json::from_str(
-                &event.data().as_string().context("event data should be string")?,
-            )
-            .context("deserialize event json")?;
-            Ok(de)
-        });
-
-        Ok(StreamWith { stream: mapped, attach: es })
+        fields: Option<&[String]>,
+    ) -> Result<Object, ApiError> {
+        let selection = Selection { fields: fields.map(<[_]>::to_vec), limit: None };
+        let path = object_path(&format!("{group}/{kind}/{name}"), false, &selection);
+        self.request(&path).await
     }
 
-    async fn request<T: DeserializeOwned>(&self, path: &str) -> anyhow::Result<T> {
-        Ok(http::Request::new(&format!("{}/{path}", &self.host)).send().await?.json::<T>().await?)
+    #[allow(clippy::redundant_async_block)]
+    pub fn watch_list(
+        self: &Rc<Self>,
+        group: String,
+        kind: String,
+        selection: Selection,
+        resume: ResumeToken,
+    ) -> impl Future<Output = Result<LocalBoxStream<'static, Result<WatchListEvent, ApiError>>, ApiError>>
+    {
+        let this = self.clone();
+        async move { this.watch(&group, &kind, None, &selection, resume).await }
     }
-}
 
-#[derive(Deserialize, PartialEq)]
-pub struct Discovery {
-    pub groups: IdMap<RcStr, Group>,
-    pub apis:   IdMap<GroupKind, ObjectDef>,
-}
-
-#[derive(Deserialize, Clone, PartialEq)]
-pub struct ObjectDef {
-    #[serde(flatten)]
-    pub id:           GroupKind,
-    pub display_name: i18n::Key,
-    #[serde(default)]
-    pub metadata:     KnownObjectMetadata,
-    pub fields:       IdMap<RcStr, FieldDef>,
-}
-impl HasId<GroupKind> for ObjectDef {
-    fn id(&self) -> GroupKind { self.id.clone() }
-}
+    #[allow(clippy::redundant_async_block)]
+    pub fn watch_single(
+        self: &Rc<Self>,
+        group: String,
+        kind: String,
+        name: String,
+        fields: Option<Vec<String>>,
+        resume: ResumeToken,
+    ) -> impl Future<
+        Output = Result<LocalBoxStream<'static, Result<WatchSingleEvent, ApiError>>, ApiError>,
+    > {
+        let this = self.clone();
+        let selection = Selection { fields, limit: None };
+        async move { this.watch(&group, &kind, Some(&name), &selection, resume).await }
+    }
 
-#[derive(Deserialize, Clone, PartialEq, Default)]
-pub struct KnownObjectMetadata {
-    #[serde(rename = "webconsole/site/hide-name")]
-    #[serde(default)]
-    pub hide_name: bool,
+    /// Returns the shared watch of a list, opening a new one if no component holds it.
+    pub fn subscribe_list(
+        self: &Rc<Self>,
+        group: String,
+        kind: String,
+        selection: Selection,
+    ) -> Rc<ListSubscription> {
+        let key = ListKey { group, kind, selection };
 
-    #[serde(rename = "webconsole/site/desc-name")]
-    #[serde(default)]
-    pub desc_name: bool,
+        let mut lists = self.lists.borrow_mut();
+        if let Some(sub) = lists.get(&key).and_then(Weak::upgrade) {
+            return sub;
+        }
 
-    #[serde(rename = "webconsole/site/default-display-mode")]
-    #[serde(default)]
-    pub default_display_mode: list_page::DisplayMode,
-}
+        lists.retain(|_, sub| sub.strong_count() > 0);
+        let sub = Rc::new(ListSubscription::new(key.clone(), self.clone()));
+        lists.insert(key, Rc::downgrade(&sub));
+        sub
+    }
 
-#[derive(Deserialize, Clone, PartialEq)]
-pub struct FieldDef {
-    pub path:         RcStr,
-    pub display_name: i18n::Key,
-    #[serde(default)]
-    pub metadata:     KnownFieldMetadata,
-    #[serde(rename = "type")]
-    pub ty:           FieldType,
-}
-impl HasId<RcStr> for FieldDef {
-    fn id(&self) -> RcStr { self.path.clone() }
-}
+    async fn watch<T: DeserializeOwned + 'static>(
+        &self,
+        group: &str,
+        kind: &str,
+        name: Option<&str>,
+        selection: &Selection,
+        resume: ResumeToken,
+    ) -> Result<LocalBoxStream<'static, Result<T, ApiError>>, ApiError> {
+        let path = match name {
+            Some(name) => format!("{group}/{kind}/{name}"),
+            None => format!("{group}/{kind}"),
+        };
+        let path = object_path(&path, true, selection);
+
+        if let Some(mux) = self.mux() {
+            let unauthorized = self.unauthorized.clone();
+            let exchange = self.exchange(&path, resume.get().as_deref());
+            let stream = mux.subscribe(group, kind, name, selection, resume, exchange)?;
+            let stream = stream.map(move |event| {
+                if let (Err(err @ ApiError::Unauthorized { .. }), Some(callback)) =
+                    (&event, &unauthorized)
+                {
+                    callback.emit(err.clone());
+                }
+                event
+            });
+            return Ok(stream.boxed_local());
+        }
 
-#[derive(Deserialize, Clone, PartialEq, Default)]
-pub struct KnownFieldMetadata {
-    #[serde(rename = "webconsole/site/display-priority")]
-    #[serde(default)]
-    pub display_priority: i32,
-    #[serde(rename = "webconsole/site/hide-by-default")]
-    #[serde(default)]
-    pub hide_by_default:  bool,
-}
+        let events = self.event_stream(&path, resume.get().as_deref()).await?;
+
+        let mapped = events.filter_map(move |event| {
+            let resume = resume.clone();
+            async move {
+                let event = match event {
+                    Ok(event) => event,
+                    Err(err) => return Some(Err(err)),
+                };
+                resume.observe(&event);
+                if event.event != "message" {
+                    return None;
+                }
+                Some(decode_json(&event.data))
+            }
+        });
 
-#[derive(Deserialize, Clone, PartialEq)]
-#[serde(tag = "type", rename_all = "snake_case")]
-pub enum FieldType {
-    String {},
-    Int64 {
-        #[serde(default)]
-        is_timestamp: bool,
-        #[serde(default)]
-        min:          Option<i64>,
-        #[serde(default)]
-        max:          Option<i64>,
-    },
-    Float64 {
-        #[serde(default)]
-        is_timestamp: bool,
-        #[serde(default)]
-        min:          Option<f64>,
-        #[serde(default)]
-        max:          Option<f64>,
-    },
-    Bool {},
-    Enum {
-        options: IdMap<RcStr, EnumOption>,
-    },
-    Object {
-        #[serde(flatten)]
-        gk: GroupKind,
-    },
-    Nullable {
-        item: Box<FieldType>,
-    },
-    List {
-        item: Box<FieldType>,
-    },
-    Compound {
-        fields: IdMap<RcStr, CompoundSubfield>,
-    },
-}
+        Ok(mapped.boxed_local())
+    }
 
-#[derive(Deserialize, Clone, PartialEq)]
-pub struct CompoundSubfield {
-    pub key:  RcStr,
-    pub name: i18n::Key,
-    #[serde(rename = "type")]
-    pub ty:   FieldType,
-}
+    /// Returns the shared WebSocket, reopening it if the previous one was closed.
+    ///
+    /// If a socket closes before it ever opened, the WebSocket endpoint is assumed unreachable
+    /// and `None` is returned from then on.
+    ///
+    /// Browsers cannot send custom headers with WebSocket handshakes,
+    /// so SSE is used whenever [`Client::headers`] is non-empty.
+    fn mux(&self) -> Option<Rc<mux::Mux>> {
+        if self.transport != Transport::Auto || !self.headers.is_empty() {
+            return None;
+        }
+        if self.mux_failed.get() {
+            return None;
+        }
+        let path = self.mux_path.borrow().clone()?;
 
-impl HasId<RcStr> for CompoundSubfield {
-    fn id(&self) -> RcStr { self.key.clone() }
-}
+        let mut mux = self.mux.borrow_mut();
+        if let Some(old) = &*mux {
+            if !old.is_closed() {
+                return Some(old.clone());
+            }
+            if old.never_opened() {
+                log::warn!("websocket closed before opening, falling back to SSE");
+                self.mux_failed.set(true);
+                *mux = None;
+                return None;
+            }
+        }
 
-#[derive(Deserialize, Clone, PartialEq)]
-pub struct EnumOption {
-    pub id:   RcStr,
-    pub i18n: i18n::Key,
-}
+        match mux::Mux::open(&mux::ws_url(&self.host, &path)) {
+            Ok(new) => {
+                *mux = Some(new.clone());
+                Some(new)
+            }
+            Err(err) => {
+                log::warn!("cannot open websocket, falling back to SSE: {err:?}");
+                None
+            }
+        }
+    }
 
-impl HasId<RcStr> for EnumOption {
-    fn id(&self) -> RcStr { self.id.clone() }
-}
+    /// Opens a `text/event-stream` through `fetch` so that custom headers can be sent.
+    async fn event_stream(
+        &self,
+        path: &str,
+        last_event_id: Option<&str>,
+    ) -> Result<impl Stream<Item = Result<sse::Event, ApiError>>, ApiError> {
+        let abort = fetch::AbortGuard::new().map_err(ApiError::network)?;
+        let mut req = self
+            .http_request(path)
+            .header("Accept", "text/event-stream")
+            .abort_signal(Some(&abort.signal()));
+        if let Some(id) = last_event_id {
+            req = req.header("Last-Event-ID", id);
+        }
+        let exchange = self.exchange(path, last_event_id);
+        let resp = self.send(path, req, &exchange).await?;
+        let Some(body) = resp.body() else {
+            return Err(ApiError::network("watch response has no body"));
+        };
 
-#[derive(Deserialize, Clone, PartialEq)]
-pub struct Group {
-    pub id:               RcStr,
-    pub display_name:     i18n::Key,
-    pub display_priority: u32,
-}
-impl HasId<RcStr> for Group {
-    fn id(&self) -> RcStr { self.id.clone() }
-}
+        let mut parser = sse::Parser::default();
+        let events_exchange = exchange.clone();
+        let events = fetch::read_chunks(body)
+            .map(move |chunk| match chunk {
+                Ok(chunk) => parser.feed(&chunk).into_iter().map(Ok).collect(),
+                Err(err) => {
+                    let message = format!("{err:?}");
+                    events_exchange.record(Record::Error { message: message.clone() });
+                    vec![Err(ApiError::network(message))]
+                }
+            })
+            .flat_map(futures::stream::iter);
+        let events_exchange = exchange.clone();
+        let events = events.inspect(move |event| {
+            if let Ok(event) = event {
+                events_exchange.record(Record::Event {
+                    event: event.event.clone(),
+                    data:  event.data.clone(),
+                    id:    event.id.clone(),
+                    retry: event.retry.map(|retry| retry.as_millis() as u64),
+                });
+            }
+        });
 
-#[derive(Deserialize, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
-pub struct GroupKind {
-    pub group: RcStr,
-    pub kind:  RcStr,
-}
+        Ok(StreamWith { stream: Box::pin(exchange.until_end(events)), attach: abort })
+    }
 
-#[derive(PartialEq, Eq, PartialOrd, Ord, Hash)]
-pub struct GroupKindRef<'t> {
-    pub group: &'t str,
-    pub kind:  &'t str,
+    async fn request<T: DeserializeOwned>(&self, path: &str) -> Result<T, ApiError> {
+        decode_json(&self.get_text(path).await?)
+    }
 }
 
-pub trait GroupKindDyn {
-    fn group(&self) -> &str;
-    fn kind(&self) -> &str;
-}
-impl GroupKindDyn for GroupKind {
-    fn group(&self) -> &str { return &self.group }
-    fn kind(&self) -> &str { return &self.kind }
-}
-impl<'t> GroupKindDyn for GroupKindRef<'t> {
-    fn group(&self) -> &str { return &self.group }
-    fn kind(&self) -> &str { return &self.kind }
+fn line_at(text: &str, offset: usize) -> &str {
+    let start = text[..offset.min(text.len())].rfind('\n').map_or(0, |pos| pos + 1);
+    text[start..].lines().next().unwrap_or_default()
 }
 
-impl Hash for dyn GroupKindDyn + '_ {
-    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
-        self.group().hash(state);
-        self.kind().hash(state);
-    }
-}
-impl PartialEq for dyn GroupKindDyn + '_ {
-    fn eq(&self, other: &Self) -> bool {
-        self.group() == other.group() && self.kind() == other.kind()
-    }
-}
-impl Eq for dyn GroupKindDyn + '_ {}
-impl PartialOrd for dyn GroupKindDyn + '_ {
-    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> { Some(self.cmp(other)) }
-}
-impl Ord for dyn GroupKindDyn + '_ {
-    fn cmp(&self, other: &Self) -> cmp::Ordering {
-        self.group().cmp(other.group()).then_with(|| self.kind().cmp(other.kind()))
-    }
+/// Remembers the last event ID of a watch across reconnections.
+///
+/// The ID is sent back to the server on reconnection
+/// so that it can replay the missed events instead of restarting the watch.
+/// The reconnection time requested by the server with `retry:` is kept alongside.
+#[derive(Clone, Default)]
+pub struct ResumeToken(Rc<RefCell<Resume>>);
+
+#[derive(Default)]
+struct Resume {
+    id:    Option<String>,
+    retry: Option<Duration>,
 }
 
-impl<'t> Borrow<dyn GroupKindDyn + 't> for GroupKind {
-    fn borrow(&self) -> &(dyn GroupKindDyn + 't) { self }
-}
+impl ResumeToken {
+    pub fn get(&self) -> Option<String> { RefCell::borrow(&self.0).id.clone() }
 
-#[derive(Debug, Deserialize)]
-pub struct Object {
-    #[serde(rename = "_name")]
-    pub name:   String,
-    #[serde(flatten)]
-    pub fields: serde_json::Value,
-}
+    pub fn set(&self, id: Option<String>) { self.0.borrow_mut().id = id; }
 
-#[derive(Debug, Deserialize)]
-#[serde(tag = "event")]
-pub enum WatchListEvent {
-    Clear,
-    Added { item: Object },
-    Removed { name: RcStr },
-    FieldUpdate { name: RcStr, field: RcStr, value: serde_json::Value },
-}
+    /// The reconnection time most recently requested by the server.
+    pub fn retry(&self) -> Option<Duration> { RefCell::borrow(&self.0).retry }
 
-#[derive(Debug, Deserialize)]
-#[serde(tag = "event")]
-pub enum WatchSingleEvent {
-    Update { field: RcStr, value: serde_json::Value },
+    /// Remembers the event ID and reconnection time of a server-sent event.
+    pub fn observe(&self, event: &sse::Event) {
+        let mut resume = self.0.borrow_mut();
+        if event.id.is_some() {
+            resume.id = event.resume_id().map(str::to_string);
+        }
+        if event.retry.is_some() {
+            resume.retry = event.retry;
+        }
+    }
 }
@@ -0,0 +1,52 @@
+use anyhow::Context as _;
+use futures::Stream;
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+
+/// Aborts the underlying fetch request when dropped.
+pub struct AbortGuard(web_sys::AbortController);
+
+impl AbortGuard {
+    pub fn new() -> anyhow::Result<Self> {
+        let controller = web_sys::AbortController::new().map_err(js_error)?;
+        Ok(Self(controller))
+    }
+
+    pub fn signal(&self) -> web_sys::AbortSignal { self.0.signal() }
+}
+
+impl Drop for AbortGuard {
+    fn drop(&mut self) { self.0.abort(); }
+}
+
+/// Reads a response body chunk by chunk as the bytes arrive.
+pub fn read_chunks(body: web_sys::ReadableStream) -> impl Stream<Item = anyhow::Result<Vec<u8>>> {
+    let reader: web_sys::ReadableStreamDefaultReader = body.get_reader().unchecked_into();
+
+    futures::stream::unfold(Some(reader), |reader| async move {
+        let reader = reader?;
+        match read_chunk(&reader).await {
+            Ok(Some(chunk)) => Some((Ok(chunk), Some(reader))),
+            Ok(None) => None,
+            Err(err) => Some((Err(err), None)),
+        }
+    })
+}
+
+async fn read_chunk(
+    reader: &web_sys::ReadableStreamDefaultReader,
+) -> anyhow::Result<Option<Vec<u8>>> {
+    let result = JsFuture::from(reader.read()).await.map_err(js_error).context("read body")?;
+
+    let done = js_sys::Reflect::get(&result, &JsValue::from_str("done")).map_err(js_error)?;
+    if done.as_bool().unwrap_or(true) {
+        return Ok(None);
+    }
+
+    let value = js_sys::Reflect::get(&result, &JsValue::from_str("value")).map_err(js_error)?;
+    let array: js_sys::Uint8Array =
+        value.dyn_into().map_err(js_error).context("body chunk should be a Uint8Array")?;
+    Ok(Some(array.to_vec()))
+}
+
+pub fn js_error(err: JsValue) -> anyhow::Error { anyhow::anyhow!("{err:?}") }
@@ -0,0 +1,211 @@
+//! Multiplexes many watch subscriptions over a single WebSocket.
+//!
+//! See [`webconsole_protocol::mux`] for the frame format.
+
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use futures::channel::mpsc;
+use futures::{SinkExt, Stream, StreamExt};
+use gloo::net::websocket::futures::WebSocket;
+use gloo::net::websocket::Message;
+use serde::de::DeserializeOwned;
+use wasm_bindgen_futures::spawn_local;
+use webconsole_protocol::mux::{ClientFrame, ServerFrame};
+use webconsole_protocol::session::Record;
+
+use super::record::Exchange;
+use super::{ApiError, ResumeToken, Selection};
+use crate::util::StreamWith;
+
+type Subscribers = Rc<RefCell<HashMap<u64, Subscriber>>>;
+
+struct Subscriber {
+    path:     String,
+    tx:       mpsc::UnboundedSender<Result<serde_json::Value, ApiError>>,
+    resume:   ResumeToken,
+    exchange: Exchange,
+}
+
+pub struct Mux {
+    outgoing:    mpsc::UnboundedSender<ClientFrame>,
+    subs:        Subscribers,
+    next_id:     Cell<u64>,
+    /// Whether the socket has ever sent or received a frame.
+    established: Rc<Cell<bool>>,
+}
+
+impl Mux {
+    pub fn open(url: &str) -> Result<Rc<Self>, ApiError> {
+        let ws = WebSocket::open(url).map_err(ApiError::network)?;
+
+        let (outgoing, outgoing_rx) = mpsc::unbounded();
+        let subs = Subscribers::default();
+        let established = Rc::new(Cell::new(false));
+        spawn_local(run(ws, outgoing_rx, subs.clone(), established.clone()));
+
+        Ok(Rc::new(Self { outgoing, subs, next_id: Cell::new(1), established }))
+    }
+
+    /// Whether the socket has been closed, in which case a new one should be opened.
+    pub fn is_closed(&self) -> bool { self.outgoing.is_closed() }
+
+    /// Whether the socket was closed before the handshake completed,
+    /// e.g. because a proxy does not forward WebSockets.
+    pub fn never_opened(&self) -> bool { self.is_closed() && !self.established.get() }
+
+    pub fn subscribe<T: DeserializeOwned>(
+        self: &Rc<Self>,
+        group: &str,
+        kind: &str,
+        name: Option<&str>,
+        selection: &Selection,
+        resume: ResumeToken,
+        exchange: Exchange,
+    ) -> Result<impl Stream<Item = Result<T, ApiError>>, ApiError> {
+        let id = self.next_id.get();
+        self.next_id.set(id + 1);
+
+        let path = match name {
+            Some(name) => format!("{group}/{kind}/{name}"),
+            None => format!("{group}/{kind}"),
+        };
+        let last_event_id = resume.get();
+        let (tx, rx) = mpsc::unbounded();
+        self.subs.borrow_mut().insert(id, Subscriber { path, tx, resume, exchange });
+        let guard = Unsubscribe { id, mux: self.clone() };
+
+        self.outgoing
+            .unbounded_send(ClientFrame::Subscribe {
+                id,
+                group: group.to_string(),
+                kind: kind.to_string(),
+                name: name.map(str::to_string),
+                fields: selection.fields.clone(),
+                limit: selection.limit,
+                last_event_id,
+            })
+            .map_err(|_| ApiError::network("websocket is closed"))?;
+
+        let stream = rx.map(|value| {
+            let value = value?;
+            serde_json::from_value(value.clone())
+                .map_err(|err| ApiError::decode(err, Some(value.to_string())))
+        });
+        Ok(StreamWith { stream, attach: guard })
+    }
+}
+
+struct Unsubscribe {
+    id:  u64,
+    mux: Rc<Mux>,
+}
+
+impl Drop for Unsubscribe {
+    fn drop(&mut self) {
+        if self.mux.subs.borrow_mut().remove(&self.id).is_some() {
+            _ = self.mux.outgoing.unbounded_send(ClientFrame::Unsubscribe { id: self.id });
+        }
+    }
+}
+
+async fn run(
+    ws: WebSocket,
+    mut outgoing_rx: mpsc::UnboundedReceiver<ClientFrame>,
+    subs: Subscribers,
+    established: Rc<Cell<bool>>,
+) {
+    let (mut sink, stream) = ws.split();
+    let mut stream = stream.fuse();
+
+    let result: Result<(), ApiError> = async {
+        loop {
+            futures::select! {
+                frame = outgoing_rx.next() => {
+                    let Some(frame) = frame else { return Ok(()) };
+                    let text = serde_json::to_string(&frame).expect("frames are serializable");
+                    sink.send(Message::Text(text)).await.map_err(ApiError::network)?;
+                    // the sink only accepts frames once the socket is open
+                    established.set(true);
+                }
+                message = stream.next() => match message {
+                    Some(Ok(Message::Text(text))) => {
+                        established.set(true);
+                        dispatch(&subs, &text);
+                    }
+                    Some(Ok(Message::Bytes(_))) => {
+                        established.set(true);
+                        log::warn!("ignoring binary websocket message");
+                    }
+                    Some(Err(err)) => return Err(ApiError::network(err)),
+                    None => return Err(ApiError::network("websocket closed by server")),
+                },
+            }
+        }
+    }
+    .await;
+
+    // dropping the receiver marks the mux as closed for new subscriptions
+    drop(outgoing_rx);
+
+    if let Err(err) = result {
+        for (_, sub) in subs.borrow_mut().drain() {
+            sub.exchange.record(Record::Error { message: err.to_string() });
+            _ = sub.tx.unbounded_send(Err(err.clone()));
+        }
+    }
+}
+
+fn dispatch(subs: &Subscribers, text: &str) {
+    let frame: ServerFrame = match serde_json::from_str(text) {
+        Ok(frame) => frame,
+        Err(err) => {
+            log::warn!("invalid websocket frame: {err:?}");
+            return;
+        }
+    };
+
+    let mut subs = subs.borrow_mut();
+    match frame {
+        ServerFrame::Event { id, event, event_id } => {
+            if let Some(sub) = subs.get(&id) {
+                sub.exchange.record(Record::Event {
+                    event: String::from("message"),
+                    data:  event.to_string(),
+                    id:    event_id.clone(),
+                    retry: None,
+                });
+                if let Some(event_id) = event_id {
+                    sub.resume.set((!event_id.is_empty()).then_some(event_id));
+                }
+                _ = sub.tx.unbounded_send(Ok(event));
+            }
+        }
+        ServerFrame::Error { id, status, message } => {
+            if let Some(sub) = subs.remove(&id) {
+                let status = status.unwrap_or(500);
+                sub.exchange.record(Record::Response { status, body: Some(message.clone()) });
+                let err = ApiError::from_status(&sub.path, status, message);
+                _ = sub.tx.unbounded_send(Err(err));
+            }
+        }
+        ServerFrame::End { id } => {
+            if let Some(sub) = subs.remove(&id) {
+                sub.exchange.record(Record::End);
+            }
+        }
+    }
+}
+
+/// Converts the HTTP API host and the advertised path to a WebSocket URL.
+pub fn ws_url(host: &str, path: &str) -> String {
+    let host = if let Some(rest) = host.strip_prefix("https://") {
+        format!("wss://{rest}")
+    } else if let Some(rest) = host.strip_prefix("http://") {
+        format!("ws://{rest}")
+    } else {
+        host.to_string()
+    };
+    format!("{host}/{}", path.trim_start_matches('/'))
+}
@@ -0,0 +1,90 @@
+//! Records the traffic of the [`Client`](super::Client) for later replay.
+//!
+//! See [`webconsole_protocol::session`] for the file format.
+
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+use futures::{Stream, StreamExt};
+use wasm_bindgen::JsCast;
+use webconsole_protocol::session::{Entry, Record};
+
+/// Keeps the session in memory until the user downloads it.
+pub struct Recorder {
+    session: RefCell<String>,
+    start:   f64,
+    next:    Cell<u64>,
+}
+
+impl Recorder {
+    pub fn new() -> Self {
+        Self {
+            session: RefCell::new(String::new()),
+            start:   js_sys::Date::now(),
+            next:    Cell::new(1),
+        }
+    }
+
+    /// The newline-delimited JSON entries recorded so far.
+    pub fn session(&self) -> String { self.session.borrow().clone() }
+
+    /// Lets the browser save the session recorded so far.
+    pub fn download(&self) -> anyhow::Result<()> {
+        let blob = gloo::file::Blob::new_with_options(
+            self.session().as_str(),
+            Some("application/x-ndjson"),
+        );
+        let url = gloo::file::ObjectUrl::from(blob);
+
+        let link = gloo::utils::document()
+            .create_element("a")
+            .map_err(super::fetch::js_error)?
+            .dyn_into::<web_sys::HtmlElement>()
+            .map_err(|_| anyhow::anyhow!("created element is not an HtmlElement"))?;
+        link.set_attribute("href", &url).map_err(super::fetch::js_error)?;
+        link.set_attribute("download", "webconsole-session.ndjson")
+            .map_err(super::fetch::js_error)?;
+        link.click();
+
+        // the download may start after the current task, so the URL must outlive it
+        gloo::timers::callback::Timeout::new(60_000, move || drop(url)).forget();
+        Ok(())
+    }
+
+    fn write(&self, entry: &Entry) {
+        let mut session = self.session.borrow_mut();
+        session.push_str(&serde_json::to_string(entry).expect("entries are serializable"));
+        session.push('\n');
+    }
+}
+
+/// The records of one request, which are dropped if the client is not recording.
+#[derive(Clone, Default)]
+pub struct Exchange(Option<(Rc<Recorder>, u64)>);
+
+impl Exchange {
+    pub fn start(recorder: Option<&Rc<Recorder>>, path: &str, last_event_id: Option<&str>) -> Self {
+        let Some(recorder) = recorder else { return Self(None) };
+        let id = recorder.next.get();
+        recorder.next.set(id + 1);
+        let exchange = Self(Some((recorder.clone(), id)));
+        exchange.record(Record::Request {
+            path:          path.to_string(),
+            last_event_id: last_event_id.map(str::to_string),
+        });
+        exchange
+    }
+
+    pub fn record(&self, record: Record) {
+        if let Some((recorder, exchange)) = &self.0 {
+            let at = js_sys::Date::now() - recorder.start;
+            recorder.write(&Entry { at, exchange: *exchange, record });
+        }
+    }
+
+    /// Records the end of `stream` when it is exhausted, but not if it is dropped before.
+    pub fn until_end<S: Stream>(self, stream: S) -> impl Stream<Item = S::Item> {
+        let end = futures::stream::once(async move { self.record(Record::End) });
+        stream.chain(end.filter_map(|()| async { None }))
+    }
+}
@@ -0,0 +1,141 @@
+//! Shares list watches among all components that display the same objects.
+//!
+//! Each distinct [`ListKey`] has at most one upstream watch and one materialised object map.
+//! Components hold an `Rc<ListSubscription>`;
+//! the upstream watch is closed when the last of them is dropped.
+
+use std::cell::{Cell, Ref, RefCell};
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
+use futures::channel::{mpsc, oneshot};
+use futures::stream::LocalBoxStream;
+use futures::StreamExt;
+use wasm_bindgen_futures::spawn_local;
+use webconsole_protocol::store::ObjectStore;
+
+use super::{ApiError, Client, Object, ResumeToken, Selection, WatchListEvent};
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ListKey {
+    pub group:     String,
+    pub kind:      String,
+    pub selection: Selection,
+}
+
+type Listener = mpsc::UnboundedSender<Result<WatchListEvent, ApiError>>;
+
+pub struct ListSubscription {
+    key:      ListKey,
+    client:   Rc<Client>,
+    shared:   Rc<Shared>,
+    shutdown: RefCell<Option<oneshot::Sender<()>>>,
+}
+
+#[derive(Default)]
+struct Shared {
+    store:     RefCell<ObjectStore>,
+    listeners: RefCell<Vec<Listener>>,
+    /// Whether the upstream watch is open, in which case `objects` is up to date.
+    running:   Cell<bool>,
+    resume:    ResumeToken,
+}
+
+impl ListSubscription {
+    pub(super) fn new(key: ListKey, client: Rc<Client>) -> Self {
+        Self { key, client, shared: Rc::default(), shutdown: RefCell::new(None) }
+    }
+
+    pub fn key(&self) -> &ListKey { &self.key }
+
+    /// The objects received so far, keyed by name.
+    pub fn objects(&self) -> Ref<'_, BTreeMap<String, Object>> {
+        Ref::map(self.shared.store.borrow(), ObjectStore::objects)
+    }
+
+    /// Listens to the changes of [`ListSubscription::objects`].
+    ///
+    /// If the upstream watch is already open, the stream starts with
+    /// [`WatchListEvent::Resumed`] since the shared objects are already up to date.
+    /// Otherwise the upstream watch is (re)opened and its events are forwarded as is.
+    /// The stream ends after an error if the upstream watch fails.
+    pub fn events(&self) -> LocalBoxStream<'static, Result<WatchListEvent, ApiError>> {
+        let (tx, rx) = mpsc::unbounded();
+
+        if self.shared.running.get() {
+            _ = tx.unbounded_send(Ok(WatchListEvent::Resumed));
+        } else {
+            self.start();
+        }
+        self.shared.listeners.borrow_mut().push(tx);
+
+        rx.boxed_local()
+    }
+
+    fn start(&self) {
+        let (shutdown_tx, mut shutdown_rx) = oneshot::channel::<()>();
+        if let Some(old) = self.shutdown.replace(Some(shutdown_tx)) {
+            _ = old.send(());
+        }
+        self.shared.running.set(true);
+
+        let watch = self.client.watch_list(
+            self.key.group.clone(),
+            self.key.kind.clone(),
+            self.key.selection.clone(),
+            self.shared.resume.clone(),
+        );
+        let shared = self.shared.clone();
+        spawn_local(async move {
+            let result = async {
+                let mut stream = watch.await?.fuse();
+                let mut last_err = None;
+
+                loop {
+                    let event = futures::select! {
+                        event = stream.next() => match event {
+                            Some(event) => event,
+                            None => break,
+                        },
+                        _ = shutdown_rx => return Ok(()),
+                    };
+                    if let Err(err) = &event {
+                        last_err = Some(err.clone());
+                    }
+                    shared.dispatch(event);
+                }
+
+                Err(last_err.unwrap_or_else(|| ApiError::network("stream closed by server")))
+            }
+            .await;
+
+            shared.running.set(false);
+            if let Err(err) = result {
+                // dropping the listeners ends their streams after the error
+                for listener in shared.listeners.take() {
+                    _ = listener.unbounded_send(Err(err.clone()));
+                }
+            }
+        });
+    }
+}
+
+impl Shared {
+    fn dispatch(&self, event: Result<WatchListEvent, ApiError>) {
+        if let Ok(event) = &event {
+            self.store.borrow_mut().apply(event);
+        }
+
+        self.listeners
+            .borrow_mut()
+            .retain(|listener| listener.unbounded_send(event.clone()).is_ok());
+    }
+}
+
+impl Drop for ListSubscription {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            _ = shutdown.send(());
+        }
+    }
+}
@@ -69,7 +69,7 @@ pub fn InlineDisplay(props: &Props) -> Html {
                         InlineDisplay(
                             i18n = props.i18n.clone(),
                             value = props.value.clone(),
-                            ty = (&**item).clone(),
+                            ty = (**item).clone(),
                             nested = false, // this is not visually nested, no need to compact the view.
                         );
                     }
@@ -98,7 +98,7 @@ pub fn InlineDisplay(props: &Props) -> Html {
                                     InlineDisplay(
                                         i18n = props.i18n.clone(),
                                         value = element.clone(),
-                                        ty = (&**item).clone(),
+                                        ty = (**item).clone(),
                                         nested = true,
                                     );
                                 }
@@ -155,7 +155,7 @@ pub fn InlineDisplay(props: &Props) -> Html {
                 match &props.value {
                     serde_json::Value::String(name) => {
                         span(class = "tag is-link is-light") {
-                            Link<Route>(to = Route::Info { group: gk.group.clone(), kind: gk.kind.clone(), name: name.into() }) {
+                            Link<Route>(to = Route::Info { group: (&gk.group).into(), kind: (&gk.kind).into(), name: name.into() }) {
                                 + name;
                             }
                         }
@@ -1,16 +1,21 @@
 use std::pin::Pin;
 use std::rc::Rc;
 
-use anyhow::{Context as _, Result};
 use defy::defy;
 use futures::channel::oneshot;
 use futures::stream::FusedStream;
 use futures::{Future, StreamExt};
+use gloo::timers::callback::{Interval, Timeout};
 use wasm_bindgen_futures::spawn_local;
 use yew::prelude::*;
 
+use crate::api::{self, ApiError};
+use crate::i18n::I18n;
+
 pub type NeedRender = bool;
 
+pub type Result<T, E = ApiError> = std::result::Result<T, E>;
+
 pub trait State: 'static {
     type Input: PartialEq + Clone;
     type Event;
@@ -19,51 +24,96 @@ pub trait State: 'static {
 
     fn reset(&mut self);
 
+    #[allow(clippy::type_complexity)]
     fn watch(
         &self,
         input: &Self::Input,
+        resume: &api::ResumeToken,
     ) -> Pin<
         Box<dyn Future<Output = Result<Box<dyn FusedStream<Item = Result<Self::Event>> + Unpin>>>>,
     >;
 
     fn update(&mut self, msg: Self::Event) -> NeedRender;
 
+    /// Whether the event indicates that a reconnected stream continues from where it was,
+    /// in which case the state is not reset.
+    fn is_resume(_event: &Self::Event) -> bool { false }
+
     type Deps<'t>: PartialEq + 't;
-    fn deps<'t>(input: &'t Self::Input) -> Self::Deps<'t>;
+    fn deps(input: &Self::Input) -> Self::Deps<'_>;
 }
 
 pub struct Comp<T: State> {
     state:       T,
-    err:         Option<String>,
+    err:         Option<ApiError>,
     shutdown_tx: Option<oneshot::Sender<()>>,
+    resume:      api::ResumeToken,
+    /// Number of consecutive failed connection attempts.
+    attempts:    u32,
+    reconnect:   Option<Reconnect>,
+}
+
+struct Reconnect {
+    err:      ApiError,
+    /// `Date.now()` at which the next attempt is made.
+    deadline: f64,
+    _timeout: Timeout,
+    _ticker:  Interval,
+}
+
+const BACKOFF_BASE_MILLIS: f64 = 1000.;
+const BACKOFF_MAX_MILLIS: f64 = 30000.;
+
+/// Exponential backoff with equal jitter, i.e. a random delay between half and all of the backoff.
+///
+/// The backoff starts from the reconnection time requested by the server with `retry:`, if any.
+/// `jitter` is a random number in `[0, 1)`.
+fn backoff_millis(attempts: u32, resume: &api::ResumeToken, jitter: f64) -> f64 {
+    let base = resume.retry().map_or(BACKOFF_BASE_MILLIS, |retry| retry.as_millis() as f64);
+    let backoff = (base * 2f64.powi(attempts.min(16) as i32)).min(BACKOFF_MAX_MILLIS);
+    backoff * (0.5 + jitter * 0.5)
 }
 
 fn start_watch<T: State>(ctx: &Context<Comp<T>>, comp: &mut Comp<T>) {
     let (shutdown_tx, mut shutdown_rx) = oneshot::channel::<()>();
 
-    let callback = ctx.link().callback(|event| Msg::Event(event));
+    let link = ctx.link().clone();
 
-    let watch = comp.state.watch(&ctx.props().input);
+    let watch = comp.state.watch(&ctx.props().input, &comp.resume);
     spawn_local({
         async move {
-            match async {
-                let mut stream = watch.await.context("open watch stream")?;
+            let result = async {
+                let mut stream = watch.await?;
+                let mut connected = false;
+                let mut last_err = None;
 
                 loop {
-                    let event: anyhow::Result<T::Event> = futures::select! {
+                    let event: Result<T::Event> = futures::select! {
                         event = stream.next() => match event {
                             Some(event) => event,
-                            None => return Ok(()),
+                            None => break,
                         },
                         _ = shutdown_rx => return Ok(()),
                     };
-                    callback.emit(event);
+                    match event {
+                        Ok(event) if !connected => {
+                            connected = true;
+                            link.send_message(Msg::Connected(event));
+                        }
+                        Ok(event) => link.send_message(Msg::Event(Ok(event))),
+                        Err(err) => {
+                            last_err = Some(err.clone());
+                            link.send_message(Msg::Event(Err(err)));
+                        }
+                    }
                 }
+
+                Err(last_err.unwrap_or_else(|| ApiError::network("stream closed by server")))
             }
-            .await
-            {
-                Ok(()) => (),
-                Err(err) => callback.emit(Err(err)),
+            .await;
+
+            if let Err(err) = result {
+                link.send_message(Msg::Disconnected(err));
             }
         }
     });
@@ -71,8 +121,29 @@ fn start_watch<T: State>(ctx: &Context<Comp<T>>, comp: &mut Comp<T>) {
     if let Some(old) = comp.shutdown_tx.replace(shutdown_tx) {
         _ = old.send(());
     }
+}
+
+impl<T: State> Comp<T> {
+    fn schedule_reconnect(&mut self, ctx: &Context<Self>, err: ApiError) {
+        let delay = backoff_millis(self.attempts, &self.resume, js_sys::Math::random());
+        self.attempts = self.attempts.saturating_add(1);
+
+        let timeout = Timeout::new(delay as u32, {
+            let link = ctx.link().clone();
+            move || link.send_message(Msg::Reconnect)
+        });
+        let ticker = Interval::new(1000, {
+            let link = ctx.link().clone();
+            move || link.send_message(Msg::Tick)
+        });
 
-    comp.state.reset();
+        self.reconnect = Some(Reconnect {
+            err,
+            deadline: js_sys::Date::now() + delay,
+            _timeout: timeout,
+            _ticker: ticker,
+        });
+    }
 }
 
 impl<T: State> Component for Comp<T> {
@@ -80,8 +151,14 @@ impl<T: State> Component for Comp<T> {
     type Properties = Props<T>;
 
     fn create(ctx: &Context<Self>) -> Self {
-        let mut obj =
-            Self { state: T::new(&ctx.props().input), err: None, shutdown_tx: None };
+        let mut obj = Self {
+            state:       T::new(&ctx.props().input),
+            err:         None,
+            shutdown_tx: None,
+            resume:      api::ResumeToken::default(),
+            attempts:    0,
+            reconnect:   None,
+        };
         start_watch(ctx, &mut obj);
         obj
     }
@@ -92,18 +169,47 @@ impl<T: State> Component for Comp<T> {
         }
     }
 
-    fn update(&mut self, _ctx: &Context<Self>, msg: Self::Message) -> bool {
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
         match msg {
+            Msg::Connected(event) => {
+                // keep displaying the stale state until the new stream is ready
+                if !T::is_resume(&event) {
+                    self.state.reset();
+                }
+                self.attempts = 0;
+                self.err = None;
+                self.state.update(event);
+                true
+            }
             Msg::Event(Ok(event)) => self.state.update(event),
             Msg::Event(Err(err)) => {
-                self.err = Some(format!("{err:?}"));
+                self.err = Some(err);
+                true
+            }
+            Msg::Disconnected(err) if err.is_transient() => {
+                self.schedule_reconnect(ctx, err);
+                true
+            }
+            Msg::Disconnected(err) => {
+                self.err = Some(err);
                 true
             }
+            Msg::Reconnect => {
+                self.reconnect = None;
+                start_watch(ctx, self);
+                true
+            }
+            Msg::Tick => self.reconnect.is_some(),
         }
     }
 
     fn changed(&mut self, ctx: &Context<Self>, old_props: &Self::Properties) -> bool {
         if T::deps(&old_props.input) != T::deps(&ctx.props().input) {
+            self.resume = api::ResumeToken::default();
+            self.attempts = 0;
+            self.reconnect = None;
+            self.err = None;
+            self.state.reset();
             start_watch(ctx, self);
         }
 
@@ -112,16 +218,41 @@ impl<T: State> Component for Comp<T> {
 
     fn view(&self, ctx: &Context<Self>) -> Html {
         defy! {
-            if let Some(err) = &self.err {
-                article(class = "message is-danger") {
+            if let Some(reconnect) = &self.reconnect {
+                let secs = ((reconnect.deadline - js_sys::Date::now()) / 1000.).ceil().max(0.);
+                article(class = "message is-warning") {
                     div(class = "message-header") {
                         p {
-                            + "Error";
+                            + format!("Disconnected, reconnecting in {secs}s");
+                        }
+                        button(
+                            class = "button is-small",
+                            onclick = ctx.link().callback(|_| Msg::Reconnect),
+                        ) {
+                            + "Retry now";
                         }
                     }
                     div(class = "message-body") {
+                        p {
+                            + describe(ctx.props().i18n.as_ref(), &reconnect.err);
+                        }
                         pre {
-                            + err;
+                            + reconnect.err.to_string();
+                        }
+                    }
+                }
+            } else {
+                if let Some(err) = &self.err {
+                    article(class = "message is-danger") {
+                        div(class = "message-header") {
+                            p {
+                                + describe(ctx.props().i18n.as_ref(), err);
+                            }
+                        }
+                        div(class = "message-body") {
+                            pre {
+                                + err.to_string();
+                            }
                         }
                     }
                 }
@@ -132,18 +263,56 @@ impl<T: State> Component for Comp<T> {
     }
 }
 
+fn describe(i18n: Option<&I18n>, err: &ApiError) -> String {
+    i18n.map_or_else(|| String::from(err.summary()), |i18n| i18n.disp(err.i18n_key()))
+}
+
 pub enum Msg<T: State> {
-    Event(anyhow::Result<T::Event>),
+    /// The first event of a (re)opened stream.
+    Connected(T::Event),
+    Event(Result<T::Event>),
+    Disconnected(ApiError),
+    Reconnect,
+    Tick,
 }
 
 #[derive(Clone, Properties)]
 pub struct Props<T: State> {
     pub input: T::Input,
     pub body:  Rc<dyn Fn(&T) -> Html>,
+    /// Translates the errors, which are summarized in English until it is available.
+    #[prop_or_default]
+    pub i18n:  Option<I18n>,
 }
 
 impl<T: State> PartialEq for Props<T> {
     fn eq(&self, other: &Self) -> bool {
-        self.input == other.input && Rc::ptr_eq(&self.body, &other.body)
+        self.input == other.input
+            && self.i18n == other.i18n
+            && std::ptr::eq(
+                Rc::as_ptr(&self.body).cast::<()>(),
+                Rc::as_ptr(&other.body).cast::<()>(),
+            )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use webconsole_protocol::sse;
+
+    use super::*;
+
+    #[test]
+    fn backoff_starts_from_server_retry() {
+        let resume = api::ResumeToken::default();
+        assert_eq!(backoff_millis(0, &resume, 1.), 1000.);
+        assert_eq!(backoff_millis(2, &resume, 0.), 2000.);
+
+        for event in sse::Parser::default().feed(b"retry: 5000\ndata: {}\n\n") {
+            resume.observe(&event);
+        }
+        assert_eq!(backoff_millis(0, &resume, 1.), 5000.);
+        assert_eq!(backoff_millis(1, &resume, 0.), 5000.);
+        assert_eq!(backoff_millis(3, &resume, 1.), BACKOFF_MAX_MILLIS);
     }
 }
@@ -1,8 +1,6 @@
 use fluent::{fluent_args, FluentArgs, FluentBundle, FluentResource};
 
-use crate::util::{Grc, RcStr};
-
-pub type Key = RcStr;
+use crate::util::Grc;
 
 #[derive(Clone, PartialEq)]
 pub struct I18n {
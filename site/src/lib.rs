@@ -15,22 +15,62 @@ mod util;
 
 #[function_component]
 pub fn App() -> Html {
-    let user_host_state = use_state(|| api::infer_host());
-    let user_host = (&*user_host_state).clone();
+    let user_host_state = use_state(api::infer_host);
+    let user_host = (*user_host_state).clone();
+    let credentials_state = use_state(|| api::load_credentials(&user_host));
+    let credentials = (*credentials_state).clone();
+    // the error that requires the user to log in
+    let login_state = use_state(|| None::<api::ApiError>);
 
-    let set_user_host = Callback::from(move |host: RcStr| {
-        if let Err(err) = gloo::storage::LocalStorage::set(api::LOCAL_STORAGE_KEY, host.to_string())
-        {
-            log::error!("store host: {err:?}");
-        }
-        user_host_state.set(host);
-    });
+    let set_user_host = {
+        let credentials_state = credentials_state.clone();
+        let login_state = login_state.clone();
+        Callback::from(move |host: RcStr| {
+            if let Err(err) =
+                gloo::storage::LocalStorage::set(api::LOCAL_STORAGE_KEY, host.to_string())
+            {
+                log::error!("store host: {err:?}");
+            }
+            credentials_state.set(api::load_credentials(&host));
+            login_state.set(None);
+            user_host_state.set(host);
+        })
+    };
 
     log::debug!("user_host = {user_host:?}");
 
+    if let Some(err) = &*login_state {
+        let on_login = {
+            let host = user_host.clone();
+            let login_state = login_state.clone();
+            Callback::from(move |new: api::Credentials| {
+                api::store_credentials(&host, Some(&new));
+                credentials_state.set(Some(new));
+                login_state.set(None);
+            })
+        };
+
+        return defy! {
+            pages::login::Comp(
+                host = user_host,
+                credentials = credentials,
+                err = Some(err.clone()),
+                on_login = on_login,
+                set_user_host = set_user_host,
+            );
+        };
+    }
+
+    let on_unauthorized = Callback::from(move |err| login_state.set(Some(err)));
+
     defy! {
         Suspense(fallback = fallback(user_host.clone(), set_user_host.clone())) {
-            Main(host = user_host, set_user_host = set_user_host);
+            Main(
+                host = user_host,
+                credentials = credentials,
+                set_user_host = set_user_host,
+                on_unauthorized = on_unauthorized,
+            );
         }
     }
 }
@@ -43,8 +83,12 @@ fn Main(props: &MainProps) -> HtmlResult {
         x
     });
 
-    let api = api::use_client(props.host.clone());
-    let queries: UseFutureHandle<anyhow::Result<_>> = use_future_with_deps(
+    let api = api::use_client(
+        props.host.clone(),
+        props.credentials.clone(),
+        props.on_unauthorized.clone(),
+    );
+    let queries: UseFutureHandle<Result<_, api::ApiError>> = use_future_with_deps(
         |_| {
             let api = api.clone();
             async move {
@@ -52,14 +96,14 @@ fn Main(props: &MainProps) -> HtmlResult {
                 Ok((locales?, discovery?))
             }
         },
-        api.host.clone(),
+        (api.host.clone(), props.credentials.clone()),
     )?;
     let (i18n, discovery) = match &*queries {
         Ok(data) => data.clone(),
         Err(err) => {
             return Ok(defy! {
                 pages::error::Error(
-                    err = format!("{err:?}"),
+                    err = err.clone(),
                     set_user_host = Some((api.host.clone(), set_user_host)),
                 );
             })
@@ -75,7 +119,7 @@ fn Main(props: &MainProps) -> HtmlResult {
                 aside(
                     class = classes!(
                         "column", "is-narrow", "is-fullheight", "section", "menu", "main-sidebar",
-                        (!*disp_nav_touch).then(|| "is-hidden-touch")),
+                        (!*disp_nav_touch).then_some("is-hidden-touch")),
                     ref = nav_node.clone(),
                 ){
                     nav::Comp(
@@ -104,8 +148,11 @@ fn Main(props: &MainProps) -> HtmlResult {
 
 #[derive(Clone, PartialEq, Properties)]
 struct MainProps {
-    host:          RcStr,
-    set_user_host: Callback<RcStr>,
+    host:            RcStr,
+    credentials:     Option<api::Credentials>,
+    set_user_host:   Callback<RcStr>,
+    /// Shows the login screen when the server rejects the credentials.
+    on_unauthorized: Callback<api::ApiError>,
 }
 
 fn fallback(host: RcStr, set_user_host: Callback<RcStr>) -> Html {
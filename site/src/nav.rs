@@ -39,6 +39,25 @@ pub fn Comp(props: &Props) -> HtmlResult {
             );
         }
 
+        if let Some(recorder) = props.api.recorder() {
+            div(class = "container mt-2") {
+                button(
+                    class = "button is-small is-fullwidth",
+                    onclick = {
+                        let recorder = recorder.clone();
+                        Callback::from(move |_| {
+                            if let Err(err) = recorder.download() {
+                                log::error!("download recording: {err:?}");
+                            }
+                        })
+                    },
+                ) {
+                    span(class = "icon mdi mdi-record-rec");
+                    span { + props.i18n.disp("base-nav-download-recording"); }
+                }
+            }
+        }
+
         hr;
 
         ul(class = "menu-list") {
@@ -56,8 +75,8 @@ pub fn Comp(props: &Props) -> HtmlResult {
                 for api in apis {
                     li {
                         Link<Route>(to = Route::List{
-                            group: api.id.group.clone(),
-                            kind: api.id.kind.clone(),
+                            group: (&api.id.group).into(),
+                            kind: (&api.id.kind).into(),
                         }) {
                             + props.i18n.disp(&api.display_name);
                         }
@@ -2,3 +2,4 @@ pub mod error;
 pub mod home;
 pub mod info;
 pub mod list;
+pub mod login;
@@ -1,9 +1,9 @@
 use defy::defy;
 use yew::prelude::*;
 
-use crate::comps;
 use crate::i18n::I18n;
 use crate::util::RcStr;
+use crate::{api, comps};
 
 #[function_component]
 pub fn Error(props: &Props) -> Html {
@@ -20,27 +20,21 @@ pub fn Error(props: &Props) -> Html {
                         }
                     }
                     div(class = "message-body") {
+                        p {
+                            + props.i18n.as_ref().map_or_else(
+                                || String::from(props.err.summary()),
+                                |i18n| i18n.disp(props.err.i18n_key()),
+                            );
+                        }
                         pre {
-                            + &props.err;
+                            + props.err.to_string();
                         }
                     }
                 }
             }
         }
         if let Some((host, callback)) = &props.set_user_host {
-            div(class = "section") {
-                div(class = "container") {
-                    comps::TextButton(
-                        default_value = Some(host.to_istring()),
-                        button = props.i18n.as_ref().map_or_else(
-                            || String::from("Switch server"),
-                            |i18n| i18n.disp("base-nav-switch-server"),
-                        ),
-                        focused = true,
-                        callback = callback.reform(Into::into),
-                    );
-                }
-            }
+            SwitchServer(i18n = props.i18n.clone(), host = host.clone(), callback = callback.clone());
 
             div(class = "section") {
                 div(class = "container") {
@@ -58,14 +52,40 @@ pub fn Error(props: &Props) -> Html {
                     }
                 }
             }
+        }
+    }
+}
 
+/// Lets the user connect to another server.
+#[function_component]
+pub fn SwitchServer(props: &SwitchServerProps) -> Html {
+    defy! {
+        div(class = "section") {
+            div(class = "container") {
+                comps::TextButton(
+                    default_value = Some(props.host.to_istring()),
+                    button = props.i18n.as_ref().map_or_else(
+                        || String::from("Switch server"),
+                        |i18n| i18n.disp("base-nav-switch-server"),
+                    ),
+                    focused = true,
+                    callback = props.callback.reform(Into::into),
+                );
+            }
         }
     }
 }
 
+#[derive(PartialEq, Properties)]
+pub struct SwitchServerProps {
+    pub i18n:     Option<I18n>,
+    pub host:     RcStr,
+    pub callback: Callback<RcStr>,
+}
+
 #[derive(PartialEq, Properties)]
 pub struct Props {
     pub i18n:          Option<I18n>,
-    pub err:           String,
+    pub err:           api::ApiError,
     pub set_user_host: Option<(RcStr, Callback<RcStr>)>,
 }
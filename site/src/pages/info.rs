@@ -2,10 +2,12 @@ use defy::defy;
 use yew::prelude::*;
 
 use crate::api;
+use crate::comps::{SelectButtonGroup, SelectButtons};
 use crate::i18n::I18n;
 use crate::util::Grc;
 
 mod loader;
+mod snapshot;
 
 #[function_component]
 pub fn Comp(props: &Props) -> Html {
@@ -29,19 +31,94 @@ pub fn Comp(props: &Props) -> Html {
         (props.group.clone(), props.kind.clone(), props.name.clone()),
     );
 
+    let mode = use_state(LoadMode::default);
+    let generation = use_state(|| 0u32);
+
     defy! {
         h1(class = "title") {
             + format!("{gk_display_name} {}", props.name);
         }
 
-        loader::Comp(
-            api = props.api.clone(),
-            i18n = props.i18n.clone(),
-            def = def.clone(),
-            group = props.group.clone(),
-            kind = props.kind.clone(),
-            name = props.name.clone(),
-        );
+        div(class = "level") {
+            div(class = "level-left") {
+                div(class = "level-item") {
+                    SelectButtonGroup<LoadMode>(
+                        i18n = props.i18n.clone(),
+                        value = *mode,
+                        callback = Callback::from({
+                            let mode = mode.clone();
+                            move |value| mode.set(value)
+                        }),
+                    );
+                }
+                if *mode == LoadMode::Snapshot {
+                    div(class = "level-item") {
+                        button(
+                            class = "button",
+                            onclick = Callback::from({
+                                let generation = generation.clone();
+                                move |_| generation.set(*generation + 1)
+                            }),
+                        ) {
+                            span(class = "icon mdi mdi-refresh");
+                            span { + props.i18n.disp("base-info-refresh"); }
+                        }
+                    }
+                }
+            }
+        }
+
+        match *mode {
+            LoadMode::Live => {
+                loader::Comp(
+                    api = props.api.clone(),
+                    i18n = props.i18n.clone(),
+                    def = def.clone(),
+                    group = props.group.clone(),
+                    kind = props.kind.clone(),
+                    name = props.name.clone(),
+                );
+            }
+            LoadMode::Snapshot => {
+                Suspense(fallback = defy! { + "Loading"; }) {
+                    snapshot::Comp(
+                        api = props.api.clone(),
+                        i18n = props.i18n.clone(),
+                        def = def.clone(),
+                        group = props.group.clone(),
+                        kind = props.kind.clone(),
+                        name = props.name.clone(),
+                        generation = *generation,
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// Whether the object is displayed with live updates or as a one-off snapshot.
+#[derive(Clone, Copy, Default, PartialEq)]
+enum LoadMode {
+    #[default]
+    Live,
+    Snapshot,
+}
+
+impl SelectButtons for LoadMode {
+    fn variants() -> &'static [Self] { &[Self::Live, Self::Snapshot] }
+
+    fn icon(&self) -> &'static str {
+        match self {
+            Self::Live => "mdi-access-point",
+            Self::Snapshot => "mdi-camera",
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Live => "base-info-live",
+            Self::Snapshot => "base-info-snapshot",
+        }
     }
 }
 
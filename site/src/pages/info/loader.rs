@@ -3,20 +3,18 @@ use std::collections::HashMap;
 use std::pin::Pin;
 use std::rc::Rc;
 
-use anyhow::Result;
 use defy::defy;
 use futures::stream::FusedStream;
 use futures::{Future, StreamExt};
-use wasm_bindgen::JsValue;
 use yew::prelude::*;
 
 use crate::api;
-use crate::comps::watch_loader;
+use crate::comps::watch_loader::{self, Result};
 use crate::i18n::I18n;
-use crate::util::{Grc, RcStr};
+use crate::util::Grc;
 
 pub struct State {
-    fields: HashMap<RcStr, serde_json::Value>,
+    fields: HashMap<String, serde_json::Value>,
 }
 
 impl watch_loader::State for State {
@@ -25,12 +23,9 @@ impl watch_loader::State for State {
 
     fn reset(&mut self) { self.fields.clear(); }
 
-    type Deps<'t> = api::GroupKind;
-    fn deps<'t>(input: &'t Self::Input) -> Self::Deps<'t> {
-        api::GroupKind {
-            group: RcStr::from(input.group.clone()),
-            kind:  RcStr::from(input.kind.clone()),
-        }
+    type Deps<'t> = api::GroupKindRef<'t>;
+    fn deps(input: &Self::Input) -> Self::Deps<'_> {
+        api::GroupKindRef { group: input.group.as_str(), kind: input.kind.as_str() }
     }
 
     type Event = api::WatchSingleEvent;
@@ -40,21 +35,26 @@ impl watch_loader::State for State {
                 self.fields.insert(field, value);
                 true
             }
+            api::WatchSingleEvent::Resumed => false,
         }
     }
 
+    fn is_resume(event: &Self::Event) -> bool { matches!(event, api::WatchSingleEvent::Resumed) }
+
     fn watch(
         &self,
         props: &Props,
+        resume: &api::ResumeToken,
     ) -> Pin<
         Box<dyn Future<Output = Result<Box<dyn FusedStream<Item = Result<Self::Event>> + Unpin>>>>,
     > {
         let api = props.api.clone();
+        let resume = resume.clone();
         let group = props.group.to_string();
         let kind = props.kind.to_string();
         let name = props.name.to_string();
         Box::pin(async move {
-            let stream = api.watch_single(group, kind, name).await?;
+            let stream = api.watch_single(group, kind, name, None, resume).await?;
             Ok(Box::new(stream.fuse()) as Box<dyn FusedStream<Item = _> + Unpin + 'static>)
         })
     }
@@ -66,59 +66,65 @@ pub fn Comp(props: &Props) -> Html {
         let props = props.clone();
 
         move |state: &State| {
-            let def = &props.def;
-            let i18n = &props.i18n;
-
-            let fields = {
-                let mut fields = def.fields.values().collect::<Vec<_>>();
-                fields.sort_by_key(|field| {
-                    (cmp::Reverse(field.metadata.display_priority), &field.path)
-                });
-                fields
-            };
-
-            defy! {
-                table(class = "table is-hidden-touch") {
-                    for &field in &fields {
-                        tbody {
-                            tr {
-                                th {
-                                    + i18n.disp(&field.display_name);
-                                }
-                                td {
-                                    + display_object(field, state.fields.get(&field.path).unwrap_or(&serde_json::Value::Null)).1;
-                                }
-                            }
-                        }
-                    }
-                }
-                div(class = "is-hidden-desktop") {
-                    for &field in &fields {
-                        div(class = "has-text-weight-bold") { +i18n.disp(&field.display_name); }
-                        div(class = "pl-1") {
-                            div(class = "container") {
-                                + display_object(field, state.fields.get(&field.path).unwrap_or(&serde_json::Value::Null)).1;
-                            }
-                        }
-                    }
-                }
-            }
+            display_fields(&props.def, &props.i18n, |field| {
+                state.fields.get(&field.path).unwrap_or(&serde_json::Value::Null)
+            })
         }
     };
 
     defy! {
         watch_loader::Comp<State>(
             input = props.clone(),
+            i18n = Some(props.i18n.clone()),
             body = Rc::new(closure) as Rc<dyn Fn(&State) -> Html>,
         );
     }
 }
 
+/// Renders the fields of an object, with the value of each field looked up by `value_of`.
+pub fn display_fields<'t>(
+    def: &api::ObjectDef,
+    i18n: &I18n,
+    value_of: impl Fn(&api::FieldDef) -> &'t serde_json::Value,
+) -> Html {
+    let fields = {
+        let mut fields = def.fields.values().collect::<Vec<_>>();
+        fields.sort_by_key(|field| (cmp::Reverse(field.metadata.display_priority), &field.path));
+        fields
+    };
+
+    defy! {
+        table(class = "table is-hidden-touch") {
+            for &field in &fields {
+                tbody {
+                    tr {
+                        th {
+                            + i18n.disp(&field.display_name);
+                        }
+                        td {
+                            + display_object(field, value_of(field)).1;
+                        }
+                    }
+                }
+            }
+        }
+        div(class = "is-hidden-desktop") {
+            for &field in &fields {
+                div(class = "has-text-weight-bold") { +i18n.disp(&field.display_name); }
+                div(class = "pl-1") {
+                    div(class = "container") {
+                        + display_object(field, value_of(field)).1;
+                    }
+                }
+            }
+        }
+    }
+}
+
 #[derive(PartialEq, PartialOrd)]
 enum SizeClass {
     Inline,
     Long,
-    Structural,
 }
 
 fn display_object(field: &api::FieldDef, value: &serde_json::Value) -> (SizeClass, Html) {
@@ -141,7 +147,6 @@ fn display_object(field: &api::FieldDef, value: &serde_json::Value) -> (SizeClas
             };
             if *is_timestamp {
                 let Some(timestamp) = number.as_f64() else { return invalid_type("Number", value) };
-                let time = js_sys::Date::new(&JsValue::from_f64(timestamp));
                 let date = js_sys::Date::new(&wasm_bindgen::JsValue::from_f64(timestamp / 1000.0));
                 let time = format!(
                     "{:0>2}:{:0>2}:{:0>2}",
@@ -156,12 +161,12 @@ fn display_object(field: &api::FieldDef, value: &serde_json::Value) -> (SizeClas
                     }},
                 )
             } else {
-                let minmax = match &field.ty {
-                    &api::FieldType::Int64 { min: Some(min), max: Some(max), .. } => {
+                let minmax = match field.ty {
+                    api::FieldType::Int64 { min: Some(min), max: Some(max), .. } => {
                         let Some(value) = number.as_i64() else { return invalid_type("Number", value) };
                         Some(((value - min) as f64) / ((max - min) as f64))
                     }
-                    &api::FieldType::Float64 { min: Some(min), max: Some(max), .. } => {
+                    api::FieldType::Float64 { min: Some(min), max: Some(max), .. } => {
                         let Some(value) = number.as_f64() else { return invalid_type("Number", value) };
                         Some((value - min) / (max - min))
                     }
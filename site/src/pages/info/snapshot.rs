@@ -0,0 +1,47 @@
+use std::rc::Rc;
+
+use defy::defy;
+use yew::prelude::*;
+use yew::suspense::use_future_with_deps;
+
+use super::loader;
+use crate::api;
+use crate::i18n::I18n;
+use crate::pages::error::Error;
+use crate::util::{self, Grc};
+
+/// Displays the object as fetched once, without subscribing to updates.
+#[function_component]
+pub fn Comp(props: &Props) -> HtmlResult {
+    let object = use_future_with_deps(
+        {
+            let api = props.api.clone();
+            move |deps: Rc<(AttrValue, AttrValue, AttrValue, u32)>| {
+                let (group, kind, name, _) = &*deps;
+                api.get(group.to_string(), kind.to_string(), name.to_string(), None)
+            }
+        },
+        (props.group.clone(), props.kind.clone(), props.name.clone(), props.generation),
+    )?;
+
+    Ok(match &*object {
+        Ok(object) => loader::display_fields(&props.def, &props.i18n, |field| {
+            util::get_json_path(&object.fields, &field.path).unwrap_or(&serde_json::Value::Null)
+        }),
+        Err(err) => defy! {
+            Error(i18n = Some(props.i18n.clone()), err = err.clone(), set_user_host = None);
+        },
+    })
+}
+
+#[derive(Clone, PartialEq, Properties)]
+pub struct Props {
+    pub api:        Grc<api::Client>,
+    pub i18n:       I18n,
+    pub def:        api::ObjectDef,
+    pub group:      AttrValue,
+    pub kind:       AttrValue,
+    pub name:       AttrValue,
+    /// Incremented to fetch the object again.
+    pub generation: u32,
+}
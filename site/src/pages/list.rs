@@ -1,13 +1,13 @@
 use std::collections::HashSet;
 
 use defy::defy;
-use serde::Deserialize;
 use yew::prelude::*;
 
 use crate::api;
+use crate::api::DisplayMode;
 use crate::comps::SelectButtons;
 use crate::i18n::I18n;
-use crate::util::{Grc, RcStr};
+use crate::util::Grc;
 
 mod field_selector;
 mod object_list;
@@ -26,11 +26,8 @@ pub fn Comp(props: &Props) -> Html {
 
     let list_display_name = props.i18n.disp(&api.display_name);
     use_effect_with_deps(
-        {
-            let list_display_name = format!("{list_display_name}");
-            move |_| {
-                gloo::utils::document().set_title(&list_display_name);
-            }
+        move |_| {
+            gloo::utils::document().set_title(&list_display_name);
         },
         (props.group.clone(), props.kind.clone()),
     );
@@ -67,7 +64,7 @@ pub fn Comp(props: &Props) -> Html {
                     set_display_mode_callback = Callback::from({
                         let display_state = display_state.clone();
                         move |mode| {
-                            let mut display: DisplayState = (&*display_state).clone();
+                            let mut display: DisplayState = (*display_state).clone();
                             display.mode = mode;
                             display_state.set(display);
                         }
@@ -76,7 +73,7 @@ pub fn Comp(props: &Props) -> Html {
                         let display_state = display_state.clone();
 
                         move |(field_path, visible)| {
-                            let mut display: DisplayState = (&*display_state).clone();
+                            let mut display: DisplayState = (*display_state).clone();
 
                             if visible {
                                 display.hidden.remove(&field_path);
@@ -125,7 +122,7 @@ fn fallback() -> Html {
 #[derive(Clone, Default, PartialEq)]
 pub struct DisplayState {
     pub mode:   DisplayMode,
-    pub hidden: HashSet<RcStr>,
+    pub hidden: HashSet<String>,
     pub dep:    Option<api::ObjectDef>,
 }
 
@@ -142,14 +139,6 @@ impl DisplayState {
     }
 }
 
-#[derive(Clone, Copy, Default, PartialEq, Deserialize)]
-#[serde(rename_all = "kebab-case")]
-pub enum DisplayMode {
-    #[default]
-    Cards,
-    Table,
-}
-
 impl SelectButtons for DisplayMode {
     fn variants() -> &'static [Self] { &[Self::Cards, Self::Table] }
 
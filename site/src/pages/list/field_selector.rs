@@ -7,7 +7,6 @@ use super::{panel_block, DisplayMode, DisplayState};
 use crate::api;
 use crate::comps::SelectButtonGroup;
 use crate::i18n::I18n;
-use crate::util::RcStr;
 
 #[function_component]
 pub fn FieldSelector(props: &FieldSelectorProps) -> Html {
@@ -85,5 +84,5 @@ pub struct FieldSelectorProps {
     pub def:                       api::ObjectDef,
     pub display:                   DisplayState,
     pub set_display_mode_callback: Callback<DisplayMode>,
-    pub set_visible_callback:      Callback<(RcStr, bool)>,
+    pub set_visible_callback:      Callback<(String, bool)>,
 }
@@ -1,9 +1,9 @@
+use std::cell::RefCell;
 use std::cmp;
 use std::collections::{BTreeMap, HashSet};
 use std::pin::Pin;
 use std::rc::Rc;
 
-use anyhow::Result;
 use defy::defy;
 use futures::stream::FusedStream;
 use futures::{Future, StreamExt};
@@ -11,65 +11,51 @@ use yew::prelude::*;
 use yew_router::prelude::*;
 
 use super::DisplayMode;
-use crate::comps::watch_loader;
+use crate::comps::watch_loader::{self, Result};
 use crate::i18n::I18n;
-use crate::util::{self, Grc, RcStr};
+use crate::util::{self, Grc};
 use crate::{api, comps, Route};
 
+/// Displays the objects of a list subscription shared with other components.
 pub struct ObjectStore {
-    objects: BTreeMap<String, api::Object>,
+    sub: RefCell<Rc<api::ListSubscription>>,
 }
 
 impl watch_loader::State for ObjectStore {
     type Input = ObjectListProps;
-    fn new(_input: &Self::Input) -> Self { Self { objects: BTreeMap::new() } }
+    fn new(input: &Self::Input) -> Self { Self { sub: RefCell::new(input.subscribe()) } }
 
-    fn reset(&mut self) { self.objects.clear(); }
+    /// The shared objects are reset by the subscription when the server clears the list.
+    fn reset(&mut self) {}
 
-    type Deps<'t> = api::GroupKind;
-    fn deps<'t>(input: &'t Self::Input) -> Self::Deps<'t> {
-        api::GroupKind {
-            group: RcStr::from(input.group.clone()),
-            kind:  RcStr::from(input.kind.clone()),
-        }
+    /// Resubscribes when the visible fields change so that hidden fields are not watched.
+    type Deps<'t> = (api::GroupKindRef<'t>, Vec<String>);
+    fn deps(input: &Self::Input) -> Self::Deps<'_> {
+        let group_kind =
+            api::GroupKindRef { group: input.group.as_str(), kind: input.kind.as_str() };
+        (group_kind, input.visible_fields())
     }
 
     type Event = api::WatchListEvent;
     fn update(&mut self, msg: Self::Event) -> watch_loader::NeedRender {
-        match msg {
-            api::WatchListEvent::Clear => {
-                self.objects.clear();
-                true
-            }
-            api::WatchListEvent::Added { item: object } => {
-                self.objects.insert(object.name.clone(), object);
-                true
-            }
-            api::WatchListEvent::Removed { name } => {
-                self.objects.remove(&*name);
-                true
-            }
-            api::WatchListEvent::FieldUpdate { name, field, value } => {
-                let Some(object) = self.objects.get_mut(&*name) else { return false };
-                if let Err(err) = util::set_json_path(&mut object.fields, &*field, value) {
-                    log::warn!("invalid json path: {err:?}");
-                }
-                true
-            }
-        }
+        !matches!(msg, api::WatchListEvent::Resumed)
     }
 
+    fn is_resume(event: &Self::Event) -> bool { matches!(event, api::WatchListEvent::Resumed) }
+
+    /// The shared subscription resumes its own upstream watch,
+    /// so the resume token of the loader is not used.
     fn watch(
         &self,
         props: &ObjectListProps,
+        _resume: &api::ResumeToken,
     ) -> Pin<
         Box<dyn Future<Output = Result<Box<dyn FusedStream<Item = Result<Self::Event>> + Unpin>>>>,
     > {
-        let api = props.api.clone();
-        let group = props.group.to_string();
-        let kind = props.kind.to_string();
+        let sub = props.subscribe();
+        let stream = sub.events();
+        *self.sub.borrow_mut() = sub;
         Box::pin(async move {
-            let stream = api.watch_list(group, kind).await?;
             Ok(Box::new(stream.fuse()) as Box<dyn FusedStream<Item = _> + Unpin + 'static>)
         })
     }
@@ -102,31 +88,34 @@ pub fn ObjectList(props: &ObjectListProps) -> Html {
                 DisplayMode::Cards => display_cards,
                 DisplayMode::Table => display_table,
             };
-            display(state, def, &fields, i18n)
+            let sub = state.sub.borrow().clone();
+            let objects = sub.objects();
+            display(&objects, def, &fields, i18n)
         }
     };
 
     defy! {
         watch_loader::Comp<ObjectStore>(
             input = props.clone(),
+            i18n = Some(props.i18n.clone()),
             body = Rc::new(closure) as Rc<dyn Fn(&ObjectStore) -> Html>,
         );
     }
 }
 
 fn display_cards(
-    state: &ObjectStore,
+    objects: &BTreeMap<String, api::Object>,
     def: &api::ObjectDef,
     fields: &[&api::FieldDef],
     i18n: &I18n,
 ) -> Html {
     defy! {
         div {
-            for object in iter_map_order(&state.objects, def.metadata.desc_name) {
+            for object in iter_map_order(objects, def.metadata.desc_name) {
                 div(class = "card object-thumbnail") {
                     if !def.metadata.hide_name {
                         header(class = "card-header") {
-                            Link<Route>(to = Route::Info { group: def.id.group.clone(), kind: def.id.kind.clone(), name: (&object.name).into() }) {
+                            Link<Route>(to = Route::Info { group: (&def.id.group).into(), kind: (&def.id.kind).into(), name: (&object.name).into() }) {
                                 p(class = "card-header-title") {
                                     + &object.name;
                                 }
@@ -162,7 +151,7 @@ fn display_cards(
 }
 
 fn display_table(
-    state: &ObjectStore,
+    objects: &BTreeMap<String, api::Object>,
     def: &api::ObjectDef,
     fields: &[&api::FieldDef],
     i18n: &I18n,
@@ -181,10 +170,10 @@ fn display_table(
                 }
             }
             tbody {
-                for object in iter_map_order(&state.objects, def.metadata.desc_name) {
+                for object in iter_map_order(objects, def.metadata.desc_name) {
                     Link<Route>(
                         classes = "undecorate-hyperlink",
-                        to = Route::Info { group: def.id.group.clone(), kind: def.id.kind.clone(), name: (&object.name).into() },
+                        to = Route::Info { group: (&def.id.group).into(), kind: (&def.id.kind).into(), name: (&object.name).into() },
                     ) {
                         tr {
                             if !def.metadata.hide_name {
@@ -230,6 +219,26 @@ pub struct ObjectListProps {
     pub group:        AttrValue,
     pub kind:         AttrValue,
     pub def:          api::ObjectDef,
-    pub hidden:       HashSet<RcStr>,
+    pub hidden:       HashSet<String>,
     pub display_mode: DisplayMode,
 }
+
+impl ObjectListProps {
+    fn subscribe(&self) -> Rc<api::ListSubscription> {
+        let selection = api::Selection { fields: Some(self.visible_fields()), limit: None };
+        self.api.subscribe_list(self.group.to_string(), self.kind.to_string(), selection)
+    }
+
+    /// Paths of the fields not hidden by the field selector, in a stable order.
+    fn visible_fields(&self) -> Vec<String> {
+        let mut fields = self
+            .def
+            .fields
+            .values()
+            .filter(|field| !self.hidden.contains(&field.path))
+            .map(|field| field.path.to_string())
+            .collect::<Vec<_>>();
+        fields.sort();
+        fields
+    }
+}
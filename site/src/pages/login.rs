@@ -0,0 +1,150 @@
+use defy::defy;
+use yew::prelude::*;
+
+use super::error::SwitchServer;
+use crate::api;
+use crate::util::RcStr;
+
+/// Collects the credentials for an API server behind an authenticating proxy.
+#[function_component]
+pub fn Comp(props: &Props) -> Html {
+    let basic = use_state(|| matches!(props.credentials, Some(api::Credentials::Basic { .. })));
+
+    let token_node = use_node_ref();
+    let username_node = use_node_ref();
+    let password_node = use_node_ref();
+
+    let submit = {
+        let basic = *basic;
+        let token_node = token_node.clone();
+        let username_node = username_node.clone();
+        let password_node = password_node.clone();
+        let on_login = props.on_login.clone();
+        Callback::from(move |event: SubmitEvent| {
+            event.prevent_default();
+
+            let value = |node: &NodeRef| {
+                node.cast::<web_sys::HtmlInputElement>()
+                    .map(|input| input.value())
+                    .unwrap_or_default()
+            };
+            let credentials = if basic {
+                api::Credentials::Basic {
+                    username: value(&username_node),
+                    password: value(&password_node),
+                }
+            } else {
+                api::Credentials::Token { token: value(&token_node) }
+            };
+            on_login.emit(credentials);
+        })
+    };
+
+    let (default_token, default_username) = match &props.credentials {
+        Some(api::Credentials::Token { token }) => (Some(token.clone()), None),
+        Some(api::Credentials::Basic { username, .. }) => (None, Some(username.clone())),
+        None => (None, None),
+    };
+
+    defy! {
+        div(class = "section") {
+            div(class = "container") {
+                article(class = "message is-warning") {
+                    div(class = "message-header") {
+                        p { + "Login required"; }
+                    }
+                    div(class = "message-body") {
+                        p {
+                            + format!("{} requires credentials.", &props.host);
+                        }
+                        if let Some(err) = &props.err {
+                            pre { + err.to_string(); }
+                        }
+                    }
+                }
+
+                div(class = "tabs") {
+                    ul {
+                        li(class = classes!((!*basic).then_some("is-active"))) {
+                            a(onclick = {
+                                let basic = basic.clone();
+                                Callback::from(move |_| basic.set(false))
+                            }) {
+                                + "API token";
+                            }
+                        }
+                        li(class = classes!(basic.then_some("is-active"))) {
+                            a(onclick = {
+                                let basic = basic.clone();
+                                Callback::from(move |_| basic.set(true))
+                            }) {
+                                + "Username and password";
+                            }
+                        }
+                    }
+                }
+
+                form(onsubmit = submit) {
+                    if *basic {
+                        div(class = "field") {
+                            label(class = "label") { + "Username"; }
+                            div(class = "control") {
+                                input(
+                                    ref = username_node.clone(),
+                                    class = "input",
+                                    type = "text",
+                                    autocomplete = "username",
+                                    value = default_username,
+                                );
+                            }
+                        }
+                        div(class = "field") {
+                            label(class = "label") { + "Password"; }
+                            div(class = "control") {
+                                input(
+                                    ref = password_node.clone(),
+                                    class = "input",
+                                    type = "password",
+                                    autocomplete = "current-password",
+                                );
+                            }
+                        }
+                    } else {
+                        div(class = "field") {
+                            label(class = "label") { + "API token"; }
+                            div(class = "control") {
+                                input(
+                                    ref = token_node.clone(),
+                                    class = "input",
+                                    type = "password",
+                                    value = default_token,
+                                );
+                            }
+                        }
+                    }
+
+                    div(class = "field") {
+                        div(class = "control") {
+                            button(class = "button is-primary", type = "submit") {
+                                + "Log in";
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        SwitchServer(i18n = None, host = props.host.clone(), callback = props.set_user_host.clone());
+    }
+}
+
+#[derive(PartialEq, Properties)]
+pub struct Props {
+    pub host:          RcStr,
+    /// The previously stored credentials, used to prefill the form.
+    pub credentials:   Option<api::Credentials>,
+    /// The error that caused the login screen to be shown.
+    pub err:           Option<api::ApiError>,
+    pub on_login:      Callback<api::Credentials>,
+    pub set_user_host: Callback<RcStr>,
+}